@@ -1,319 +1,11497 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::alt_bn128::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::{blake3, keccak};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::mpl_token_metadata::types::DataV2;
+use anchor_spl::metadata::{self, CreateMasterEditionV3, CreateMetadataAccountsV3, Metadata};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use switchboard_on_demand::{RandomnessAccountData, SWITCHBOARD_ON_DEMAND_PROGRAM_ID};
+use std::cell::RefMut;
 
 declare_id!("DRJk4gJFdYCCHNYY5qFZfrM9ysNrMz3kXJN5JVZdz8Jm");
 
+// Slots a player may take to act before the opponent can claim victory (~10 minutes),
+// unless the game is played by correspondence
+pub const TURN_TIMEOUT_SLOTS: u64 = 1_500;
+// Slots after the game ends before it can be closed without both reveals (~1 day)
+pub const REVEAL_DEADLINE_SLOTS: u64 = 216_000;
+// Slots a game must sit without a move before its arbiter may rule on it (~12 hours)
+pub const ARBITER_DISPUTE_WINDOW_SLOTS: u64 = 108_000;
+// Largest supported board is 12x12 (or two stacked 10x10 layers); smaller boards use
+// the front of each array
+pub const MAX_CELLS: usize = 200;
+// Hit boards pack four 2-bit cells per byte (0=empty, 1=miss, 2=hit, 3=mine)
+pub const PACKED_CELLS: usize = MAX_CELLS / 4;
+// Cell masks hold one bit per cell, cell i at bit i % 64 of word i / 64
+pub const MASK_WORDS: usize = MAX_CELLS.div_ceil(64);
+pub type CellMask = [u64; MASK_WORDS];
+// Every cell of both boards can be shot once, so the move log never overflows
+pub const MAX_MOVES: usize = 2 * MAX_CELLS;
+// Bytes enable_move_history grows a game account by to hold its move log
+pub const MOVE_HISTORY_LEN: usize = MAX_MOVES * std::mem::size_of::<MoveRecord>();
+// SPL account compression and its noop log wrapper, which archive closed games
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+// Optional mechanics, switched on per game as bits of GameRules.variants
+pub const VARIANT_RADAR: u32 = 1 << 0;
+pub const VARIANT_AREABOMB: u32 = 1 << 1;
+pub const VARIANT_MINES: u32 = 1 << 2;
+pub const VARIANT_MOVING_SHIPS: u32 = 1 << 3;
+pub const VARIANT_LIMITED_AMMO: u32 = 1 << 4;
+// Board commitments hash with SHA-256 unless one of these picks another primitive
+pub const VARIANT_KECCAK_COMMITMENTS: u32 = 1 << 5;
+pub const VARIANT_BLAKE3_COMMITMENTS: u32 = 1 << 6;
+// Each player proves in zero knowledge that their committed board holds a legal fleet
+// before the first shot (Groth16 over alt_bn128, checked against a ZkVerifier key)
+pub const VARIANT_ZK_FLEET: u32 = 1 << 7;
+// Every shot is answered with a zero-knowledge proof, so with the fleet already proven
+// there is no end-of-game reveal (or honesty bond); requires VARIANT_ZK_FLEET
+pub const VARIANT_ZK_SHOTS: u32 = 1 << 8;
+// Lets anyone, not just the two players, post chat messages to the game
+pub const VARIANT_SPECTATOR_CHAT: u32 = 1 << 9;
+// No two ships may sit on neighbouring cells, diagonals included (checked at the reveal)
+pub const VARIANT_NO_TOUCH: u32 = 1 << 10;
+// Two bits of variants give the layers stacked under the surface (0-2). Layers are laid out
+// one after another, so row y of layer z is row z * board_size + y of the whole board
+pub const VARIANT_LAYERS_SHIFT: u32 = 11;
+pub const VARIANT_LAYERS_MASK: u32 = 0b11 << VARIANT_LAYERS_SHIFT;
+pub const MAX_LAYERS: usize = 3;
+// Hex grid in axial coordinates: x is q and y is r over a size x size rhombus, so each cell
+// also borders (x + 1, y - 1) and (x - 1, y + 1), and ships may lie along that third axis
+pub const VARIANT_HEX: u32 = 1 << 13;
+// Correspondence play: 1-7 days per move instead of TURN_TIMEOUT_SLOTS, with a MoveAwaited
+// event each time the game hands over to a player (0 = a live game)
+pub const VARIANT_CORRESPONDENCE_SHIFT: u32 = 14;
+pub const VARIANT_CORRESPONDENCE_MASK: u32 = 0b111 << VARIANT_CORRESPONDENCE_SHIFT;
+pub const SLOTS_PER_DAY: u64 = 216_000;
+// Circuits with a ZkVerifier key, and the public inputs each takes
+pub const ZK_CIRCUIT_FLEET: u8 = 0; // Board commitment (2 halves)
+pub const ZK_CIRCUIT_SHOT: u8 = 1; // Board commitment (2 halves), cell index, cell value
+pub const MAX_ZK_INPUTS: usize = 4;
+// With limited ammo, each player gets shots for this share of the board (60 on 10x10)
+pub const AMMO_PERCENT_OF_CELLS: usize = 60;
+// Committed cell value of a sea mine, and how many a board may hide (VARIANT_MINES)
+pub const MINE_CELL: u8 = u8::MAX;
+pub const MAX_MINES: usize = 2;
+// Standard fleet: carrier, battleship, cruiser, submarine, destroyer (17 squares)
+pub const STANDARD_FLEET: [u8; 5] = [5, 4, 3, 3, 2];
+// Honesty bond each player posts, returned on a correct board reveal (0.01 SOL;
+// kept above the vault's rent-exempt minimum so partial payouts never strand it)
+pub const HONESTY_BOND_LAMPORTS: u64 = 10_000_000;
+// Bounty a keeper earns for cranking a stuck game forward (0.001 SOL); timeouts and
+// reveal forfeits take it out of the defaulting player's honesty bond
+pub const KEEPER_BOUNTY_LAMPORTS: u64 = 1_000_000;
+// Longest a session key can stay authorized to move for its wallet (~1 day)
+pub const MAX_SESSION_SLOTS: u64 = 216_000;
+// Action tags in the message a player signs for a relayed move
+pub const RELAYED_FIRE: u8 = 1;
+pub const RELAYED_REVEAL: u8 = 2;
+// Most ships a custom fleet may have
+pub const MAX_SHIPS: usize = 8;
+// Rating every new profile starts from, and the ELO K-factor applied per game
+pub const INITIAL_RATING: u32 = 1_200;
+pub const ELO_K_FACTOR: f64 = 32.0;
+// Achievements, as bits of PlayerProfile.achievements
+pub const ACHIEVEMENT_FIRST_WIN: u32 = 1 << 0;
+pub const ACHIEVEMENT_FLAWLESS_VICTORY: u32 = 1 << 1; // Won without losing a ship
+pub const ACHIEVEMENT_WIN_STREAK: u32 = 1 << 2; // WIN_STREAK_ACHIEVEMENT wins in a row
+pub const ACHIEVEMENT_CARRIER_FINALE: u32 = 1 << 3; // The winning shot sank the largest ship
+pub const WIN_STREAK_ACHIEVEMENT: u32 = 10;
+// A StreakMilestone event fires at every multiple of this many consecutive wins
+pub const STREAK_MILESTONE: u32 = 5;
+// Reputation lost per game timed out or forfeited by not revealing; each finished game and
+// each honest reveal earns one point back
+pub const REPUTATION_TIMEOUT_PENALTY: u32 = 5;
+// Open games the lobby registry can list at once
+pub const MAX_LOBBY_ENTRIES: usize = 50;
+// Top players the leaderboard keeps
+pub const MAX_LEADERBOARD_ENTRIES: usize = 20;
+// Offenders the cheater registry tracks; when full, the lightest record makes way
+pub const MAX_CHEATER_ENTRIES: usize = 100;
+// Fewest shots that can sink the standard fleet, and the most a 10x10 board allows
+pub const MIN_PUZZLE_PAR: u16 = 17;
+pub const MAX_PUZZLE_PAR: u16 = 100;
+// Players the daily challenge leaderboard keeps, fewest shots first
+pub const MAX_CHALLENGE_LEADERS: usize = 20;
+pub const SECONDS_PER_DAY: i64 = 86_400;
+// Largest share of the pot the admin can hand back to a player who abandons mid-game (50%)
+pub const MAX_ABANDON_REFUND_BPS: u16 = 5_000;
+// Mints (lamports included) the config can hold wager limits for
+pub const MAX_WAGER_LIMITS: usize = 8;
+// Display name bounds; names are lowercase ASCII letters, digits and underscores
+pub const MIN_NAME_LEN: usize = 3;
+pub const MAX_NAME_LEN: usize = 20;
+// Most profiles one clan can hold
+pub const MAX_CLAN_MEMBERS: u32 = 50;
+// Members each clan can field in one war
+pub const MAX_WAR_ROSTER: usize = 10;
+// Emote ids the frontend has art for
+pub const NUM_EMOTES: u8 = 16;
+// Longest chat message, in bytes
+pub const MAX_MESSAGE_LEN: usize = 140;
+// Streamers/casters a game can hand both boards to
+pub const MAX_OBSERVERS: usize = 4;
+// Highest rake the admin can set on wager payouts (10%)
+pub const MAX_FEE_BPS: u16 = 1_000;
+// Share of the rake a referred player generates that goes to their referrer (20%)
+pub const REFERRAL_SHARE_BPS: u64 = 2_000;
+// Largest single-elimination bracket a tournament can run
+pub const MAX_TOURNAMENT_PLAYERS: usize = 16;
+// Trophy NFT metadata; the URI gets the game, final score and shot count appended
+pub const TROPHY_NAME: &str = "Battleship Trophy";
+pub const TROPHY_SYMBOL: &str = "BSHIP";
+pub const TROPHY_BASE_URI: &str = "https://battleships.gorbagana.wtf/trophy";
+
 #[program]
 pub mod battleship {
     use super::*;
 
-    pub fn initialize_game(ctx: Context<InitializeGame>, board_commitment: [u8; 32]) -> Result<()> {
-        let game = &mut ctx.accounts.game;
-        
-        game.player1 = ctx.accounts.player.key();
-        game.player2 = Pubkey::default(); // Will be set when second player joins
-        game.board_commit1 = board_commitment;
-        game.board_commit2 = [0; 32]; // Will be set when player2 joins
-        game.turn = 1; // Player1 starts
-        game.board_hits1 = [0; 100]; // 10x10 grid for hits on player1's board
-        game.board_hits2 = [0; 100]; // 10x10 grid for hits on player2's board
-        game.hits_count1 = 0; // How many hits player1's fleet has taken
-        game.hits_count2 = 0; // How many hits player2's fleet has taken
-        game.is_initialized = false; // Game ready when both players joined
-        game.is_game_over = false;
-        game.winner = 0; // 0 = none, 1 = player1, 2 = player2
-        game.pending_shot = None;
-        game.pending_shot_by = Pubkey::default();
-        game.player1_revealed = false;
-        game.player2_revealed = false;
-        game.bump = ctx.bumps.game;
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_game(
+        ctx: Context<InitializeGame>,
+        game_id: u64,
+        board_commitment: [u8; 32],
+        wager: u64,
+        invited_opponent: Pubkey,
+        rules: GameRules,
+        fleet: Vec<u8>,
+        turn_commitment: [u8; 32],
+        ranked: bool,
+        arbiter: Pubkey
+    ) -> Result<()> {
+        let player1 = ctx.accounts.player.key();
+        initialize_lamport_game(
+            &ctx.accounts.game,
+            &mut ctx.accounts.board1,
+            &ctx.accounts.vault,
+            &mut ctx.accounts.player_profile,
+            &ctx.accounts.referrer_profile,
+            &ctx.accounts.config,
+            &ctx.accounts.player,
+            &ctx.accounts.system_program,
+            player1,
+            [ctx.bumps.game, ctx.bumps.board1, ctx.bumps.vault],
+            game_id,
+            board_commitment,
+            wager,
+            invited_opponent,
+            rules,
+            &fleet,
+            turn_commitment,
+            ranked,
+            arbiter,
+        )?;
+        
+        msg!(
+            "⚓ New Battleship game #{} initialized by player: {} (wager: {} lamports)",
+            game_id,
+            player1,
+            wager
+        );
+        Ok(())
+    }
+
+    // Same as initialize_game, for another program to call: player1 is the authority, which can be
+    // a PDA the calling program signs for, while the payer funds the stake, bond and rent
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_game_cpi(
+        ctx: Context<InitializeGameCpi>,
+        game_id: u64,
+        board_commitment: [u8; 32],
+        wager: u64,
+        invited_opponent: Pubkey,
+        rules: GameRules,
+        fleet: Vec<u8>,
+        turn_commitment: [u8; 32],
+        ranked: bool,
+        arbiter: Pubkey
+    ) -> Result<()> {
+        let player1 = ctx.accounts.authority.key();
+        initialize_lamport_game(
+            &ctx.accounts.game,
+            &mut ctx.accounts.board1,
+            &ctx.accounts.vault,
+            &mut ctx.accounts.player_profile,
+            &ctx.accounts.referrer_profile,
+            &ctx.accounts.config,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            player1,
+            [ctx.bumps.game, ctx.bumps.board1, ctx.bumps.vault],
+            game_id,
+            board_commitment,
+            wager,
+            invited_opponent,
+            rules,
+            &fleet,
+            turn_commitment,
+            ranked,
+            arbiter,
+        )?;
+        
+        msg!(
+            "⚓ New Battleship game #{} initialized for authority: {} (wager: {} lamports)",
+            game_id,
+            player1,
+            wager
+        );
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_game_spl(
+        ctx: Context<InitializeGameSpl>,
+        game_id: u64,
+        board_commitment: [u8; 32],
+        wager: u64,
+        invited_opponent: Pubkey,
+        rules: GameRules,
+        fleet: Vec<u8>,
+        turn_commitment: [u8; 32],
+        ranked: bool,
+        arbiter: Pubkey
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        require!(wager > 0, ErrorCode::NoWager);
+        check_wager_limits(&ctx.accounts.config, ctx.accounts.mint.key(), wager)?;
+        // A game stuck waiting on fleet proofs is unwound by cancel_match, which refunds lamports
+        require!(rules.variants & (VARIANT_ZK_FLEET | VARIANT_ZK_SHOTS) == 0, ErrorCode::ZkGameNeedsLamports);
         
-        msg!("⚓ New Battleship game initialized by player: {}", game.player1);
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.player_token_account.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.player.to_account_info(),
+                },
+            ),
+            wager,
+        )?;
+        
+        post_bond(&ctx.accounts.system_program, &ctx.accounts.player, &ctx.accounts.vault, HONESTY_BOND_LAMPORTS)?;
+        
+        let game = &mut ctx.accounts.game.load_init()?;
+        init_player1_game(
+            game,
+            &ctx.accounts.config,
+            ctx.accounts.player.key(),
+            game_id,
+            ctx.bumps.game,
+            ctx.bumps.vault,
+            wager,
+            invited_opponent,
+            rules,
+            &fleet,
+            turn_commitment,
+            ranked,
+            arbiter,
+        )?;
+        open_board(&mut ctx.accounts.board1, ctx.accounts.game.key(), 1, board_commitment, ctx.bumps.board1);
+        game.wager_mint = ctx.accounts.mint.key(); // Wager is in this mint's base units
+        game.referrer1 = referrer_of(&ctx.accounts.player_profile);
+        
+        msg!(
+            "⚓ New Battleship game #{} initialized by player: {} (wager: {} of mint {})",
+            game_id,
+            game.player1,
+            wager,
+            game.wager_mint
+        );
+        Ok(())
+    }
+
+    pub fn join_game(ctx: Context<JoinGame>, board_commitment: [u8; 32], turn_nonce: [u8; 32]) -> Result<()> {
+        let player2 = ctx.accounts.player.key();
+        join_lamport_game(
+            &ctx.accounts.game,
+            &mut ctx.accounts.board2,
+            &ctx.accounts.vault,
+            &ctx.accounts.player1_profile,
+            &mut ctx.accounts.player_profile,
+            &ctx.accounts.referrer_profile,
+            &ctx.accounts.config,
+            &mut ctx.accounts.lobby,
+            &ctx.accounts.player,
+            &ctx.accounts.system_program,
+            player2,
+            ctx.bumps.board2,
+            board_commitment,
+            turn_nonce,
+        )?;
+        
+        msg!("🚢 Player {} joined the game! Game is now active.", player2);
         Ok(())
     }
 
-    pub fn join_game(ctx: Context<JoinGame>, board_commitment: [u8; 32]) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+    // Same as join_game, for another program to call: player2 is the authority, which can be a
+    // PDA the calling program signs for, while the payer funds the stake and bond
+    pub fn join_game_cpi(ctx: Context<JoinGameCpi>, board_commitment: [u8; 32], turn_nonce: [u8; 32]) -> Result<()> {
+        let player2 = ctx.accounts.authority.key();
+        join_lamport_game(
+            &ctx.accounts.game,
+            &mut ctx.accounts.board2,
+            &ctx.accounts.vault,
+            &ctx.accounts.player1_profile,
+            &mut ctx.accounts.player_profile,
+            &ctx.accounts.referrer_profile,
+            &ctx.accounts.config,
+            &mut ctx.accounts.lobby,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            player2,
+            ctx.bumps.board2,
+            board_commitment,
+            turn_nonce,
+        )?;
+        
+        msg!("🚢 Authority {} joined the game! Game is now active.", player2);
+        Ok(())
+    }
+
+    pub fn join_game_spl(
+        ctx: Context<JoinGameSpl>,
+        board_commitment: [u8; 32],
+        turn_nonce: [u8; 32]
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        check_open_seat(game, ctx.accounts.player.key())?;
+        check_ranked_join(game, &ctx.accounts.config, &ctx.accounts.player1_profile, &ctx.accounts.player_profile)?;
+        check_min_reputation(game, &ctx.accounts.player_profile)?;
+        
+        // Player2 must match player1's stake in the same mint
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.player_token_account.to_account_info(),
+                    to: ctx.accounts.vault_token_account.to_account_info(),
+                    authority: ctx.accounts.player.to_account_info(),
+                },
+            ),
+            game.wager,
+        )?;
         
-        require!(!game.is_initialized, ErrorCode::GameAlreadyFull);
-        require!(game.player1 != ctx.accounts.player.key(), ErrorCode::CannotPlayAgainstYourself);
+        post_bond(&ctx.accounts.system_program, &ctx.accounts.player, &ctx.accounts.vault, game.bond)?;
         
-        game.player2 = ctx.accounts.player.key();
-        game.board_commit2 = board_commitment;
-        game.is_initialized = true;
+        seat_player2(game, ctx.accounts.player.key(), turn_nonce)?;
+        open_board(&mut ctx.accounts.board2, ctx.accounts.game.key(), 2, board_commitment, ctx.bumps.board2);
+        game.referrer2 = referrer_of(&ctx.accounts.player_profile);
+        
+        // Public games leave the lobby as soon as they fill up
+        if game.is_public != 0 {
+            let lobby = ctx.accounts.lobby.as_mut().ok_or(ErrorCode::LobbyRequired)?;
+            remove_lobby_entry(lobby, ctx.accounts.game.key());
+        }
         
         msg!("🚢 Player {} joined the game! Game is now active.", game.player2);
         Ok(())
     }
 
-    pub fn fire_shot(ctx: Context<FireShot>, x: u8, y: u8) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_game_nft(
+        ctx: Context<InitializeGameNft>,
+        game_id: u64,
+        board_commitment: [u8; 32],
+        invited_opponent: Pubkey,
+        rules: GameRules,
+        fleet: Vec<u8>,
+        turn_commitment: [u8; 32],
+        ranked: bool,
+        arbiter: Pubkey
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        // The escrowed NFTs can't be re-staked in place, so there are no series or rematches
+        require!(rules.best_of == 1, ErrorCode::NftGameSingleRound);
+        // A game stuck waiting on fleet proofs is unwound by cancel_match, which refunds lamports
+        require!(rules.variants & (VARIANT_ZK_FLEET | VARIANT_ZK_SHOTS) == 0, ErrorCode::ZkGameNeedsLamports);
         
-        require!(game.is_initialized, ErrorCode::GameNotReady);
-        require!(!game.is_game_over, ErrorCode::GameOver);
-        require!(x < 10 && y < 10, ErrorCode::InvalidCoordinate);
-        require!(game.pending_shot.is_none(), ErrorCode::ShotPending);
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.player_nft_account.to_account_info(),
+                    to: ctx.accounts.vault_nft_account.to_account_info(),
+                    authority: ctx.accounts.player.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        
+        post_bond(&ctx.accounts.system_program, &ctx.accounts.player, &ctx.accounts.vault, HONESTY_BOND_LAMPORTS)?;
+        
+        let game = &mut ctx.accounts.game.load_init()?;
+        init_player1_game(
+            game,
+            &ctx.accounts.config,
+            ctx.accounts.player.key(),
+            game_id,
+            ctx.bumps.game,
+            ctx.bumps.vault,
+            0,
+            invited_opponent,
+            rules,
+            &fleet,
+            turn_commitment,
+            ranked,
+            arbiter,
+        )?;
+        open_board(&mut ctx.accounts.board1, ctx.accounts.game.key(), 1, board_commitment, ctx.bumps.board1);
+        game.nft_mint1 = ctx.accounts.nft_mint.key(); // The stake is this NFT, not a wager
+        
+        msg!(
+            "⚓ New Battleship game #{} initialized by player: {} (stake: NFT {})",
+            game_id,
+            game.player1,
+            game.nft_mint1
+        );
+        Ok(())
+    }
+
+    pub fn join_game_nft(
+        ctx: Context<JoinGameNft>,
+        board_commitment: [u8; 32],
+        turn_nonce: [u8; 32]
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.nft_mint1 != Pubkey::default(), ErrorCode::WrongWagerType);
+        check_open_seat(game, ctx.accounts.player.key())?;
+        check_ranked_join(game, &ctx.accounts.config, &ctx.accounts.player1_profile, &ctx.accounts.player_profile)?;
+        check_min_reputation(game, &ctx.accounts.player_profile)?;
+        
+        // Player2 puts up an NFT of their own against player1's
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.player_nft_account.to_account_info(),
+                    to: ctx.accounts.vault_nft_account.to_account_info(),
+                    authority: ctx.accounts.player.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        
+        post_bond(&ctx.accounts.system_program, &ctx.accounts.player, &ctx.accounts.vault, game.bond)?;
+        
+        seat_player2(game, ctx.accounts.player.key(), turn_nonce)?;
+        open_board(&mut ctx.accounts.board2, ctx.accounts.game.key(), 2, board_commitment, ctx.bumps.board2);
+        game.nft_mint2 = ctx.accounts.nft_mint.key();
+        
+        // Public games leave the lobby as soon as they fill up
+        if game.is_public != 0 {
+            let lobby = ctx.accounts.lobby.as_mut().ok_or(ErrorCode::LobbyRequired)?;
+            remove_lobby_entry(lobby, ctx.accounts.game.key());
+        }
+        
+        msg!("🚢 Player {} joined the game with NFT {}! Game is now active.", game.player2, game.nft_mint2);
+        Ok(())
+    }
+
+    pub fn reveal_turn_nonce(ctx: Context<RevealTurnNonce>, nonce: [u8; 32]) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(ctx.accounts.player.key() == game.player1, ErrorCode::NotPlayer1);
+        require_in_progress(game)?;
+        require!(game.turn_pending != 0, ErrorCode::TurnAlreadyDecided);
+        require!(
+            hashv(&[&nonce]).to_bytes() == game.turn_commitment,
+            ErrorCode::CommitmentMismatch
+        );
+        
+        // Neither nonce alone decides the flip: player2 chose theirs without seeing player1's
+        let flip = hashv(&[&nonce, &game.turn_nonce]).to_bytes()[0] & 1;
+        game.turn = flip + 1;
+        game.first_turn = game.turn;
+        game.turn_pending = 0;
+        game.last_action_slot = Clock::get()?.slot;
+        
+        msg!("🪙 Coin flip decided - player{} moves first", game.turn);
+        Ok(())
+    }
+
+    pub fn request_randomness(ctx: Context<RequestRandomness>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        let player = ctx.accounts.player.key();
+        require!(player == game.player1 || player == game.player2, ErrorCode::NotAPlayer);
+        require_in_progress(game)?;
+        require!(game.randomness_account == Pubkey::default(), ErrorCode::RandomnessAlreadyRequested);
+        
+        // Only randomness committed in the previous slot is still unknown to everyone
+        let randomness_data = RandomnessAccountData::parse(ctx.accounts.randomness_account.data.borrow())
+            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+        require!(
+            randomness_data.seed_slot == Clock::get()?.slot.saturating_sub(1),
+            ErrorCode::StaleRandomness
+        );
+        
+        game.randomness_account = ctx.accounts.randomness_account.key();
+        
+        msg!("🎲 Player {} requested randomness from {}", player, game.randomness_account);
+        Ok(())
+    }
+
+    pub fn consume_randomness(ctx: Context<ConsumeRandomness>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        let player = ctx.accounts.player.key();
+        require!(player == game.player1 || player == game.player2, ErrorCode::NotAPlayer);
+        require_in_progress(game)?;
+        
+        let randomness_data = RandomnessAccountData::parse(ctx.accounts.randomness_account.data.borrow())
+            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+        let value = randomness_data
+            .get_value(&Clock::get()?)
+            .map_err(|_| ErrorCode::RandomnessNotRevealed)?;
+        
+        game.randomness = value; // Kept for future power-up drops
+        game.randomness_account = Pubkey::default();
+        
+        if game.turn_pending != 0 && game.rules.vrf_first_turn != 0 {
+            game.turn = (value[0] & 1) + 1;
+            game.first_turn = game.turn;
+            game.turn_pending = 0;
+            game.last_action_slot = Clock::get()?.slot;
+            
+            msg!("🎲 VRF decided - player{} moves first", game.turn);
+        }
+        
+        Ok(())
+    }
+
+    pub fn fire_shot(ctx: Context<FireShot>, x: u8, y: u8) -> Result<ShotOutcome> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let player = acting_player(ctx.accounts.player.key(), &ctx.accounts.profile)?;
+        let game = &mut ctx.accounts.game.load_mut()?;
+        let boards = [&*ctx.accounts.board1, &*ctx.accounts.board2];
+        fire_at(game, boards, game_key, player, x, y, events)?;
+        
+        // The shooter keeps the turn until the defender reveals, so the result is still pending
+        let defender_is_player1 = game.turn == 2;
+        Ok(shot_outcome(game, boards, x, y, defender_is_player1))
+    }
+
+    pub fn reveal_shot_result(
+        ctx: Context<RevealShotResult>,
+        ship_id: u8,
+        cell_salt: [u8; 32],
+        proof: Vec<[u8; 32]>
+    ) -> Result<ShotOutcome> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let player = acting_player(ctx.accounts.player.key(), &ctx.accounts.profile)?;
+        let (game, history) = &mut load_game_with_history(&ctx.accounts.game)?;
+        
+        let boards = &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2];
+        
+        let (x, y) = game.pending_shot().ok_or(ErrorCode::NoPendingShot)?;
+        resolve_pending_shot(game, boards, game_key, player, ship_id, &cell_salt, CellProof::Merkle(&proof), history, events)?;
+        
+        let defender_is_player1 = player == game.player1;
+        Ok(shot_outcome(game, read_boards(boards), x, y, defender_is_player1))
+    }
+
+    pub fn reveal_and_fire(
+        ctx: Context<RevealAndFire>,
+        ship_id: u8,
+        cell_salt: [u8; 32],
+        proof: Vec<[u8; 32]>,
+        next_x: u8,
+        next_y: u8
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let player = acting_player(ctx.accounts.player.key(), &ctx.accounts.profile)?;
+        let (game, history) = &mut load_game_with_history(&ctx.accounts.game)?;
+        
+        let boards = &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2];
+        
+        // Resolving the shot hands the turn to the defender, who fires straight back
+        resolve_pending_shot(game, boards, game_key, player, ship_id, &cell_salt, CellProof::Merkle(&proof), history, events)?;
+        
+        // A shot that sank the defender's last ship (or exposed a cheat, or a late reveal that
+        // struck the defender out) ends the game
+        if game.is_over() {
+            msg!("🏁 Game ended on the revealed shot; counter-fire at ({}, {}) skipped", next_x, next_y);
+            return Ok(());
+        }
+        
+        fire_at(game, read_boards(boards), game_key, player, next_x, next_y, events)
+    }
+
+    // Read-only; simulate it to get the game's headline numbers without decoding the account
+    pub fn get_game_summary(ctx: Context<GetGameSummary>) -> Result<GameSummary> {
+        let game = &ctx.accounts.game.load()?;
+        
+        let last_move = game.last_move;
+        let deadline_slot = match game.state() {
+            GameState::InProgress => game.last_action_slot.saturating_add(game.rules.turn_timeout_slots()),
+            GameState::AwaitingReveal => game.last_action_slot.saturating_add(REVEAL_DEADLINE_SLOTS),
+            _ => 0,
+        };
+        
+        Ok(GameSummary {
+            state: game.state,
+            turn: game.turn,
+            player_to_move: player_to_move(game),
+            winner: game.winner,
+            end_reason: game.end_reason,
+            hits_count1: game.hits_count1,
+            hits_count2: game.hits_count2,
+            move_count: game.move_count,
+            last_shot_x: last_move.x,
+            last_shot_y: last_move.y,
+            last_shot_by: last_move.shooter,
+            last_shot_result: last_move.result,
+            last_action_slot: game.last_action_slot,
+            deadline_slot,
+            clock1: game.clock1,
+            clock2: game.clock2,
+        })
+    }
+
+    pub fn relayed_fire_shot(ctx: Context<RelayedMove>, x: u8, y: u8, nonce: u64) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        // The shooter signed the move off-chain; the relayer only pays fees
+        let shooter_num = game.turn;
+        let (shooter, board) = if shooter_num == 1 {
+            (game.player1, &ctx.accounts.board1)
+        } else {
+            (game.player2, &ctx.accounts.board2)
+        };
+        let message = relayed_move_message(game_key, board.commitment, RELAYED_FIRE, &[x, y], nonce);
+        verify_ed25519_signature(&ctx.accounts.instructions, &shooter, &message)?;
+        consume_relay_nonce(game, shooter_num, nonce)?;
+        
+        fire_at(game, [&*ctx.accounts.board1, &*ctx.accounts.board2], game_key, shooter, x, y, events)
+    }
+
+    pub fn relayed_reveal_shot_result(
+        ctx: Context<RelayedMove>,
+        ship_id: u8,
+        cell_salt: [u8; 32],
+        proof: Vec<[u8; 32]>,
+        nonce: u64
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let (game, history) = &mut load_game_with_history(&ctx.accounts.game)?;
+        
+        // The Merkle proof already pins the result, so the defender only signs off on
+        // resolving this particular pending shot
+        let (x, y) = game.pending_shot().ok_or(ErrorCode::NoPendingShot)?;
+        let defender_num = player_to_move(game);
+        let (defender, board) = if defender_num == 1 {
+            (game.player1, &ctx.accounts.board1)
+        } else {
+            (game.player2, &ctx.accounts.board2)
+        };
+        let message = relayed_move_message(game_key, board.commitment, RELAYED_REVEAL, &[x, y], nonce);
+        verify_ed25519_signature(&ctx.accounts.instructions, &defender, &message)?;
+        consume_relay_nonce(game, defender_num, nonce)?;
+        
+        resolve_pending_shot(game, &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2], game_key, defender, ship_id, &cell_salt, CellProof::Merkle(&proof), history, events)
+    }
+
+    pub fn fire_salvo(ctx: Context<FireSalvo>, coords: Vec<(u8, u8)>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.rules.salvo != 0, ErrorCode::NotSalvoGame);
+        require_in_progress(game)?;
+        require!(game.turn_pending == 0, ErrorCode::TurnNotDecided);
+        require!(game.pending_salvo().is_empty(), ErrorCode::ShotPending);
         
         let current_player = ctx.accounts.player.key();
         let is_player1 = current_player == game.player1;
         let is_player2 = current_player == game.player2;
         
         require!(is_player1 || is_player2, ErrorCode::NotAPlayer);
-        
-        // Check if it's the player's turn
         require!(
             (game.turn == 1 && is_player1) || (game.turn == 2 && is_player2),
             ErrorCode::NotYourTurn
         );
         
-        let coordinate_index = (x + 10 * y) as usize;
+        // Up to one shot for each ship the shooter still has afloat
+        let own_ship_hits = if is_player1 { &game.ship_hits1 } else { &game.ship_hits2 };
+        let ships_afloat = game
+            .fleet_of(is_player1)
+            .iter()
+            .zip(own_ship_hits.iter())
+            .filter(|(&len, &hits)| hits < len)
+            .count();
+        require!(!coords.is_empty() && coords.len() <= ships_afloat, ErrorCode::InvalidSalvoSize);
         
-        // Check the opponent's board to ensure this coordinate hasn't been shot before
-        let opponent_board = if is_player1 {
-            &game.board_hits2
-        } else {
-            &game.board_hits1
-        };
+        let opponent_board = if is_player1 { &ctx.accounts.board2.hits } else { &ctx.accounts.board1.hits };
+        
+        for (i, &(x, y)) in coords.iter().enumerate() {
+            require!(
+                x < game.rules.board_size && y < game.rules.rows(),
+                ErrorCode::InvalidCoordinate
+            );
+            require!(hit_cell(opponent_board, cell_index(game, x, y)) == 0, ErrorCode::AlreadyShotHere);
+            require!(!coords[..i].contains(&(x, y)), ErrorCode::AlreadyShotHere);
+        }
+        require!(coords.len() <= ammo_left(game, game.turn), ErrorCode::OutOfAmmo);
+        
+        if record_strike(game)? {
+            return Ok(());
+        }
+        charge_clock(game)?;
+        record_shots_fired(game, coords.len());
         
-        require!(opponent_board[coordinate_index] == 0, ErrorCode::AlreadyShotHere);
+        msg!("💥 Player {} fired a salvo of {} shots", current_player, coords.len());
         
-        // Set pending shot
-        game.pending_shot = Some((x, y));
+        game.set_pending_salvo(&coords);
         game.pending_shot_by = current_player;
+        game.draw_offered_by = 0; // Firing declines any standing draw offer or abort proposal
+        game.abort_proposed_by = 0;
+        game.last_action_slot = Clock::get()?.slot;
         
-        msg!("💥 Player {} fired at coordinate ({}, {})", current_player, x, y);
-        Ok(())
+        notify_player_to_move(game, game_key, events)
+    }
+
+    pub fn reveal_salvo_results(ctx: Context<RevealSalvoResults>, results: Vec<ShotReveal>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let (game, history) = &mut load_game_with_history(&ctx.accounts.game)?;
+        let boards = &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2];
+        
+        resolve_pending_salvo(game, boards, game_key, ctx.accounts.player.key(), &results, false, history, events)
+    }
+
+    // Catch-up for the defender: resolves whatever is queued - the pending shot, or any part
+    // of a pending salvo - checking each result against its own queue entry, and announces
+    // every shot it resolves with a ShotResolved event
+    pub fn resolve_shots_batch(ctx: Context<RevealSalvoResults>, results: Vec<ShotReveal>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let player = ctx.accounts.player.key();
+        let (game, history) = &mut load_game_with_history(&ctx.accounts.game)?;
+        let boards = &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2];
+        
+        if let Some((x, y)) = game.pending_shot() {
+            let [result] = results.as_slice() else {
+                return err!(ErrorCode::InvalidBatchSize);
+            };
+            require!(result.x == x && result.y == y, ErrorCode::NoPendingShot);
+            let (shooter, moves) = (game.pending_shot_by, game.move_count);
+            let proof = CellProof::Merkle(&result.proof);
+            resolve_pending_shot(game, boards, game_key, player, result.ship_id, &result.cell_salt, proof, history, events)?;
+            // A strike-out ends the game without resolving the shot
+            if game.move_count > moves {
+                emit_shot_resolved(game, game_key, shooter, events)?;
+            }
+            Ok(())
+        } else {
+            resolve_pending_salvo(game, boards, game_key, player, &results, true, history, events)
+        }
     }
 
-    pub fn reveal_shot_result(ctx: Context<RevealShotResult>, was_hit: bool) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+    pub fn radar_scan(ctx: Context<RadarScan>, x: u8, y: u8) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
         
-        require!(game.is_initialized, ErrorCode::GameNotReady);
-        require!(!game.is_game_over, ErrorCode::GameOver);
-        require!(game.pending_shot.is_some(), ErrorCode::NoPendingShot);
+        require!(game.rules.variants & VARIANT_RADAR != 0, ErrorCode::RadarDisabled);
+        require!(game.rules.salvo == 0, ErrorCode::SalvoGame);
+        require_in_progress(game)?;
+        require!(game.turn_pending == 0, ErrorCode::TurnNotDecided);
+        require!(
+            x < game.rules.board_size && y < game.rules.board_size,
+            ErrorCode::InvalidCoordinate
+        );
+        require!(game.pending_shot().is_none() && game.pending_salvo().is_empty(), ErrorCode::ShotPending);
         
         let current_player = ctx.accounts.player.key();
         let is_player1 = current_player == game.player1;
-        let is_player2 = current_player == game.player2;
+        require!(is_player1 || current_player == game.player2, ErrorCode::NotAPlayer);
+        require!(game.turn == if is_player1 { 1 } else { 2 }, ErrorCode::NotYourTurn);
         
-        require!(is_player1 || is_player2, ErrorCode::NotAPlayer);
+        let radar_state = if is_player1 { game.radar_state1 } else { game.radar_state2 };
+        require!(radar_state == 0, ErrorCode::RadarAlreadyUsed);
+        
+        if record_strike(game)? {
+            return Ok(());
+        }
+        charge_clock(game)?;
+        
+        // The scan takes the place of a shot; the defender answers with the ship count
+        if is_player1 {
+            game.radar_scan1 = [x, y, 0];
+            game.radar_state1 = 1;
+        } else {
+            game.radar_scan2 = [x, y, 0];
+            game.radar_state2 = 1;
+        }
+        game.draw_offered_by = 0; // Scanning declines any standing draw offer or abort proposal
+        game.abort_proposed_by = 0;
+        game.last_action_slot = Clock::get()?.slot;
         
-        // Ensure this is the defending player (opposite of who fired)
-        let is_defender = if game.pending_shot_by == game.player1 {
-            is_player2
+        msg!("📡 Player {} scanned around ({}, {})", current_player, x, y);
+        notify_player_to_move(game, game_key, events)
+    }
+
+    pub fn reveal_radar_scan(ctx: Context<RevealRadarScan>, ship_cells: u8) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        
+        // The defender is whoever didn't start the scan
+        let current_player = ctx.accounts.player.key();
+        let scanner_is_player1 = if game.radar_state1 == 1 {
+            true
+        } else if game.radar_state2 == 1 {
+            false
         } else {
-            is_player1
+            return err!(ErrorCode::NoPendingScan);
         };
+        let defender = if scanner_is_player1 { game.player2 } else { game.player1 };
+        require!(current_player == defender, ErrorCode::NotDefender);
         
-        require!(is_defender, ErrorCode::NotDefender);
+        // The count is checked against the board once it's revealed, like shot results
+        require!(ship_cells <= 9, ErrorCode::InvalidScanResult);
         
-        let (x, y) = game.pending_shot.unwrap();
-        let coordinate_index = (x + 10 * y) as usize;
+        if record_strike(game)? {
+            return Ok(());
+        }
+        charge_clock(game)?;
         
-        // Update the defender's board
-        let (defender_board, defender_hits_count, attacker_player_num) = if is_player1 {
-            (&mut game.board_hits1, &mut game.hits_count1, 2)
+        let (scanner, [x, y, _]) = if scanner_is_player1 {
+            game.radar_scan1[2] = ship_cells;
+            game.radar_state1 = 2;
+            (game.player1, game.radar_scan1)
         } else {
-            (&mut game.board_hits2, &mut game.hits_count2, 1)
+            game.radar_scan2[2] = ship_cells;
+            game.radar_state2 = 2;
+            (game.player2, game.radar_scan2)
         };
+        emit_cpi!(RadarScanned {
+            game: game_key,
+            scanner,
+            x,
+            y,
+            ship_cells,
+        });
         
-        if was_hit {
-            defender_board[coordinate_index] = 2; // 2 = hit
-            *defender_hits_count += 1;
-            msg!("🎯 HIT! Player {} hit a ship!", game.pending_shot_by);
-            
-            // Check for win condition (17 is standard Battleship total ship squares)
-            if *defender_hits_count >= 17 {
-                game.is_game_over = true;
-                game.winner = attacker_player_num;
-                msg!("🏆 Player {} wins! All ships sunk!", game.pending_shot_by);
-            }
+        game.last_action_slot = Clock::get()?.slot;
+        pass_turn(game, [&*ctx.accounts.board1, &*ctx.accounts.board2], game_key, events)?;
+        
+        msg!("📡 Radar found {} ship cells for player {}", ship_cells, scanner);
+        Ok(())
+    }
+
+    pub fn move_ship(
+        ctx: Context<MoveShip>,
+        ship_id: u8,
+        vacated: CellOpening,
+        occupied: CellOpening,
+        new_commitment: [u8; 32]
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.rules.variants & VARIANT_MOVING_SHIPS != 0, ErrorCode::MovingShipsDisabled);
+        require_in_progress(game)?;
+        require!(game.turn_pending == 0, ErrorCode::TurnNotDecided);
+        require!(game.pending_shot().is_none() && game.pending_salvo().is_empty(), ErrorCode::ShotPending);
+        
+        let current_player = ctx.accounts.player.key();
+        let is_player1 = current_player == game.player1;
+        require!(is_player1 || current_player == game.player2, ErrorCode::NotAPlayer);
+        require!(game.turn == if is_player1 { 1 } else { 2 }, ErrorCode::NotYourTurn);
+        
+        require!(ship_id != 0 && (ship_id as usize) <= game.fleet_of(is_player1).len(), ErrorCode::InvalidShipId);
+        let ship = (ship_id - 1) as usize;
+        let own_ship_hits = if is_player1 { &game.ship_hits1 } else { &game.ship_hits2 };
+        require!(own_ship_hits[ship] == 0, ErrorCode::ShipDamaged);
+        
+        // A ship slides one cell along its axis: its tail empties and the cell past its head
+        // fills. The final board reveal checks the result is still a straight ship
+        let size = game.rules.board_size;
+        require!(
+            vacated.x < size && vacated.y < size && occupied.x < size && occupied.y < size,
+            ErrorCode::InvalidCoordinate
+        );
+        let length = game.fleet_of(is_player1)[ship];
+        let along_row = vacated.y == occupied.y && vacated.x.abs_diff(occupied.x) == length;
+        let along_column = vacated.x == occupied.x && vacated.y.abs_diff(occupied.y) == length;
+        require!(along_row || along_column, ErrorCode::InvalidShipMove);
+        
+        let vacated_index = cell_index(game, vacated.x, vacated.y);
+        let occupied_index = cell_index(game, occupied.x, occupied.y);
+        let own_board = if is_player1 { &mut ctx.accounts.board1 } else { &mut ctx.accounts.board2 };
+        require!(hit_cell(&own_board.hits, occupied_index) == 0, ErrorCode::AlreadyShotHere);
+        
+        // Swap one leaf at a time, proving each against the commitment left by the step before
+        let hash = game.rules.commit_hash();
+        let depth = merkle_depth(game.rules.cells());
+        let mut commitment = own_board.commitment;
+        for (opening, index, from, to) in [
+            (&vacated, vacated_index, ship_id, 0),
+            (&occupied, occupied_index, 0, ship_id),
+        ] {
+            let old_leaf = cell_leaf(hash, index, from, &opening.cell_salt);
+            let old_root = merkle_root_from_proof(hash, old_leaf, index, &opening.proof);
+            require!(
+                opening.proof.len() == depth
+                    && bound_commitment(hash, old_root, &game_key, &current_player) == commitment,
+                ErrorCode::InvalidCellProof
+            );
+            let new_leaf = cell_leaf(hash, index, to, &opening.cell_salt);
+            let new_root = merkle_root_from_proof(hash, new_leaf, index, &opening.proof);
+            commitment = bound_commitment(hash, new_root, &game_key, &current_player);
+        }
+        require!(commitment == new_commitment, ErrorCode::CommitmentMismatch);
+        
+        if record_strike(game)? {
+            return Ok(());
+        }
+        charge_clock(game)?;
+        
+        own_board.commitment = new_commitment;
+        game.draw_offered_by = 0; // Moving declines any standing draw offer or abort proposal
+        game.abort_proposed_by = 0;
+        game.last_action_slot = Clock::get()?.slot;
+        pass_turn(game, [&*ctx.accounts.board1, &*ctx.accounts.board2], game_key, events)?;
+        
+        msg!("🚢 Player {} moved a ship instead of firing", current_player);
+        Ok(())
+    }
+
+    pub fn fire_bomb(ctx: Context<FireBomb>, x: u8, y: u8) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.rules.variants & VARIANT_AREABOMB != 0, ErrorCode::AreabombDisabled);
+        require_in_progress(game)?;
+        require!(game.turn_pending == 0, ErrorCode::TurnNotDecided);
+        require!(game.pending_shot().is_none() && game.pending_salvo().is_empty(), ErrorCode::ShotPending);
+        require!(game.radar_state1 != 1 && game.radar_state2 != 1, ErrorCode::ScanPending);
+        
+        // (x, y) is the top-left corner of the 2x2 area, which must fit on one layer
+        require!(
+            x + 1 < game.rules.board_size && y + 1 < game.rules.rows() && y % game.rules.board_size + 1 < game.rules.board_size,
+            ErrorCode::InvalidCoordinate
+        );
+        
+        let current_player = ctx.accounts.player.key();
+        let is_player1 = current_player == game.player1;
+        require!(is_player1 || current_player == game.player2, ErrorCode::NotAPlayer);
+        require!(game.turn == if is_player1 { 1 } else { 2 }, ErrorCode::NotYourTurn);
+        
+        let bomb_used = if is_player1 { game.bomb_used1 } else { game.bomb_used2 };
+        require!(bomb_used == 0, ErrorCode::AreabombAlreadyUsed);
+        
+        // Cells already shot are skipped; the rest go out like a salvo, which the defender
+        // resolves together with reveal_salvo_results
+        let opponent_board = if is_player1 { &ctx.accounts.board2.hits } else { &ctx.accounts.board1.hits };
+        let coords: Vec<(u8, u8)> = [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+            .into_iter()
+            .filter(|&(cx, cy)| hit_cell(opponent_board, cell_index(game, cx, cy)) == 0)
+            .collect();
+        require!(!coords.is_empty(), ErrorCode::AlreadyShotHere);
+        require!(coords.len() <= ammo_left(game, game.turn), ErrorCode::OutOfAmmo);
+        
+        if record_strike(game)? {
+            return Ok(());
+        }
+        charge_clock(game)?;
+        record_shots_fired(game, coords.len());
+        
+        msg!("💣 Player {} dropped an areabomb on ({}, {})", current_player, x, y);
+        
+        game.set_pending_salvo(&coords);
+        game.pending_shot_by = current_player;
+        if is_player1 {
+            game.bomb_used1 = 1;
         } else {
-            defender_board[coordinate_index] = 1; // 1 = miss
-            msg!("💦 MISS! Player {} missed.", game.pending_shot_by);
+            game.bomb_used2 = 1;
         }
+        game.draw_offered_by = 0; // Firing declines any standing draw offer or abort proposal
+        game.abort_proposed_by = 0;
+        game.last_action_slot = Clock::get()?.slot;
         
-        // Clear pending shot and switch turns
-        game.pending_shot = None;
-        game.pending_shot_by = Pubkey::default();
+        notify_player_to_move(game, game_key, events)
+    }
+
+    pub fn fire_depth_charge(ctx: Context<FireDepthCharge>, x: u8, y: u8) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
         
-        if !game.is_game_over {
-            game.turn = if game.turn == 1 { 2 } else { 1 };
+        require!(game.rules.layers() > 1, ErrorCode::SingleLayerBoard);
+        require_in_progress(game)?;
+        require!(game.turn_pending == 0, ErrorCode::TurnNotDecided);
+        require!(game.pending_shot().is_none() && game.pending_salvo().is_empty(), ErrorCode::ShotPending);
+        
+        // (x, y) is a surface cell; the charge sinks through every layer beneath it
+        require!(
+            x < game.rules.board_size && y < game.rules.board_size,
+            ErrorCode::InvalidCoordinate
+        );
+        
+        let current_player = ctx.accounts.player.key();
+        let is_player1 = current_player == game.player1;
+        require!(is_player1 || current_player == game.player2, ErrorCode::NotAPlayer);
+        require!(game.turn == if is_player1 { 1 } else { 2 }, ErrorCode::NotYourTurn);
+        
+        let charge_used = if is_player1 { game.depth_charge_used1 } else { game.depth_charge_used2 };
+        require!(charge_used == 0, ErrorCode::DepthChargeAlreadyUsed);
+        
+        // Like an areabomb, the column's unshot cells go out as a salvo
+        let size = game.rules.board_size;
+        let opponent_board = if is_player1 { &ctx.accounts.board2.hits } else { &ctx.accounts.board1.hits };
+        let coords: Vec<(u8, u8)> = (0..game.rules.layers() as u8)
+            .map(|z| (x, z * size + y))
+            .filter(|&(cx, cy)| hit_cell(opponent_board, cell_index(game, cx, cy)) == 0)
+            .collect();
+        require!(!coords.is_empty(), ErrorCode::AlreadyShotHere);
+        require!(coords.len() <= ammo_left(game, game.turn), ErrorCode::OutOfAmmo);
+        
+        if record_strike(game)? {
+            return Ok(());
         }
+        charge_clock(game)?;
+        record_shots_fired(game, coords.len());
         
-        Ok(())
+        msg!("🌊 Player {} dropped a depth charge on ({}, {})", current_player, x, y);
+        
+        game.set_pending_salvo(&coords);
+        game.pending_shot_by = current_player;
+        if is_player1 {
+            game.depth_charge_used1 = 1;
+        } else {
+            game.depth_charge_used2 = 1;
+        }
+        game.draw_offered_by = 0; // Firing declines any standing draw offer or abort proposal
+        game.abort_proposed_by = 0;
+        game.last_action_slot = Clock::get()?.slot;
+        
+        notify_player_to_move(game, game_key, events)
     }
 
-    pub fn reveal_board_player1(
+    // Reveals the signer's own board once the game is over: an honest board earns the bond
+    // back, one that contradicts the recorded shot results forfeits the game
+    pub fn reveal_board(
         ctx: Context<RevealBoard>, 
-        original_board: [u8; 100], 
+        original_board: Vec<u8>, 
         salt: [u8; 32]
     ) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+        let game_key = ctx.accounts.game.key();
+        let player = ctx.accounts.player.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
         
-        require!(game.is_game_over, ErrorCode::GameNotOver);
-        require!(ctx.accounts.player.key() == game.player1, ErrorCode::NotPlayer1);
-        require!(!game.player1_revealed, ErrorCode::AlreadyRevealed);
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        let is_player1 = player == game.player1;
+        require!(is_player1 || player == game.player2, ErrorCode::NotAPlayer);
+        let (player_num, board) = if is_player1 {
+            (1, &mut ctx.accounts.board1)
+        } else {
+            (2, &mut ctx.accounts.board2)
+        };
+        let revealed = if is_player1 { game.player1_revealed } else { game.player2_revealed };
+        require!(revealed == 0, ErrorCode::AlreadyRevealed);
+        
+        require!(original_board.len() == game.rules.cells(), ErrorCode::InvalidBoardSize);
         
-        // Verify commitment
-        let mut data_to_hash = Vec::new();
-        data_to_hash.extend_from_slice(&original_board);
-        data_to_hash.extend_from_slice(&salt);
-        let computed_hash = hash(&data_to_hash).to_bytes();
+        // Verify commitment by rebuilding the Merkle root from every cell
+        let hash = game.rules.commit_hash();
+        let computed_root = board_merkle_root(hash, &original_board, &salt);
+        let commitment = bound_commitment(hash, computed_root, &game_key, &player);
+        
+        require!(commitment == board.commitment, ErrorCode::CommitmentMismatch);
+        
+        // Verify fleet configuration (ship squares must match the player's fleet)
+        let size = game.rules.board_size as usize;
+        let fleet = game.fleet_of(is_player1);
+        let ship_count = original_board.iter().filter(|&&cell| cell != 0 && cell != MINE_CELL).count();
+        require!(ship_count == fleet_cells(game, is_player1), ErrorCode::InvalidFleetConfiguration);
+        require!(valid_mines(game, &original_board), ErrorCode::InvalidMines);
+        require!(
+            validate_fleet_placement(&original_board, size, fleet, game.rules.hex()),
+            ErrorCode::InvalidShipPlacement
+        );
+        require!(valid_depths(game, &original_board, fleet), ErrorCode::InvalidShipDepth);
+        require!(
+            game.rules.variants & VARIANT_NO_TOUCH == 0 || !ships_touch(&original_board, size, game.rules.hex()),
+            ErrorCode::ShipsTouching
+        );
         
-        require!(computed_hash == game.board_commit1, ErrorCode::CommitmentMismatch);
+        if is_player1 {
+            game.player1_revealed = 1;
+        } else {
+            game.player2_revealed = 1;
+        }
         
-        // Verify fleet configuration (17 total ship squares)
-        let ship_count = original_board.iter().filter(|&&cell| cell == 1).count();
-        require!(ship_count == 17, ErrorCode::InvalidFleetConfiguration);
+        // Kept so later checks can read the fleet's cells without the board being passed again
+        let (ships, mines) = board_masks(&original_board);
+        board.ship_mask = ships;
         
-        game.player1_revealed = true;
+        // Verify the shot results recorded against this board - a contradiction forfeits the game
+        if !verify_shot_consistency(game, &board.hits, &ships, &mines, is_player1) {
+            let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+            flag_cheater(game, game_key, is_player1, events)?;
+            settle_reveals(game);
+            return Ok(());
+        }
         
-        // If both players revealed, verify shot consistency
-        if game.player2_revealed {
-            verify_shot_consistency(game, &original_board, true)?;
+        // An honest reveal earns the bond back, less any keeper bounty docked from it
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player.to_account_info(),
+            game_key,
+            game.vault_bump,
+            bond_left(game, player_num),
+        )?;
+        if is_player1 {
+            game.bond1_settled = 1;
+        } else {
+            game.bond2_settled = 1;
         }
+        settle_reveals(game);
         
-        msg!("📋 Player1 board revealed and verified!");
+        msg!("📋 Player{} board revealed and verified!", player_num);
         Ok(())
     }
 
+    // Kept for clients built before reveal_board; only the named seat may call each one
+    pub fn reveal_board_player1(
+        ctx: Context<RevealBoard>, 
+        original_board: Vec<u8>, 
+        salt: [u8; 32]
+    ) -> Result<()> {
+        require!(ctx.accounts.player.key() == ctx.accounts.game.load()?.player1, ErrorCode::NotPlayer1);
+        reveal_board(ctx, original_board, salt)
+    }
+
     pub fn reveal_board_player2(
         ctx: Context<RevealBoard>, 
-        original_board: [u8; 100], 
+        original_board: Vec<u8>, 
         salt: [u8; 32]
     ) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+        require!(ctx.accounts.player.key() == ctx.accounts.game.load()?.player2, ErrorCode::NotPlayer2);
+        reveal_board(ctx, original_board, salt)
+    }
+
+    pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
         
-        require!(game.is_game_over, ErrorCode::GameNotOver);
-        require!(ctx.accounts.player.key() == game.player2, ErrorCode::NotPlayer2);
-        require!(!game.player2_revealed, ErrorCode::AlreadyRevealed);
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+        require!(series_over(game), ErrorCode::SeriesInProgress);
+        require!(game.wager > 0, ErrorCode::NoWager);
+        require!(game.wager_mint == Pubkey::default(), ErrorCode::WrongWagerType);
+        require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
         
-        // Verify commitment
-        let mut data_to_hash = Vec::new();
-        data_to_hash.extend_from_slice(&original_board);
-        data_to_hash.extend_from_slice(&salt);
-        let computed_hash = hash(&data_to_hash).to_bytes();
+        let winner = game_winner(game)?;
+        require!(ctx.accounts.player.key() == winner, ErrorCode::NotWinner);
         
-        require!(computed_hash == game.board_commit2, ErrorCode::CommitmentMismatch);
+        // Pay out the whole vault except unsettled bonds, so it closes cleanly
+        let pot = vault_pot(&ctx.accounts.vault, game)?;
+        let fee = protocol_fee(&ctx.accounts.config, pot);
+        let game_key = ctx.accounts.game.key();
+        let vault_seeds: &[&[u8]] = &[b"vault", game_key.as_ref(), &[game.vault_bump]];
         
-        // Verify fleet configuration (17 total ship squares)
-        let ship_count = original_board.iter().filter(|&&cell| cell == 1).count();
-        require!(ship_count == 17, ErrorCode::InvalidFleetConfiguration);
+        // A player who timed out mid-game gets the share set at creation of what's left back
+        let abandon_refund = abandon_refund(game, pot - fee);
+        let loser_info = if abandon_refund > 0 {
+            let loser_key = if game.winner == 1 { game.player2 } else { game.player1 };
+            let loser = ctx.accounts.loser.as_ref().ok_or(ErrorCode::LoserAccountRequired)?;
+            require!(loser.key() == loser_key, ErrorCode::LoserAccountRequired);
+            loser.to_account_info()
+        } else {
+            ctx.accounts.player.to_account_info()
+        };
         
-        game.player2_revealed = true;
+        // The rake goes to the treasury first, then any abandon refund, the rest to the winner
+        for (to, amount) in [
+            (ctx.accounts.treasury.to_account_info(), fee),
+            (loser_info, abandon_refund),
+            (ctx.accounts.player.to_account_info(), pot - fee - abandon_refund),
+        ] {
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to,
+                    },
+                    &[vault_seeds],
+                ),
+                amount,
+            )?;
+        }
         
-        // If both players revealed, verify shot consistency
-        if game.player1_revealed {
-            verify_shot_consistency(game, &original_board, false)?;
+        // Each player's stake generated half the rake; a referred player's half pays their referrer a share
+        let referral_share = (fee / 2) * REFERRAL_SHARE_BPS / 10_000;
+        for referrer in [game.referrer1, game.referrer2].into_iter().filter(|referrer| *referrer != Pubkey::default()) {
+            let referrer_profile = [&mut ctx.accounts.referrer1_profile, &mut ctx.accounts.referrer2_profile]
+                .into_iter()
+                .flatten()
+                .find(|profile| profile.wallet == referrer)
+                .ok_or(ErrorCode::ReferrerAccountRequired)?;
+            referrer_profile.referral_rewards += referral_share;
+            ctx.accounts.config.referral_rewards_owed += referral_share;
         }
         
-        msg!("📋 Player2 board revealed and verified!");
+        game.winnings_claimed = 1;
+        
+        msg!(
+            "💰 Player {} claimed {} lamports in winnings ({} fee, {} refunded to the opponent)!",
+            winner,
+            pot - fee - abandon_refund,
+            fee,
+            abandon_refund
+        );
         Ok(())
     }
-}
 
-// Helper function to verify shot consistency after both boards are revealed
-fn verify_shot_consistency(
-    game: &Game, 
-    revealed_board: &[u8; 100], 
-    is_player1_board: bool
-) -> Result<()> {
-    let hits_board = if is_player1_board {
-        &game.board_hits1
-    } else {
-        &game.board_hits2
-    };
-    
-    for i in 0..100 {
-        match hits_board[i] {
-            1 => {
-                // Marked as miss - should be empty on revealed board
-                require!(revealed_board[i] == 0, ErrorCode::CheatingDetected);
-            },
-            2 => {
-                // Marked as hit - should have ship on revealed board
-                require!(revealed_board[i] == 1, ErrorCode::CheatingDetected);
-            },
-            _ => {} // 0 = not shot, no verification needed
+    pub fn claim_winnings_spl(ctx: Context<ClaimWinningsSpl>) -> Result<()> {
+        let mut game = ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+        require!(series_over(&game), ErrorCode::SeriesInProgress);
+        require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
+        
+        let winner = game_winner(&game)?;
+        require!(ctx.accounts.player.key() == winner, ErrorCode::NotWinner);
+        
+        // The vault ATA is owned by the game PDA, so the game signs the payout
+        let pot = ctx.accounts.vault_token_account.amount;
+        let fee = protocol_fee(&ctx.accounts.config, pot);
+        
+        // Each player's stake generated half the rake; a referred player's half pays their referrer a
+        // share straight from the vault, in the wager's mint
+        let referral_share = (fee / 2) * REFERRAL_SHARE_BPS / 10_000;
+        let mut referral_payouts = Vec::new();
+        for referrer in [game.referrer1, game.referrer2].into_iter().filter(|referrer| *referrer != Pubkey::default()) {
+            let referrer_token_account = [&ctx.accounts.referrer1_token_account, &ctx.accounts.referrer2_token_account]
+                .into_iter()
+                .flatten()
+                .find(|token_account| token_account.owner == referrer)
+                .ok_or(ErrorCode::ReferrerAccountRequired)?;
+            referral_payouts.push((referrer_token_account.to_account_info(), referral_share));
         }
-    }
-    
-    Ok(())
-}
-
-#[derive(Accounts)]
+        let referral_total = referral_share * referral_payouts.len() as u64;
+        
+        // A player who timed out mid-game gets the share set at creation of what's left back
+        let abandon_refund = abandon_refund(&game, pot - fee);
+        let loser_info = if abandon_refund > 0 {
+            let loser_key = if game.winner == 1 { game.player2 } else { game.player1 };
+            let loser = ctx.accounts.loser_token_account.as_ref().ok_or(ErrorCode::LoserAccountRequired)?;
+            require!(loser.owner == loser_key, ErrorCode::LoserAccountRequired);
+            loser.to_account_info()
+        } else {
+            ctx.accounts.winner_token_account.to_account_info()
+        };
+        
+        // Mark the claim up front: the game must be unborrowed to sign the transfers below
+        game.winnings_claimed = 1;
+        let (player1, game_id_bytes, bump, wager_mint) =
+            (game.player1, game.game_id.to_le_bytes(), game.bump, game.wager_mint);
+        drop(game);
+        let game_seeds: &[&[u8]] = &[b"game", player1.as_ref(), &game_id_bytes, &[bump]];
+        
+        for (to, amount) in [
+            (ctx.accounts.treasury_token_account.to_account_info(), fee - referral_total),
+            (loser_info, abandon_refund),
+            (ctx.accounts.winner_token_account.to_account_info(), pot - fee - abandon_refund),
+        ]
+        .into_iter()
+        .chain(referral_payouts)
+        {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.vault_token_account.to_account_info(),
+                        to,
+                        authority: ctx.accounts.game.to_account_info(),
+                    },
+                    &[game_seeds],
+                ),
+                amount,
+            )?;
+        }
+        
+        msg!(
+            "💰 Player {} claimed {} tokens of mint {} in winnings ({} fee, {} refunded to the opponent)!",
+            winner,
+            pot - fee - abandon_refund,
+            wager_mint,
+            fee,
+            abandon_refund
+        );
+        Ok(())
+    }
+
+    pub fn claim_nfts(ctx: Context<ClaimNfts>) -> Result<()> {
+        let mut game = ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+        require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
+        
+        let winner = game_winner(&game)?;
+        require!(ctx.accounts.player.key() == winner, ErrorCode::NotWinner);
+        
+        // Mark the claim up front: the game must be unborrowed to sign the transfers below
+        game.winnings_claimed = 1;
+        let (player1, game_id_bytes, bump, nft_mint1, nft_mint2) =
+            (game.player1, game.game_id.to_le_bytes(), game.bump, game.nft_mint1, game.nft_mint2);
+        drop(game);
+        
+        // NFT stakes take no protocol fee; each vault's rent goes back to whoever opened it
+        let game_seeds: &[&[u8]] = &[b"game", player1.as_ref(), &game_id_bytes, &[bump]];
+        
+        release_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_nft_account1,
+            ctx.accounts.winner_nft_account1.to_account_info(),
+            ctx.accounts.player1.to_account_info(),
+            ctx.accounts.game.to_account_info(),
+            game_seeds,
+        )?;
+        release_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_nft_account2,
+            ctx.accounts.winner_nft_account2.to_account_info(),
+            ctx.accounts.player2.to_account_info(),
+            ctx.accounts.game.to_account_info(),
+            game_seeds,
+        )?;
+        
+        msg!("🖼️ Player {} claimed NFTs {} and {}!", winner, nft_mint1, nft_mint2);
+        Ok(())
+    }
+
+    // Gives the joiner a different fleet from the creator's, e.g. a stronger creator taking
+    // 20 squares against a standard 17; each fleet is then sunk, and revealed, on its own terms
+    pub fn set_handicap_fleet(ctx: Context<SetHandicapFleet>, fleet: Vec<u8>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        require!(
+            game.state() == GameState::WaitingForOpponent && game.player2 == Pubkey::default(),
+            ErrorCode::GameAlreadyFull
+        );
+        // Fleet proofs are checked against a single fleet
+        require!(game.rules.variants & VARIANT_ZK_FLEET == 0, ErrorCode::InvalidFleetConfiguration);
+        require!(validate_fleet(&fleet, &game.rules), ErrorCode::InvalidFleetConfiguration);
+        
+        let fleet: &[u8] = if fleet.is_empty() { &STANDARD_FLEET } else { &fleet };
+        game.fleet2 = [0; MAX_SHIPS];
+        game.fleet2[..fleet.len()].copy_from_slice(fleet);
+        game.fleet2_len = fleet.len() as u8;
+        
+        msg!(
+            "⚖️ Handicap set: player1 defends {} squares, player2 {}",
+            fleet_cells(game, true),
+            fleet_cells(game, false)
+        );
+        Ok(())
+    }
+
+    pub fn offer_draw(ctx: Context<OfferDraw>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        
+        let player = ctx.accounts.player.key();
+        game.draw_offered_by = if player == game.player1 {
+            1
+        } else if player == game.player2 {
+            2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        
+        msg!("🤝 Player {} offered a draw", player);
+        Ok(())
+    }
+
+    // One emote per player per move, so the game's log can't be flooded
+    pub fn send_emote(ctx: Context<SendEmote>, emote_id: u8) -> Result<()> {
+        require!(emote_id < NUM_EMOTES, ErrorCode::InvalidEmote);
+        
+        let game_key = ctx.accounts.game.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
+        require_in_progress(game)?;
+        
+        let player = ctx.accounts.player.key();
+        let stamp = game.move_count + 1;
+        let last_emote = if player == game.player1 {
+            &mut game.last_emote1
+        } else if player == game.player2 {
+            &mut game.last_emote2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        require!(*last_emote != stamp, ErrorCode::EmoteRateLimited);
+        *last_emote = stamp;
+        
+        emit_cpi!(EmoteSent {
+            game: game_key,
+            player,
+            emote_id,
+        });
+        Ok(())
+    }
+
+    // Messages are only emitted, never stored, so chatting costs no rent
+    pub fn send_message(ctx: Context<SendMessage>, message: String) -> Result<()> {
+        require!(!message.is_empty() && message.len() <= MAX_MESSAGE_LEN, ErrorCode::InvalidMessage);
+        
+        let game = ctx.accounts.game.load()?;
+        let sender = ctx.accounts.sender.key();
+        require!(
+            sender == game.player1
+                || sender == game.player2
+                || game.rules.variants & VARIANT_SPECTATOR_CHAT != 0,
+            ErrorCode::NotAPlayer
+        );
+        
+        emit_cpi!(ChatMessage {
+            game: ctx.accounts.game.key(),
+            sender,
+            message,
+        });
+        Ok(())
+    }
+
+    pub fn accept_draw(ctx: Context<AcceptDraw>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        require!(game.draw_offered_by != 0, ErrorCode::NoDrawOffered);
+        
+        let player = ctx.accounts.player.key();
+        let accepting_player = if player == game.player1 {
+            1
+        } else if player == game.player2 {
+            2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        require!(accepting_player != game.draw_offered_by, ErrorCode::CannotAcceptOwnDraw);
+        
+        // Both boards must still be revealed before the pot is split
+        let over = game_over_state(game);
+        game.set_state(over);
+        game.winner = 0;
+        game.end_reason = 5; // 5 = draw
+        game.draw_offered_by = 0;
+        game.set_pending_shot(None);
+        game.set_pending_salvo(&[]);
+        game.pending_shot_by = Pubkey::default();
+        game.last_action_slot = Clock::get()?.slot;
+        
+        msg!("🤝 Draw agreed - both players must reveal their boards");
+        Ok(())
+    }
+
+    pub fn settle_draw(ctx: Context<SettleDraw>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_draw_settleable(game)?;
+        require!(
+            game.wager_mint == Pubkey::default() && game.nft_mint1 == Pubkey::default(),
+            ErrorCode::WrongWagerType
+        );
+        
+        // Split the pot evenly, leaving any unsettled bonds in the vault
+        let pot = vault_pot(&ctx.accounts.vault, game)?;
+        let player1_share = pot / 2;
+        let game_key = ctx.accounts.game.key();
+        
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player1.to_account_info(),
+            game_key,
+            game.vault_bump,
+            player1_share,
+        )?;
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player2.to_account_info(),
+            game_key,
+            game.vault_bump,
+            pot - player1_share,
+        )?;
+        
+        game.winnings_claimed = 1;
+        
+        msg!("🤝 Draw settled - {} lamports split between both players", pot);
+        Ok(())
+    }
+
+    pub fn settle_draw_spl(ctx: Context<SettleDrawSpl>) -> Result<()> {
+        let mut game = ctx.accounts.game.load_mut()?;
+        
+        require_draw_settleable(&game)?;
+        
+        // Mark the claim up front: the game must be unborrowed to sign the transfers below
+        game.winnings_claimed = 1;
+        let (player1, game_id_bytes, bump, wager_mint) =
+            (game.player1, game.game_id.to_le_bytes(), game.bump, game.wager_mint);
+        drop(game);
+        
+        let pot = ctx.accounts.vault_token_account.amount;
+        let player1_share = pot / 2;
+        let game_seeds: &[&[u8]] = &[b"game", player1.as_ref(), &game_id_bytes, &[bump]];
+        
+        for (to, amount) in [
+            (ctx.accounts.player1_token_account.to_account_info(), player1_share),
+            (ctx.accounts.player2_token_account.to_account_info(), pot - player1_share),
+        ] {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.vault_token_account.to_account_info(),
+                        to,
+                        authority: ctx.accounts.game.to_account_info(),
+                    },
+                    &[game_seeds],
+                ),
+                amount,
+            )?;
+        }
+        
+        msg!("🤝 Draw settled - {} tokens of mint {} split between both players", pot, wager_mint);
+        Ok(())
+    }
+
+    pub fn settle_draw_nft(ctx: Context<SettleDrawNft>) -> Result<()> {
+        let mut game = ctx.accounts.game.load_mut()?;
+        
+        require_draw_settleable(&game)?;
+        
+        // Mark the claim up front: the game must be unborrowed to sign the transfers below
+        game.winnings_claimed = 1;
+        let (player1, game_id_bytes, bump, nft_mint1, nft_mint2) =
+            (game.player1, game.game_id.to_le_bytes(), game.bump, game.nft_mint1, game.nft_mint2);
+        drop(game);
+        
+        // Each player simply gets their own NFT back
+        let game_seeds: &[&[u8]] = &[b"game", player1.as_ref(), &game_id_bytes, &[bump]];
+        
+        release_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_nft_account1,
+            ctx.accounts.player1_nft_account.to_account_info(),
+            ctx.accounts.player1.to_account_info(),
+            ctx.accounts.game.to_account_info(),
+            game_seeds,
+        )?;
+        release_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_nft_account2,
+            ctx.accounts.player2_nft_account.to_account_info(),
+            ctx.accounts.player2.to_account_info(),
+            ctx.accounts.game.to_account_info(),
+            game_seeds,
+        )?;
+        
+        msg!("🤝 Draw settled - NFTs {} and {} returned to their owners", nft_mint1, nft_mint2);
+        Ok(())
+    }
+
+    pub fn claim_timeout_victory(ctx: Context<ClaimTimeoutVictory>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        
+        let claimant = ctx.accounts.player.key();
+        let claimant_num = if claimant == game.player1 {
+            1
+        } else if claimant == game.player2 {
+            2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        
+        require!(claimant_num != player_to_move(game), ErrorCode::NotOpponentsMove);
+        
+        let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+        require!(elapsed > game.rules.turn_timeout_slots(), ErrorCode::TimeoutNotReached);
+        
+        end_by_timeout(game, claimant_num)?;
+        
+        msg!("⏰ Player {} wins by timeout after {} idle slots!", claimant, elapsed);
+        Ok(())
+    }
+
+    pub fn crank_timeout(ctx: Context<CrankTimeout>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        
+        let stalling_player = player_to_move(game);
+        let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+        require!(elapsed > game.rules.turn_timeout_slots(), ErrorCode::TimeoutNotReached);
+        
+        end_by_timeout(game, if stalling_player == 1 { 2 } else { 1 })?;
+        let bounty = pay_keeper_bounty(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.keeper.to_account_info(),
+            game,
+            game_key,
+            stalling_player,
+        )?;
+        
+        msg!("⏰ Keeper ended the game - player{} timed out (bounty: {} lamports)", stalling_player, bounty);
+        Ok(())
+    }
+
+    pub fn flag_timeout(ctx: Context<FlagTimeout>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.rules.clock_slots > 0, ErrorCode::NoTimeControl);
+        require_in_progress(game)?;
+        require!(game.turn_pending == 0, ErrorCode::TurnNotDecided);
+        
+        let flagged = player_to_move(game);
+        let remaining = if flagged == 1 { game.clock1 } else { game.clock2 };
+        let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+        require!(elapsed > remaining, ErrorCode::ClockNotExpired);
+        
+        if flagged == 1 {
+            game.clock1 = 0;
+        } else {
+            game.clock2 = 0;
+        }
+        end_by_timeout(game, if flagged == 1 { 2 } else { 1 })?;
+        let bounty = pay_keeper_bounty(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.keeper.to_account_info(),
+            game,
+            game_key,
+            flagged,
+        )?;
+        
+        msg!(
+            "⏱️ Player{}'s clock ran out - player{} wins on time! (bounty: {} lamports)",
+            flagged,
+            game.winner,
+            bounty
+        );
+        Ok(())
+    }
+
+    pub fn resign(ctx: Context<Resign>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        
+        let resigning_player = ctx.accounts.player.key();
+        let winner = if resigning_player == game.player1 {
+            2
+        } else if resigning_player == game.player2 {
+            1
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        
+        let over = game_over_state(game);
+        game.set_state(over);
+        game.winner = winner;
+        game.end_reason = 3; // 3 = resignation
+        game.set_pending_shot(None);
+        game.set_pending_salvo(&[]);
+        game.pending_shot_by = Pubkey::default();
+        game.last_action_slot = Clock::get()?.slot;
+        
+        msg!("🏳️ Player {} resigned! Player{} wins.", resigning_player, winner);
+        Ok(())
+    }
+
+    pub fn claim_reveal_forfeit(ctx: Context<ClaimRevealForfeit>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
+        require!(game.end_reason != 4, ErrorCode::AlreadyForfeited);
+        
+        let claimant = ctx.accounts.player.key();
+        let (claimant_num, claimant_revealed, opponent_revealed) = if claimant == game.player1 {
+            (1, game.player1_revealed != 0, game.player2_revealed != 0)
+        } else if claimant == game.player2 {
+            (2, game.player2_revealed != 0, game.player1_revealed != 0)
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        require!(claimant_revealed, ErrorCode::BoardsNotRevealed);
+        require!(!opponent_revealed, ErrorCode::OpponentRevealed);
+        require!(game.cheater != claimant_num, ErrorCode::CheatingDetected);
+        
+        // last_action_slot marks when the game ended
+        let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+        require!(elapsed > REVEAL_DEADLINE_SLOTS, ErrorCode::RevealDeadlineNotReached);
+        
+        // An unrevealed board can't be checked for cheating, so the honest revealer wins
+        game.winner = claimant_num;
+        game.end_reason = 4; // 4 = reveal forfeit
+        settle_reveals(game);
+        
+        msg!("⌛ Player {} wins - opponent never revealed their board", claimant);
+        Ok(())
+    }
+
+    pub fn crank_reveal_forfeit(ctx: Context<CrankRevealForfeit>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
+        require!(game.end_reason != 4, ErrorCode::AlreadyForfeited);
+        
+        // last_action_slot marks when the game ended
+        let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+        require!(elapsed > REVEAL_DEADLINE_SLOTS, ErrorCode::RevealDeadlineNotReached);
+        
+        let (revealer, defaulter) = match (game.player1_revealed != 0, game.player2_revealed != 0) {
+            (true, false) => (1, 2),
+            (false, true) => (2, 1),
+            (true, true) => return err!(ErrorCode::OpponentRevealed),
+            (false, false) => {
+                // Neither board can ever be checked, so there is no result to award
+                require!(game.state() == GameState::AwaitingReveal, ErrorCode::BoardsNotRevealed);
+                game.set_state(GameState::Aborted);
+                msg!("🪦 Game aborted - neither player revealed their board");
+                return Ok(());
+            }
+        };
+        require!(game.cheater != revealer, ErrorCode::CheatingDetected);
+        
+        game.winner = revealer;
+        game.end_reason = 4; // 4 = reveal forfeit
+        settle_reveals(game);
+        let bounty = pay_keeper_bounty(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.keeper.to_account_info(),
+            game,
+            game_key,
+            defaulter,
+        )?;
+        
+        msg!(
+            "⌛ Keeper awarded the game to player{} - player{} never revealed (bounty: {} lamports)",
+            revealer,
+            defaulter,
+            bounty
+        );
+        Ok(())
+    }
+
+    pub fn claim_forfeited_bond(ctx: Context<ClaimForfeitedBond>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        
+        let claimant = ctx.accounts.player.key();
+        let (claimant_num, opponent_num, claimant_revealed, opponent_revealed, opponent_settled) =
+            if claimant == game.player1 {
+                (1, 2, game.player1_revealed != 0, game.player2_revealed != 0, game.bond2_settled != 0)
+            } else if claimant == game.player2 {
+                (2, 1, game.player2_revealed != 0, game.player1_revealed != 0, game.bond1_settled != 0)
+            } else {
+                return err!(ErrorCode::NotAPlayer);
+            };
+        require!(claimant_revealed, ErrorCode::BoardsNotRevealed);
+        require!(game.cheater != claimant_num, ErrorCode::CheatingDetected);
+        require!(!opponent_settled, ErrorCode::BondAlreadySettled);
+        
+        // The opponent forfeits by cheating or by never revealing before the deadline
+        let reveal_deadline_passed = Clock::get()?.slot
+            > game.last_action_slot.saturating_add(REVEAL_DEADLINE_SLOTS);
+        require!(
+            game.cheater == opponent_num || (!opponent_revealed && reveal_deadline_passed),
+            ErrorCode::BondNotForfeited
+        );
+        
+        let forfeited = bond_left(game, opponent_num);
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player.to_account_info(),
+            ctx.accounts.game.key(),
+            game.vault_bump,
+            forfeited,
+        )?;
+        if opponent_num == 1 {
+            game.bond1_settled = 1;
+        } else {
+            game.bond2_settled = 1;
+        }
+        
+        msg!("⚖️ Player {} collected the opponent's {} lamport bond", claimant, forfeited);
+        Ok(())
+    }
+
+    pub fn cancel_game(ctx: Context<CancelGame>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        // Matched games hold both players' stakes; those are unwound with cancel_match
+        require!(
+            game.state() == GameState::WaitingForOpponent && game.player2 == Pubkey::default(),
+            ErrorCode::GameAlreadyFull
+        );
+        require!(
+            game.wager_mint == Pubkey::default() && game.nft_mint1 == Pubkey::default(),
+            ErrorCode::WrongWagerType
+        );
+        game.set_state(GameState::Cancelled);
+        
+        if game.is_public != 0 {
+            let lobby = ctx.accounts.lobby.as_mut().ok_or(ErrorCode::LobbyRequired)?;
+            remove_lobby_entry(lobby, ctx.accounts.game.key());
+        }
+        
+        // Refund player1's stake before the game account is closed
+        let refund = ctx.accounts.vault.lamports();
+        if refund > 0 {
+            let game_key = ctx.accounts.game.key();
+            let vault_seeds: &[&[u8]] = &[b"vault", game_key.as_ref(), &[game.vault_bump]];
+            
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: ctx.accounts.player.to_account_info(),
+                    },
+                    &[vault_seeds],
+                ),
+                refund,
+            )?;
+        }
+        
+        msg!("🗑️ Game cancelled by player: {} (refunded {} lamports)", game.player1, refund);
+        Ok(())
+    }
+
+    pub fn cancel_game_spl(ctx: Context<CancelGameSpl>) -> Result<()> {
+        let mut game = ctx.accounts.game.load_mut()?;
+        
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        game.set_state(GameState::Cancelled);
+        
+        if game.is_public != 0 {
+            let lobby = ctx.accounts.lobby.as_mut().ok_or(ErrorCode::LobbyRequired)?;
+            remove_lobby_entry(lobby, ctx.accounts.game.key());
+        }
+        
+        // The game must be unborrowed to sign the refund below
+        let (player1, game_id_bytes, bump, vault_bump) =
+            (game.player1, game.game_id.to_le_bytes(), game.bump, game.vault_bump);
+        drop(game);
+        
+        let refund = ctx.accounts.vault_token_account.amount;
+        let game_seeds: &[&[u8]] = &[b"game", player1.as_ref(), &game_id_bytes, &[bump]];
+        
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.player_token_account.to_account_info(),
+                    authority: ctx.accounts.game.to_account_info(),
+                },
+                &[game_seeds],
+            ),
+            refund,
+        )?;
+        
+        // Close the emptied vault so its rent goes back to player1 as well
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.vault_token_account.to_account_info(),
+                destination: ctx.accounts.player.to_account_info(),
+                authority: ctx.accounts.game.to_account_info(),
+            },
+            &[game_seeds],
+        ))?;
+        
+        // The bond sits in the lamport vault alongside the token escrow
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player.to_account_info(),
+            ctx.accounts.game.key(),
+            vault_bump,
+            ctx.accounts.vault.lamports(),
+        )?;
+        
+        msg!("🗑️ Game cancelled by player: {} (refunded {} tokens)", player1, refund);
+        Ok(())
+    }
+
+    pub fn cancel_game_nft(ctx: Context<CancelGameNft>) -> Result<()> {
+        let mut game = ctx.accounts.game.load_mut()?;
+        
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        game.set_state(GameState::Cancelled);
+        
+        if game.is_public != 0 {
+            let lobby = ctx.accounts.lobby.as_mut().ok_or(ErrorCode::LobbyRequired)?;
+            remove_lobby_entry(lobby, ctx.accounts.game.key());
+        }
+        
+        // The game must be unborrowed to sign the NFT's return below
+        let (player1, game_id_bytes, bump, vault_bump, nft_mint1) =
+            (game.player1, game.game_id.to_le_bytes(), game.bump, game.vault_bump, game.nft_mint1);
+        drop(game);
+        
+        let game_seeds: &[&[u8]] = &[b"game", player1.as_ref(), &game_id_bytes, &[bump]];
+        
+        release_nft(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault_nft_account,
+            ctx.accounts.player_nft_account.to_account_info(),
+            ctx.accounts.player.to_account_info(),
+            ctx.accounts.game.to_account_info(),
+            game_seeds,
+        )?;
+        
+        // The bond sits in the lamport vault alongside the NFT escrow
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player.to_account_info(),
+            ctx.accounts.game.key(),
+            vault_bump,
+            ctx.accounts.vault.lamports(),
+        )?;
+        
+        msg!("🗑️ Game cancelled by player: {} (returned NFT {})", player1, nft_mint1);
+        Ok(())
+    }
+
+    pub fn close_game(ctx: Context<CloseGame>) -> Result<()> {
+        require_closeable(&*ctx.accounts.game.load()?)?;
+        archive_game(
+            &*ctx.accounts.game.load()?,
+            [&*ctx.accounts.board1, &*ctx.accounts.board2],
+            ctx.accounts.game.key(),
+            &ctx.accounts.config,
+            &ctx.accounts.merkle_tree,
+            &ctx.accounts.compression_program,
+            &ctx.accounts.noop_program,
+            &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority),
+        )?;
+        
+        // Split the rent refund: half to player2, the rest to player1 on close
+        let game_info = ctx.accounts.game.to_account_info();
+        let player2_share = game_info.lamports() / 2;
+        game_info.sub_lamports(player2_share)?;
+        ctx.accounts.player2.add_lamports(player2_share)?;
+        
+        ctx.accounts.game.close(ctx.accounts.player1.to_account_info())?;
+        
+        msg!("🧹 Game closed, rent refunded to both players");
+        Ok(())
+    }
+
+    pub fn crank_close_game(ctx: Context<CrankCloseGame>) -> Result<()> {
+        require_closeable(&*ctx.accounts.game.load()?)?;
+        archive_game(
+            &*ctx.accounts.game.load()?,
+            [&*ctx.accounts.board1, &*ctx.accounts.board2],
+            ctx.accounts.game.key(),
+            &ctx.accounts.config,
+            &ctx.accounts.merkle_tree,
+            &ctx.accounts.compression_program,
+            &ctx.accounts.noop_program,
+            &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority),
+        )?;
+        
+        // The keeper's bounty comes off the top of the game's rent, the rest is split as usual
+        let game_info = ctx.accounts.game.to_account_info();
+        game_info.sub_lamports(KEEPER_BOUNTY_LAMPORTS)?;
+        ctx.accounts.keeper.add_lamports(KEEPER_BOUNTY_LAMPORTS)?;
+        
+        let player2_share = game_info.lamports() / 2;
+        game_info.sub_lamports(player2_share)?;
+        ctx.accounts.player2.add_lamports(player2_share)?;
+        
+        ctx.accounts.game.close(ctx.accounts.player1.to_account_info())?;
+        
+        msg!("🧹 Keeper closed a finished game (bounty: {} lamports)", KEEPER_BOUNTY_LAMPORTS);
+        Ok(())
+    }
+
+    pub fn propose_rematch(
+        ctx: Context<ProposeRematch>,
+        board_commitment: [u8; 32],
+        roll_pot: bool,
+        top_up: u64
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        require!(game.player1_revealed != 0 && game.player2_revealed != 0, ErrorCode::BoardsNotRevealed);
+        require!(game.cheater == 0, ErrorCode::CheatingDetected);
+        require!(game.rematch_proposer == 0, ErrorCode::RematchAlreadyProposed);
+        require!(game.nft_mint1 == Pubkey::default(), ErrorCode::NftGameSingleRound);
+        require!(game.spectator_bets == 0, ErrorCode::BetsOutstanding);
+        
+        let proposer = ctx.accounts.player.key();
+        let proposer_num = if proposer == game.player1 {
+            1
+        } else if proposer == game.player2 {
+            2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        
+        // A top-up raises a lamport wager; the acceptor has to match it
+        if top_up > 0 {
+            require!(game.wager > 0, ErrorCode::NoWager);
+            require!(game.wager_mint == Pubkey::default(), ErrorCode::WrongWagerType);
+        }
+        
+        post_rematch_stake(
+            game,
+            &ctx.accounts.system_program,
+            &ctx.accounts.player,
+            &ctx.accounts.vault,
+            roll_pot,
+            top_up,
+        )?;
+        
+        game.rematch_proposer = proposer_num;
+        game.rematch_commitment = board_commitment;
+        game.rematch_roll_pot = roll_pot as u8;
+        game.rematch_top_up = top_up;
+        // The proposer's new bond is held back from any payout until the rematch resolves
+        if proposer_num == 1 {
+            game.bond1_settled = 0;
+        } else {
+            game.bond2_settled = 0;
+        }
+        
+        msg!("🔁 Player {} proposed a rematch (roll pot: {}, top-up: {})", proposer, roll_pot, top_up);
+        Ok(())
+    }
+
+    pub fn accept_rematch(ctx: Context<AcceptRematch>, board_commitment: [u8; 32]) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.rematch_proposer != 0, ErrorCode::NoRematchProposed);
+        
+        let acceptor = ctx.accounts.player.key();
+        let acceptor_num = if acceptor == game.player1 {
+            1
+        } else if acceptor == game.player2 {
+            2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        require!(acceptor_num != game.rematch_proposer, ErrorCode::CannotAcceptOwnRematch);
+        
+        let roll_pot = game.rematch_roll_pot != 0;
+        let top_up = game.rematch_top_up;
+        post_rematch_stake(
+            game,
+            &ctx.accounts.system_program,
+            &ctx.accounts.player,
+            &ctx.accounts.vault,
+            roll_pot,
+            top_up,
+        )?;
+        
+        let (board_commit1, board_commit2) = if acceptor_num == 1 {
+            (board_commitment, game.rematch_commitment)
+        } else {
+            (game.rematch_commitment, board_commitment)
+        };
+        reset_for_rematch(game, &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2], board_commit1, board_commit2);
+        game.wager += top_up; // Both matched it, so each has that much more at stake
+        game.last_action_slot = Clock::get()?.slot;
+        
+        msg!("🔁 Rematch accepted! Player{} moves first.", game.turn);
+        Ok(())
+    }
+
+    pub fn cancel_rematch(ctx: Context<CancelRematch>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        let proposer = ctx.accounts.player.key();
+        let proposer_num = if proposer == game.player1 { 1 } else if proposer == game.player2 { 2 } else { 0 };
+        require!(
+            game.rematch_proposer != 0 && proposer_num == game.rematch_proposer,
+            ErrorCode::NoRematchProposed
+        );
+        
+        // Hand back exactly what propose_rematch took
+        let refund = if game.rematch_roll_pot != 0 { game.bond } else { game.bond + game.wager } + game.rematch_top_up;
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player.to_account_info(),
+            ctx.accounts.game.key(),
+            game.vault_bump,
+            refund,
+        )?;
+        
+        if proposer_num == 1 {
+            game.bond1_settled = 1;
+        } else {
+            game.bond2_settled = 1;
+        }
+        game.rematch_proposer = 0;
+        game.rematch_commitment = [0; 32];
+        game.rematch_roll_pot = 0;
+        game.rematch_top_up = 0;
+        
+        msg!("🔁 Player {} withdrew their rematch offer", proposer);
+        Ok(())
+    }
+
+    pub fn initialize_lobby(ctx: Context<InitializeLobby>) -> Result<()> {
+        let lobby = &mut ctx.accounts.lobby;
+        
+        lobby.bump = ctx.bumps.lobby;
+        lobby.entries = Vec::new();
+        
+        msg!("🏛️ Game lobby created");
+        Ok(())
+    }
+
+    pub fn list_game(ctx: Context<ListGame>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        let lobby = &mut ctx.accounts.lobby;
+        
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        require!(game.is_public == 0, ErrorCode::AlreadyListed);
+        require!(game.invited_opponent == Pubkey::default(), ErrorCode::NotInvited);
+        require!(lobby.entries.len() < MAX_LOBBY_ENTRIES, ErrorCode::LobbyFull);
+        
+        lobby.entries.push(LobbyEntry {
+            game: ctx.accounts.game.key(),
+            creator: game.player1,
+            wager: game.wager,
+            wager_mint: game.wager_mint,
+            board_size: game.rules.board_size,
+        });
+        game.is_public = 1;
+        
+        msg!("📣 Game {} listed in the lobby by {}", ctx.accounts.game.key(), game.player1);
+        Ok(())
+    }
+
+    // Opt-in replay log: the account grows by MOVE_HISTORY_LEN, paid for by the caller, and
+    // each resolved shot is appended after the game state. Must come before the first shot
+    pub fn enable_move_history(ctx: Context<EnableMoveHistory>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        require!(game.move_history == 0, ErrorCode::MoveHistoryEnabled);
+        require!(game.move_count == 0 && !game.is_over(), ErrorCode::MoveHistoryClosed);
+        
+        game.move_history = 1;
+        
+        msg!("📜 Move history enabled for game {} by {}", ctx.accounts.game.key(), ctx.accounts.player.key());
+        Ok(())
+    }
+    
+    // Set before anyone joins, so the joiner knows who will see their board
+    pub fn designate_observers(ctx: Context<DesignateObservers>, observers: Vec<Pubkey>) -> Result<()> {
+        let game = ctx.accounts.game.load()?;
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        require!(
+            !observers.is_empty()
+                && observers.len() <= MAX_OBSERVERS
+                && !observers.contains(&game.player1),
+            ErrorCode::InvalidObservers
+        );
+        
+        let record = &mut ctx.accounts.observers;
+        record.game = ctx.accounts.game.key();
+        record.sealed1 = vec![[0; 32]; observers.len()];
+        record.sealed2 = vec![[0; 32]; observers.len()];
+        record.observers = observers;
+        record.bump = ctx.bumps.observers;
+        
+        msg!("🎥 {} observers designated for game {}", record.observers.len(), record.game);
+        Ok(())
+    }
+
+    // Each player encrypts their board and salts to every observer's key off-chain and records
+    // the ciphertext hashes here, before the first shot; an observer receiving a blob can then
+    // show it's the one the player committed to handing over
+    pub fn seal_board_for_observers(ctx: Context<SealBoardForObservers>, sealed: Vec<[u8; 32]>) -> Result<()> {
+        let game = ctx.accounts.game.load()?;
+        require!(game.move_count == 0 && !game.is_over(), ErrorCode::ObserverSealClosed);
+        
+        let record = &mut ctx.accounts.observers;
+        require!(sealed.len() == record.observers.len(), ErrorCode::InvalidObservers);
+        require!(!sealed.contains(&[0; 32]), ErrorCode::InvalidObservers);
+        
+        let player = ctx.accounts.player.key();
+        let slot = if player == game.player1 {
+            &mut record.sealed1
+        } else if player == game.player2 {
+            &mut record.sealed2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        require!(slot.iter().all(|hash| *hash == [0; 32]), ErrorCode::BoardAlreadySealed);
+        *slot = sealed;
+        
+        msg!("🎥 Player {} sealed their board for the observers", player);
+        Ok(())
+    }
+
+    pub fn initialize_profile(ctx: Context<InitializeProfile>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        
+        profile.wallet = ctx.accounts.player.key();
+        profile.bump = ctx.bumps.profile;
+        profile.games_played = 0;
+        profile.wins = 0;
+        profile.losses = 0;
+        profile.draws = 0;
+        profile.shots_fired = 0;
+        profile.hits = 0;
+        profile.ships_sunk = 0;
+        profile.rating = INITIAL_RATING;
+        profile.session_key = Pubkey::default();
+        profile.session_expires_slot = 0;
+        profile.season = 0; // Season stats restart whenever the profile first plays in a new season
+        profile.season_rating = INITIAL_RATING;
+        profile.season_wins = 0;
+        profile.season_losses = 0;
+        profile.season_draws = 0;
+        profile.win_streak = 0;
+        profile.achievements = 0;
+        profile.loss_streak = 0;
+        profile.best_win_streak = 0;
+        profile.referrer = Pubkey::default(); // Set by the player's first initialize_game/join_game
+        profile.referral_rewards = 0;
+        
+        msg!("🪪 Profile created for player: {}", profile.wallet);
+        Ok(())
+    }
+
+    pub fn register_session_key(
+        ctx: Context<RegisterSessionKey>,
+        session_key: Pubkey,
+        duration_slots: u64
+    ) -> Result<()> {
+        require!(
+            duration_slots > 0 && duration_slots <= MAX_SESSION_SLOTS,
+            ErrorCode::InvalidSessionDuration
+        );
+        require!(session_key != Pubkey::default(), ErrorCode::InvalidSessionKey);
+        
+        // Registering again replaces (and so revokes) any earlier session key
+        let profile = &mut ctx.accounts.profile;
+        profile.session_key = session_key;
+        profile.session_expires_slot = Clock::get()?.slot + duration_slots;
+        
+        msg!(
+            "🔑 Session key {} may move for {} until slot {}",
+            session_key,
+            profile.wallet,
+            profile.session_expires_slot
+        );
+        Ok(())
+    }
+
+    pub fn register_name(ctx: Context<RegisterName>, name: String) -> Result<()> {
+        require_valid_name(&name)?;
+        
+        // The name PDA can only be created once, so holding it is what makes a name unique.
+        // Renaming releases the old one for anyone to claim
+        let profile = &mut ctx.accounts.profile;
+        require!(profile.name.is_empty() || ctx.accounts.old_name_record.is_some(), ErrorCode::NameRecordRequired);
+        
+        let name_record = &mut ctx.accounts.name_record;
+        name_record.owner = profile.wallet;
+        name_record.bump = ctx.bumps.name_record;
+        profile.name = name;
+        
+        msg!("🏷️ {} is now known as {}", profile.wallet, profile.name);
+        Ok(())
+    }
+
+    // Ownership is only checked now; frontends should re-check the token account before
+    // showing the avatar as verified, since the NFT may have been sold since
+    pub fn link_avatar(ctx: Context<LinkAvatar>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.avatar_mint = ctx.accounts.nft_mint.key();
+        
+        msg!("🖼️ {} linked avatar {}", profile.wallet, profile.avatar_mint);
+        Ok(())
+    }
+
+    pub fn create_clan(ctx: Context<CreateClan>, name: String) -> Result<()> {
+        require_valid_name(&name)?;
+        let profile = &mut ctx.accounts.profile;
+        require!(profile.clan == Pubkey::default(), ErrorCode::AlreadyInClan);
+        
+        // The founder is the clan's first member
+        let clan = &mut ctx.accounts.clan;
+        clan.name = name;
+        clan.founder = profile.wallet;
+        clan.members = 1;
+        clan.wins = 0;
+        clan.losses = 0;
+        clan.draws = 0;
+        clan.bump = ctx.bumps.clan;
+        profile.clan = clan.key();
+        
+        msg!("🏴 Clan {} founded by {}", clan.name, clan.founder);
+        Ok(())
+    }
+
+    pub fn join_clan(ctx: Context<JoinClan>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        let clan = &mut ctx.accounts.clan;
+        require!(profile.clan == Pubkey::default(), ErrorCode::AlreadyInClan);
+        require!(clan.members < MAX_CLAN_MEMBERS, ErrorCode::ClanFull);
+        
+        clan.members += 1;
+        profile.clan = clan.key();
+        
+        msg!("🏴 {} joined clan {}", profile.wallet, clan.name);
+        Ok(())
+    }
+
+    // The last member out closes the clan and takes its rent, freeing the name
+    pub fn leave_clan(ctx: Context<LeaveClan>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        let clan = &mut ctx.accounts.clan;
+        // The founder runs the clan's wars and rosters, so they're the last one out
+        require!(
+            clan.founder != profile.wallet || clan.members == 1,
+            ErrorCode::FounderLeavesLast
+        );
+        
+        clan.members -= 1;
+        profile.clan = Pubkey::default();
+        msg!("🏴 {} left clan {}", profile.wallet, clan.name);
+        
+        if clan.members == 0 {
+            clan.close(ctx.accounts.player.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    pub fn declare_clan_war(
+        ctx: Context<DeclareClanWar>,
+        war_id: u64,
+        stake: u64,
+        start_slot: u64,
+        end_slot: u64
+    ) -> Result<()> {
+        require!(ctx.accounts.clan_a.key() != ctx.accounts.clan_b.key(), ErrorCode::SameClan);
+        require!(
+            start_slot > Clock::get()?.slot && end_slot > start_slot,
+            ErrorCode::InvalidWarWindow
+        );
+        require!(stake == 0 || stake >= Rent::get()?.minimum_balance(0), ErrorCode::WagerTooSmall);
+        
+        if stake > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.founder.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                stake,
+            )?;
+        }
+        
+        let war = &mut ctx.accounts.clan_war;
+        war.clan_a = ctx.accounts.clan_a.key();
+        war.clan_b = ctx.accounts.clan_b.key();
+        war.founder_a = ctx.accounts.founder.key();
+        war.founder_b = ctx.accounts.clan_b.founder;
+        war.war_id = war_id;
+        war.bump = ctx.bumps.clan_war;
+        war.vault_bump = ctx.bumps.vault;
+        war.stake = stake; // Each side's share of the pot; 0 = for glory only
+        war.start_slot = start_slot;
+        war.end_slot = end_slot;
+        war.state = 0; // 0 = declared, 1 = accepted, 2 = finalized
+        war.roster_a = Vec::new();
+        war.roster_b = Vec::new();
+        war.points_a = 0;
+        war.points_b = 0;
+        war.winner = 0;
+        war.claimed = 0;
+        
+        msg!("⚔️ Clan war #{} declared on {} for {} lamports a side", war_id, war.clan_b, stake);
+        Ok(())
+    }
+
+    pub fn accept_clan_war(ctx: Context<AcceptClanWar>) -> Result<()> {
+        let war = &mut ctx.accounts.clan_war;
+        require!(war.state == 0, ErrorCode::WarAlreadyAccepted);
+        require!(Clock::get()?.slot < war.start_slot, ErrorCode::WarStarted);
+        
+        if war.stake > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.founder.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                war.stake,
+            )?;
+        }
+        war.state = 1;
+        
+        msg!("⚔️ Clan war accepted - it runs from slot {} to {}", war.start_slot, war.end_slot);
+        Ok(())
+    }
+
+    // The declaring founder can call off a war the other clan never accepted
+    pub fn cancel_clan_war(ctx: Context<CancelClanWar>) -> Result<()> {
+        require!(ctx.accounts.clan_war.state == 0, ErrorCode::WarAlreadyAccepted);
+        
+        let war_key = ctx.accounts.clan_war.key();
+        let vault_seeds: &[&[u8]] = &[b"clan_war_vault", war_key.as_ref(), &[ctx.accounts.clan_war.vault_bump]];
+        let refund = ctx.accounts.vault.lamports();
+        if refund > 0 {
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: ctx.accounts.founder.to_account_info(),
+                    },
+                    &[vault_seeds],
+                ),
+                refund,
+            )?;
+        }
+        
+        msg!("⚔️ Clan war {} called off", war_key);
+        Ok(())
+    }
+
+    // Founders pick their own rosters, so players who merely joined the clan can't take its seats
+    pub fn enlist_for_war(ctx: Context<EnlistForWar>) -> Result<()> {
+        let war = &mut ctx.accounts.clan_war;
+        let player = ctx.accounts.player.key();
+        let clan = ctx.accounts.clan.key();
+        
+        require!(war.state < 2 && Clock::get()?.slot < war.start_slot, ErrorCode::WarStarted);
+        require!(
+            !war.roster_a.contains(&player) && !war.roster_b.contains(&player),
+            ErrorCode::AlreadyRegistered
+        );
+        
+        let roster = if clan == war.clan_a {
+            &mut war.roster_a
+        } else if clan == war.clan_b {
+            &mut war.roster_b
+        } else {
+            return err!(ErrorCode::NotInClan);
+        };
+        require!(roster.len() < MAX_WAR_ROSTER, ErrorCode::RosterFull);
+        roster.push(player);
+        
+        msg!("⚔️ {} enlisted for clan {}", player, clan);
+        Ok(())
+    }
+
+    // Permissionless - anyone can close out a war once its window has passed. A drawn war
+    // hands each founder their stake back; otherwise the winning roster claims the pot
+    pub fn finalize_clan_war(ctx: Context<FinalizeClanWar>) -> Result<()> {
+        let war = &mut ctx.accounts.clan_war;
+        require!(war.state == 1, ErrorCode::WarNotAccepted);
+        require!(Clock::get()?.slot > war.end_slot, ErrorCode::WarNotOver);
+        
+        war.state = 2;
+        war.winner = match war.points_a.cmp(&war.points_b) {
+            std::cmp::Ordering::Greater => 1,
+            std::cmp::Ordering::Less => 2,
+            std::cmp::Ordering::Equal => 0,
+        };
+        
+        if war.winner == 0 && war.stake > 0 {
+            let war_key = war.key();
+            let vault_seeds: &[&[u8]] = &[b"clan_war_vault", war_key.as_ref(), &[war.vault_bump]];
+            for founder in [&ctx.accounts.founder_a, &ctx.accounts.founder_b] {
+                system_program::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.vault.to_account_info(),
+                            to: founder.to_account_info(),
+                        },
+                        &[vault_seeds],
+                    ),
+                    war.stake,
+                )?;
+            }
+        }
+        
+        msg!("⚔️ Clan war over: {} to {} (winner: side {})", war.points_a, war.points_b, war.winner);
+        Ok(())
+    }
+
+    // Each member of the winning roster takes an equal share of both stakes
+    pub fn claim_war_prize(ctx: Context<ClaimWarPrize>) -> Result<()> {
+        let war = &mut ctx.accounts.clan_war;
+        let player = ctx.accounts.player.key();
+        require!(war.state == 2 && war.winner != 0, ErrorCode::NotEligibleForPrize);
+        
+        let (roster, offset) = if war.winner == 1 { (&war.roster_a, 0) } else { (&war.roster_b, MAX_WAR_ROSTER) };
+        let seat = roster.iter().position(|member| *member == player).ok_or(ErrorCode::NotEligibleForPrize)?;
+        let share = 2 * war.stake / roster.len() as u64;
+        let bit = 1u32 << (offset + seat);
+        require!(war.claimed & bit == 0, ErrorCode::WinningsAlreadyClaimed);
+        war.claimed |= bit;
+        
+        if share > 0 {
+            let war_key = war.key();
+            let vault_seeds: &[&[u8]] = &[b"clan_war_vault", war_key.as_ref(), &[war.vault_bump]];
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: ctx.accounts.player.to_account_info(),
+                    },
+                    &[vault_seeds],
+                ),
+                share,
+            )?;
+        }
+        
+        msg!("⚔️ {} claimed {} lamports of war spoils", player, share);
+        Ok(())
+    }
+
+    // Permissionless - anyone can score a finished game for a war, once, apart from recording
+    // the players' stats
+    pub fn record_war_game(ctx: Context<RecordWarGame>) -> Result<()> {
+        let game = ctx.accounts.game.load()?;
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        // Reveals settle cheating and reveal forfeits, so only then is the result final
+        require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+        require!(game.ranked != 0, ErrorCode::CasualGame);
+        
+        let war = &mut ctx.accounts.clan_war;
+        require!(war.state == 1, ErrorCode::WarNotAccepted);
+        require!(score_war_game(war, &game), ErrorCode::NotAWarGame);
+        ctx.accounts.war_game.bump = ctx.bumps.war_game;
+        
+        msg!("⚔️ Game scored for the war: {} to {}", war.points_a, war.points_b);
+        Ok(())
+    }
+
+    pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.session_key = Pubkey::default();
+        profile.session_expires_slot = 0;
+        
+        msg!("🔑 Session key revoked for {}", profile.wallet);
+        Ok(())
+    }
+
+    pub fn record_game_result(ctx: Context<RecordGameResult>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        // Reveals settle cheating and reveal forfeits, so only then is the result final
+        require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+        require!(game.stats_recorded == 0, ErrorCode::StatsAlreadyRecorded);
+        require!(game.ranked != 0, ErrorCode::CasualGame);
+        
+        let boards = [&*ctx.accounts.board1, &*ctx.accounts.board2];
+        record_profile_stats(&mut ctx.accounts.player1_profile, game, boards, true);
+        record_profile_stats(&mut ctx.accounts.player2_profile, game, boards, false);
+        update_reputation(&mut ctx.accounts.player1_profile, game, 1);
+        update_reputation(&mut ctx.accounts.player2_profile, game, 2);
+        update_streaks(&mut ctx.accounts.player1_profile, game, game_key, 1, events)?;
+        update_streaks(&mut ctx.accounts.player2_profile, game, game_key, 2, events)?;
+        award_achievements(&mut ctx.accounts.player1_profile, game, game_key, 1, events)?;
+        award_achievements(&mut ctx.accounts.player2_profile, game, game_key, 2, events)?;
+        
+        // Clan totals only count games between clans, so members can't farm each other
+        let (clan1, clan2) = (ctx.accounts.player1_profile.clan, ctx.accounts.player2_profile.clan);
+        if clan1 != clan2 {
+            if clan1 != Pubkey::default() {
+                let clan = ctx.accounts.clan1.as_mut().ok_or(ErrorCode::ClanAccountRequired)?;
+                record_clan_result(clan, game, 1);
+            }
+            if clan2 != Pubkey::default() {
+                let clan = ctx.accounts.clan2.as_mut().ok_or(ErrorCode::ClanAccountRequired)?;
+                record_clan_result(clan, game, 2);
+            }
+        }
+        
+        // Both rating changes use the pre-game ratings
+        let (rating1, rating2) = (ctx.accounts.player1_profile.rating, ctx.accounts.player2_profile.rating);
+        let score1 = match game.winner {
+            1 => 1.0,
+            2 => 0.0,
+            _ => 0.5,
+        };
+        ctx.accounts.player1_profile.rating = elo_rating(rating1, rating2, score1);
+        ctx.accounts.player2_profile.rating = elo_rating(rating2, rating1, 1.0 - score1);
+        
+        // A game recorded after its season rolled over only counts toward lifetime stats
+        if game.season == ctx.accounts.config.season {
+            record_season_stats(&mut ctx.accounts.player1_profile, game, 1);
+            record_season_stats(&mut ctx.accounts.player2_profile, game, 2);
+            
+            let (rating1, rating2) = (
+                ctx.accounts.player1_profile.season_rating,
+                ctx.accounts.player2_profile.season_rating,
+            );
+            ctx.accounts.player1_profile.season_rating = elo_rating(rating1, rating2, score1);
+            ctx.accounts.player2_profile.season_rating = elo_rating(rating2, rating1, 1.0 - score1);
+            
+            if let Some(leaderboard) = ctx.accounts.leaderboard.as_mut() {
+                update_leaderboard(leaderboard, &ctx.accounts.player1_profile);
+                update_leaderboard(leaderboard, &ctx.accounts.player2_profile);
+            }
+        }
+        game.stats_recorded = 1;
+        
+        msg!("📊 Recorded game result for {} and {}", game.player1, game.player2);
+        Ok(())
+    }
+
+    pub fn mint_trophy(ctx: Context<MintTrophy>) -> Result<()> {
+        let mut game = ctx.accounts.game.load_mut()?;
+        
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        // Reveals settle cheating and reveal forfeits, so only then is the result final
+        require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+        require!(game.trophy_minted == 0, ErrorCode::TrophyAlreadyMinted);
+        
+        let winner = game_winner(&game)?;
+        require!(ctx.accounts.winner.key() == winner, ErrorCode::NotWinner);
+        
+        // Score is hits landed by each player; every resolved cell on either board is a shot
+        let shots = shots_taken(&game, [&*ctx.accounts.board1, &*ctx.accounts.board2]);
+        let uri = format!(
+            "{}?game={}&score={}-{}&shots={}",
+            TROPHY_BASE_URI,
+            ctx.accounts.game.key(),
+            game.hits_count2,
+            game.hits_count1,
+            shots
+        );
+        
+        // Mark the mint up front: the game must be unborrowed to sign the CPIs below
+        game.trophy_minted = 1;
+        let (player1, game_id_bytes, bump) = (game.player1, game.game_id.to_le_bytes(), game.bump);
+        drop(game);
+        
+        // The game PDA is mint and update authority, so it signs every step
+        let game_info = ctx.accounts.game.to_account_info();
+        let game_seeds: &[&[u8]] = &[b"game", player1.as_ref(), &game_id_bytes, &[bump]];
+        
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: ctx.accounts.trophy_mint.to_account_info(),
+                    to: ctx.accounts.winner_token_account.to_account_info(),
+                    authority: game_info.clone(),
+                },
+                &[game_seeds],
+            ),
+            1,
+        )?;
+        
+        metadata::create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    mint: ctx.accounts.trophy_mint.to_account_info(),
+                    mint_authority: game_info.clone(),
+                    payer: ctx.accounts.winner.to_account_info(),
+                    update_authority: game_info.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[game_seeds],
+            ),
+            DataV2 {
+                name: TROPHY_NAME.to_string(),
+                symbol: TROPHY_SYMBOL.to_string(),
+                uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            false,
+            true,
+            None,
+        )?;
+        
+        // A zero-supply master edition makes the trophy a one-of-one
+        metadata::create_master_edition_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: ctx.accounts.master_edition.to_account_info(),
+                    mint: ctx.accounts.trophy_mint.to_account_info(),
+                    update_authority: game_info.clone(),
+                    mint_authority: game_info.clone(),
+                    payer: ctx.accounts.winner.to_account_info(),
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[game_seeds],
+            ),
+            Some(0),
+        )?;
+        
+        msg!("🏆 Minted trophy {} to {}", ctx.accounts.trophy_mint.key(), winner);
+        Ok(())
+    }
+
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        
+        leaderboard.bump = ctx.bumps.leaderboard;
+        leaderboard.entries = Vec::new();
+        
+        msg!("🏆 Leaderboard created");
+        Ok(())
+    }
+
+    pub fn arbitrate_game(ctx: Context<ArbitrateGame>, winner: u8) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(
+            matches!(game.state(), GameState::InProgress | GameState::AwaitingReveal),
+            ErrorCode::GameNotStuck
+        );
+        let slot = Clock::get()?.slot;
+        require!(
+            slot > game.last_action_slot.saturating_add(ARBITER_DISPUTE_WINDOW_SLOTS),
+            ErrorCode::DisputeWindowOpen
+        );
+        // A ruling settles the whole series, and a drawn series has nothing to settle it by
+        require!(winner <= 2 && (winner != 0 || game.rules.best_of == 1), ErrorCode::InvalidRuling);
+        
+        // Neither board was proven dishonest, so both bonds go home
+        for (player_num, player) in [(1, &ctx.accounts.player1), (2, &ctx.accounts.player2)] {
+            pay_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                player.to_account_info(),
+                ctx.accounts.game.key(),
+                game.vault_bump,
+                bond_left(game, player_num),
+            )?;
+        }
+        game.bond1_settled = 1;
+        game.bond2_settled = 1;
+        
+        let needed = game.rules.best_of / 2 + 1;
+        match winner {
+            1 => game.round_wins1 = needed - 1,
+            2 => game.round_wins2 = needed - 1,
+            _ => {}
+        }
+        game.winner = winner;
+        game.end_reason = if winner == 0 { 5 } else { 8 }; // A ruled draw settles like an agreed one
+        game.set_state(GameState::Finished);
+        game.draw_offered_by = 0;
+        game.set_pending_shot(None);
+        game.set_pending_salvo(&[]);
+        game.pending_shot_by = Pubkey::default();
+        game.last_action_slot = slot;
+        
+        emit_cpi!(ArbiterRuling {
+            game: ctx.accounts.game.key(),
+            arbiter: ctx.accounts.arbiter.key(),
+            winner,
+            slot,
+        });
+        
+        msg!("⚖️ Arbiter {} ruled game #{}: winner {}", ctx.accounts.arbiter.key(), game.game_id, winner);
+        Ok(())
+    }
+
+    pub fn initialize_cheater_registry(ctx: Context<InitializeCheaterRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        
+        registry.bump = ctx.bumps.registry;
+        registry.entries = Vec::new();
+        
+        msg!("🚨 Cheater registry created");
+        Ok(())
+    }
+
+    pub fn record_offense(ctx: Context<RecordOffense>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.offense_recorded == 0, ErrorCode::OffenseAlreadyRecorded);
+        
+        // A board that contradicted its shot results, or one never revealed past the deadline
+        let offender_num = if game.cheater != 0 {
+            game.cheater
+        } else if game.end_reason == 4 {
+            3 - game.winner
+        } else {
+            return err!(ErrorCode::NoOffense);
+        };
+        let offender = if offender_num == 1 { game.player1 } else { game.player2 };
+        
+        let registry = &mut ctx.accounts.registry;
+        let slot = Clock::get()?.slot;
+        if let Some(entry) = registry.entries.iter_mut().find(|entry| entry.player == offender) {
+            entry.offenses += 1;
+            entry.last_offense_slot = slot;
+        } else {
+            if registry.entries.len() >= MAX_CHEATER_ENTRIES {
+                let lightest = (0..registry.entries.len())
+                    .min_by_key(|&i| (registry.entries[i].offenses, registry.entries[i].last_offense_slot))
+                    .unwrap();
+                registry.entries.swap_remove(lightest);
+            }
+            registry.entries.push(CheaterEntry {
+                player: offender,
+                offenses: 1,
+                last_offense_slot: slot,
+            });
+        }
+        game.offense_recorded = 1;
+        
+        msg!("🚨 Offense recorded against {}", offender);
+        Ok(())
+    }
+
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        tournament_id: u64,
+        entry_fee: u64,
+        max_players: u8,
+        format: u8,
+        swiss_rounds: u8
+    ) -> Result<()> {
+        // Single elimination needs a full power-of-two bracket; Swiss just an even field
+        require!(matches!(max_players, 4 | 8 | 16), ErrorCode::InvalidTournamentSize);
+        require!(
+            match format {
+                0 => swiss_rounds == 0,
+                1 => swiss_rounds >= 1 && swiss_rounds < max_players,
+                _ => false,
+            },
+            ErrorCode::InvalidTournamentFormat
+        );
+        require!(
+            entry_fee == 0 || entry_fee >= Rent::get()?.minimum_balance(0),
+            ErrorCode::WagerTooSmall
+        );
+        
+        let tournament = &mut ctx.accounts.tournament;
+        
+        tournament.organizer = ctx.accounts.organizer.key();
+        tournament.tournament_id = tournament_id;
+        tournament.bump = ctx.bumps.tournament;
+        tournament.vault_bump = ctx.bumps.vault;
+        tournament.entry_fee = entry_fee; // 0 = free to enter, no prize
+        tournament.max_players = max_players;
+        tournament.format = format; // 0 = single elimination, 1 = Swiss
+        tournament.swiss_rounds = swiss_rounds;
+        tournament.entrants = Vec::new();
+        tournament.scores = Vec::new();
+        tournament.state = 0; // 0 = registration, 1 = in progress, 2 = finished
+        tournament.round = 0;
+        tournament.bracket = Vec::new();
+        tournament.round_winners = Vec::new();
+        tournament.round_start_slot = 0;
+        tournament.champion = Pubkey::default();
+        
+        msg!(
+            "🏟️ Tournament #{} created by {} ({} players, entry fee {} lamports)",
+            tournament_id,
+            tournament.organizer,
+            max_players,
+            entry_fee
+        );
+        Ok(())
+    }
+
+    pub fn register(ctx: Context<Register>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let player = ctx.accounts.player.key();
+        
+        require!(tournament.state == 0, ErrorCode::RegistrationClosed);
+        require!(!tournament.entrants.contains(&player), ErrorCode::AlreadyRegistered);
+        
+        if tournament.entry_fee > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                tournament.entry_fee,
+            )?;
+        }
+        
+        tournament.entrants.push(player);
+        tournament.scores.push(0);
+        
+        // The bracket seeds in registration order once the field is full
+        if tournament.entrants.len() == tournament.max_players as usize {
+            tournament.state = 1;
+            tournament.round = 1;
+            tournament.bracket = tournament.entrants.clone();
+            tournament.round_winners = vec![Pubkey::default(); tournament.bracket.len() / 2];
+            tournament.round_start_slot = Clock::get()?.slot;
+            
+            msg!("🏟️ Tournament full - round 1 begins");
+        }
+        
+        msg!("📝 Player {} registered for the tournament", player);
+        Ok(())
+    }
+
+    pub fn report_result(ctx: Context<ReportResult>, match_index: u8) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        let game = &ctx.accounts.game.load()?;
+        let match_index = match_index as usize;
+        
+        require!(tournament.state == 1, ErrorCode::TournamentNotInProgress);
+        require!(match_index < tournament.round_winners.len(), ErrorCode::InvalidMatch);
+        require!(
+            tournament.round_winners[match_index] == Pubkey::default(),
+            ErrorCode::ResultAlreadyReported
+        );
+        
+        // The game must be between this match's pairing and finished during this round
+        let (seat_a, seat_b) = (tournament.bracket[2 * match_index], tournament.bracket[2 * match_index + 1]);
+        require!(
+            (game.player1 == seat_a && game.player2 == seat_b)
+                || (game.player1 == seat_b && game.player2 == seat_a),
+            ErrorCode::InvalidMatch
+        );
+        require!(game.is_over(), ErrorCode::GameNotOver);
+        require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+        require!(game.last_action_slot >= tournament.round_start_slot, ErrorCode::StaleGame);
+        
+        let winner = game_winner(game)?;
+        tournament.round_winners[match_index] = winner;
+        
+        msg!("🏟️ Round {} match {} won by {}", tournament.round, match_index, winner);
+        Ok(())
+    }
+
+    pub fn advance_round(ctx: Context<AdvanceRound>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
+        
+        require!(tournament.state == 1, ErrorCode::TournamentNotInProgress);
+        require!(
+            tournament.round_winners.iter().all(|winner| *winner != Pubkey::default()),
+            ErrorCode::RoundIncomplete
+        );
+        
+        let finished = if tournament.format == 1 {
+            // Swiss: a point per win, then re-pair everyone by the new standings
+            for i in 0..tournament.round_winners.len() {
+                let winner = tournament.round_winners[i];
+                if let Some(seat) = tournament.entrants.iter().position(|entrant| *entrant == winner) {
+                    tournament.scores[seat] += 1;
+                }
+            }
+            tournament.bracket = swiss_standings(tournament);
+            tournament.round >= tournament.swiss_rounds
+        } else {
+            tournament.bracket = tournament.round_winners.clone();
+            tournament.bracket.len() == 1
+        };
+        
+        if !finished {
+            tournament.round += 1;
+            tournament.round_winners = vec![Pubkey::default(); tournament.bracket.len() / 2];
+            tournament.round_start_slot = Clock::get()?.slot;
+            
+            msg!("🏟️ Round {} begins with {} players", tournament.round, tournament.bracket.len());
+            return Ok(());
+        }
+        
+        // Final decided (or top of the Swiss standings) - the champion takes the whole prize pool
+        let champion_key = tournament.bracket[0];
+        tournament.champion = champion_key;
+        tournament.state = 2;
+        
+        let prize = ctx.accounts.vault.lamports();
+        if prize > 0 {
+            let champion = ctx.accounts.champion.as_ref().ok_or(ErrorCode::ChampionAccountRequired)?;
+            require!(champion.key() == champion_key, ErrorCode::ChampionAccountRequired);
+            
+            let tournament_key = tournament.key();
+            let vault_seeds: &[&[u8]] = &[b"tournament_vault", tournament_key.as_ref(), &[tournament.vault_bump]];
+            
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: champion.to_account_info(),
+                    },
+                    &[vault_seeds],
+                ),
+                prize,
+            )?;
+        }
+        
+        msg!("🏆 Player {} wins the tournament and {} lamports!", champion_key, prize);
+        Ok(())
+    }
+
+    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+        
+        // Seed the treasury with its rent-exempt minimum so small fees can always land
+        let rent_minimum = Rent::get()?.minimum_balance(0);
+        let top_up = rent_minimum.saturating_sub(ctx.accounts.treasury.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        
+        let config = &mut ctx.accounts.config;
+        
+        config.admin = ctx.accounts.admin.key();
+        config.fee_bps = fee_bps;
+        config.treasury = ctx.accounts.treasury.key();
+        config.treasury_bump = ctx.bumps.treasury;
+        config.bump = ctx.bumps.config;
+        config.is_paused = false;
+        config.archive_tree = Pubkey::default(); // Set by create_archive_tree
+        config.max_rating_delta = 0; // Set by set_max_rating_delta
+        config.season = 0; // Advanced by rollover_season
+        config.season_started_slot = Clock::get()?.slot;
+        config.referral_rewards_owed = 0;
+        config.pending_admin = Pubkey::default(); // Set by propose_admin_change
+        config.timeout_strike_limit = 0; // Set by set_timeout_strike_limit
+        config.abandon_refund_bps = 0; // Set by set_abandon_refund
+        config.wager_limits = Vec::new(); // Set by set_wager_limits
+        
+        msg!("⚙️ Config created by admin: {} (fee: {} bps)", config.admin, fee_bps);
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // In-flight games are untouched; only new games and joins are blocked
+        config.is_paused = paused;
+        
+        msg!("⚙️ Admin {} set paused = {}", config.admin, paused);
+        Ok(())
+    }
+
+    pub fn rollover_season(ctx: Context<RolloverSeason>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let now = Clock::get()?.slot;
+        
+        // The closing standings are kept in the season's archive; the leaderboard starts over
+        let season = &mut ctx.accounts.season;
+        season.season = config.season;
+        season.started_slot = config.season_started_slot;
+        season.ended_slot = now;
+        season.standings = std::mem::take(&mut leaderboard.entries);
+        season.bump = ctx.bumps.season;
+        
+        config.season += 1;
+        config.season_started_slot = now;
+        
+        msg!("📅 Season {} archived; season {} begins", season.season, config.season);
+        Ok(())
+    }
+
+    pub fn set_max_rating_delta(ctx: Context<SetMaxRatingDelta>, max_rating_delta: u32) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Only affects future pairings; games already matched play on
+        config.max_rating_delta = max_rating_delta;
+        
+        msg!("⚙️ Admin {} set max rating delta = {}", config.admin, max_rating_delta);
+        Ok(())
+    }
+
+    pub fn set_timeout_strike_limit(ctx: Context<SetTimeoutStrikeLimit>, timeout_strike_limit: u8) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Games keep the limit they were created with
+        config.timeout_strike_limit = timeout_strike_limit;
+        
+        msg!("⚙️ Admin {} set timeout strike limit = {}", config.admin, timeout_strike_limit);
+        Ok(())
+    }
+
+    pub fn allow_wager_mint(ctx: Context<AllowWagerMint>) -> Result<()> {
+        let allowed_mint = &mut ctx.accounts.allowed_mint;
+        allowed_mint.mint = ctx.accounts.mint.key();
+        allowed_mint.bump = ctx.bumps.allowed_mint;
+        
+        msg!("⚙️ Admin {} allowed wagers in mint {}", ctx.accounts.admin.key(), allowed_mint.mint);
+        Ok(())
+    }
+
+    pub fn disallow_wager_mint(ctx: Context<DisallowWagerMint>) -> Result<()> {
+        // Games already escrowing the mint play on; only new ones are refused
+        msg!("⚙️ Admin {} disallowed wagers in mint {}", ctx.accounts.admin.key(), ctx.accounts.allowed_mint.mint);
+        Ok(())
+    }
+
+    pub fn set_wager_limits(
+        ctx: Context<SetWagerLimits>,
+        mint: Pubkey,
+        min_wager: u64,
+        max_wager: u64
+    ) -> Result<()> {
+        require!(max_wager == 0 || min_wager <= max_wager, ErrorCode::InvalidWagerLimits);
+        
+        // Default mint = lamport wagers; zero for both limits lifts them
+        let config = &mut ctx.accounts.config;
+        config.wager_limits.retain(|limit| limit.mint != mint);
+        if min_wager > 0 || max_wager > 0 {
+            require!(config.wager_limits.len() < MAX_WAGER_LIMITS, ErrorCode::TooManyWagerLimits);
+            config.wager_limits.push(WagerLimit { mint, min_wager, max_wager });
+        }
+        
+        msg!("⚙️ Admin {} set wager limits for {}: {} to {}", config.admin, mint, min_wager, max_wager);
+        Ok(())
+    }
+
+    pub fn set_abandon_refund(ctx: Context<SetAbandonRefund>, abandon_refund_bps: u16) -> Result<()> {
+        require!(abandon_refund_bps <= MAX_ABANDON_REFUND_BPS, ErrorCode::AbandonRefundTooHigh);
+        
+        let config = &mut ctx.accounts.config;
+        config.abandon_refund_bps = abandon_refund_bps;
+        
+        msg!("⚙️ Admin {} set abandon refund = {} bps", config.admin, abandon_refund_bps);
+        Ok(())
+    }
+
+    pub fn propose_admin_change(ctx: Context<ProposeAdminChange>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Nothing changes until the new admin accepts; proposing the default key withdraws the offer
+        config.pending_admin = new_admin;
+        
+        msg!("⚙️ Admin {} proposed {} as the new admin", config.admin, new_admin);
+        Ok(())
+    }
+
+    pub fn accept_admin_change(ctx: Context<AcceptAdminChange>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        let previous = config.admin;
+        config.admin = config.pending_admin;
+        config.pending_admin = Pubkey::default();
+        
+        msg!("⚙️ Admin handed over from {} to {}", previous, config.admin);
+        Ok(())
+    }
+
+    pub fn create_archive_tree(ctx: Context<CreateArchiveTree>, max_depth: u32, max_buffer_size: u32) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // The client allocates the tree account; the config PDA becomes its sole authority
+        let mut data = Vec::with_capacity(16);
+        data.extend_from_slice(&max_depth.to_le_bytes());
+        data.extend_from_slice(&max_buffer_size.to_le_bytes());
+        invoke_compression(
+            "init_empty_merkle_tree",
+            &data,
+            &ctx.accounts.merkle_tree,
+            config,
+            &ctx.accounts.noop_program,
+            &ctx.accounts.compression_program,
+        )?;
+        config.archive_tree = ctx.accounts.merkle_tree.key();
+        
+        msg!(
+            "🗄️ Archive tree {} created (depth {}, buffer {})",
+            config.archive_tree,
+            max_depth,
+            max_buffer_size
+        );
+        Ok(())
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        
+        // The treasury keeps its rent-exempt minimum and what referrers are owed
+        let available = ctx
+            .accounts
+            .treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0))
+            .saturating_sub(config.referral_rewards_owed);
+        require!(amount <= available, ErrorCode::InsufficientTreasury);
+        
+        let treasury_seeds: &[&[u8]] = &[b"treasury", &[config.treasury_bump]];
+        
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.treasury.to_account_info(),
+                    to: ctx.accounts.admin.to_account_info(),
+                },
+                &[treasury_seeds],
+            ),
+            amount,
+        )?;
+        
+        msg!("🏦 Admin withdrew {} lamports from the treasury", amount);
+        Ok(())
+    }
+
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let amount = ctx.accounts.profile.referral_rewards;
+        require!(amount > 0, ErrorCode::NoReferralRewards);
+        
+        let treasury_seeds: &[&[u8]] = &[b"treasury", &[ctx.accounts.config.treasury_bump]];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.treasury.to_account_info(),
+                    to: ctx.accounts.player.to_account_info(),
+                },
+                &[treasury_seeds],
+            ),
+            amount,
+        )?;
+        
+        ctx.accounts.profile.referral_rewards = 0;
+        ctx.accounts.config.referral_rewards_owed -= amount;
+        
+        msg!("🎁 Player {} claimed {} lamports in referral rewards", ctx.accounts.player.key(), amount);
+        Ok(())
+    }
+
+    pub fn withdraw_treasury_spl(ctx: Context<WithdrawTreasurySpl>, amount: u64) -> Result<()> {
+        let treasury_seeds: &[&[u8]] = &[b"treasury", &[ctx.accounts.config.treasury_bump]];
+        
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.treasury_token_account.to_account_info(),
+                    to: ctx.accounts.admin_token_account.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+                &[treasury_seeds],
+            ),
+            amount,
+        )?;
+        
+        msg!("🏦 Admin withdrew {} tokens of mint {} from the treasury", amount, ctx.accounts.mint.key());
+        Ok(())
+    }
+
+    pub fn refresh_leaderboard_entry(ctx: Context<RefreshLeaderboardEntry>) -> Result<()> {
+        // Last season's stats belong in its archive, not the current standings
+        require!(ctx.accounts.profile.season == ctx.accounts.config.season, ErrorCode::StaleSeason);
+        update_leaderboard(&mut ctx.accounts.leaderboard, &ctx.accounts.profile);
+        
+        msg!(
+            "🏆 Leaderboard refreshed for {} (season rating {})",
+            ctx.accounts.profile.wallet,
+            ctx.accounts.profile.season_rating
+        );
+        Ok(())
+    }
+
+    pub fn open_betting_pool(ctx: Context<OpenBettingPool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        
+        pool.game = ctx.accounts.game.key();
+        pool.opener = ctx.accounts.opener.key();
+        pool.bump = ctx.bumps.pool;
+        pool.total_on_player1 = 0;
+        pool.total_on_player2 = 0;
+        
+        msg!("🎟️ Spectator betting opened for game {}", pool.game);
+        Ok(())
+    }
+
+    // The marker PDA makes each wallet count once, so the audience figure can be trusted
+    pub fn register_spectator(ctx: Context<RegisterSpectator>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        require!(!game.is_over(), ErrorCode::GameOver);
+        
+        let marker = &mut ctx.accounts.marker;
+        marker.game = ctx.accounts.game.key();
+        marker.spectator = ctx.accounts.spectator.key();
+        marker.bump = ctx.bumps.marker;
+        game.spectators += 1;
+        
+        emit_cpi!(SpectatorJoined {
+            game: marker.game,
+            spectator: marker.spectator,
+            spectators: game.spectators,
+        });
+        Ok(())
+    }
+
+    // Also reclaims the marker's rent after the game is closed, when there's no count left to update
+    pub fn unregister_spectator(ctx: Context<UnregisterSpectator>) -> Result<()> {
+        let mut spectators = 0;
+        let game_info = ctx.accounts.game.to_account_info();
+        if game_info.owner == &crate::ID {
+            let mut data = game_info.try_borrow_mut_data()?;
+            require!(
+                data.len() >= Game::LEN && data[..8] == <Game as anchor_lang::Discriminator>::DISCRIMINATOR,
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            let game: &mut Game = bytemuck::from_bytes_mut(&mut data[8..Game::LEN]);
+            game.spectators = game.spectators.saturating_sub(1);
+            spectators = game.spectators;
+        }
+        
+        emit_cpi!(SpectatorLeft {
+            game: ctx.accounts.marker.game,
+            spectator: ctx.accounts.spectator.key(),
+            spectators,
+        });
+        Ok(())
+    }
+
+    pub fn place_spectator_bet(ctx: Context<PlaceSpectatorBet>, side: u8, amount: u64) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        let bettor = ctx.accounts.bettor.key();
+        
+        require!(game.state() != GameState::WaitingForOpponent, ErrorCode::GameNotReady);
+        require!(matches!(side, 1 | 2), ErrorCode::InvalidBetSide);
+        require!(amount > 0, ErrorCode::NoWager);
+        require!(bettor != game.player1 && bettor != game.player2, ErrorCode::PlayersCannotBet);
+        // Betting closes at the first shot, before anyone learns anything about the boards
+        require!(
+            game.state() == GameState::InProgress
+                && shots_taken(game, [&*ctx.accounts.board1, &*ctx.accounts.board2]) == 0
+                && game.pending_shot().is_none()
+                && game.pending_salvo().is_empty(),
+            ErrorCode::BettingClosed
+        );
+        
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.bettor.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let bet = &mut ctx.accounts.bet;
+        bet.bettor = bettor;
+        bet.game = ctx.accounts.game.key();
+        bet.side = side;
+        bet.amount = amount;
+        bet.bump = ctx.bumps.bet;
+        
+        let pool = &mut ctx.accounts.pool;
+        if side == 1 {
+            pool.total_on_player1 += amount;
+        } else {
+            pool.total_on_player2 += amount;
+        }
+        game.spectator_bets += 1;
+        
+        msg!("🎟️ Spectator {} bet {} lamports on player {}", bettor, amount, side);
+        Ok(())
+    }
+
+    pub fn settle_spectator_bets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleSpectatorBets<'info>>
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        let pool = &ctx.accounts.pool;
+        
+        // A game voided, or aborted with neither board revealed, has no result to bet on
+        let voided = matches!(game.state(), GameState::Aborted | GameState::Cancelled);
+        require!(game.is_over() || voided, ErrorCode::GameNotOver);
+        // Reveals settle cheating and reveal forfeits, so only then is the result final
+        require!(game.state() == GameState::Finished || voided, ErrorCode::BoardsNotRevealed);
+        
+        let total = pool.total_on_player1 + pool.total_on_player2;
+        let winning_total = match game.winner {
+            _ if voided => 0,
+            1 => pool.total_on_player1,
+            2 => pool.total_on_player2,
+            _ => 0,
+        };
+        
+        // remaining_accounts come in (bet, bettor) pairs; each bet closes to its bettor
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(pairs.remainder().is_empty(), ErrorCode::InvalidBetAccounts);
+        for pair in pairs {
+            let bet: Account<'info, SpectatorBet> = Account::try_from(&pair[0])?;
+            let bettor = &pair[1];
+            require!(
+                bet.game == ctx.accounts.game.key() && bet.bettor == bettor.key(),
+                ErrorCode::InvalidBetAccounts
+            );
+            
+            // Winning backers split the whole pool pro rata; with no winner (a draw or a
+            // voided game) or nobody backing the winner, every stake is refunded
+            let payout = if winning_total == 0 {
+                bet.amount
+            } else if bet.side == game.winner {
+                (bet.amount as u128 * total as u128 / winning_total as u128) as u64
+            } else {
+                0
+            };
+            
+            ctx.accounts.pool.sub_lamports(payout)?;
+            bettor.add_lamports(payout)?;
+            bet.close(bettor.clone())?;
+            game.spectator_bets -= 1;
+            
+            msg!("🎟️ Settled bet of {} lamports for {}: paid {}", bet.amount, bettor.key(), payout);
+        }
+        
+        // Once every bet is paid, the pool's rent and rounding dust go back to its opener
+        if game.spectator_bets == 0 {
+            ctx.accounts.pool.close(ctx.accounts.opener.to_account_info())?;
+        }
+        
+        Ok(())
+    }
+
+    pub fn initialize_ffa_game(
+        ctx: Context<InitializeFfaGame>,
+        game_id: u64,
+        max_players: u8,
+        teams: bool,
+        wager: u64,
+        board_commitment: [u8; 32]
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        require!(matches!(max_players, 3 | 4), ErrorCode::InvalidPlayerCount);
+        require!(!teams || max_players == 4, ErrorCode::InvalidPlayerCount);
+        
+        // An empty vault must end up rent-exempt, so tiny wagers are rejected
+        require!(
+            wager == 0 || wager >= Rent::get()?.minimum_balance(0),
+            ErrorCode::WagerTooSmall
+        );
+        check_wager_limits(&ctx.accounts.config, Pubkey::default(), wager)?;
+        
+        if wager > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                wager,
+            )?;
+        }
+        
+        let game = &mut ctx.accounts.game.load_init()?;
+        
+        game.game_id = game_id;
+        game.players[0] = ctx.accounts.player.key();
+        game.referrers[0] = referrer_of(&ctx.accounts.player_profile);
+        game.board_commits[0] = board_commitment;
+        game.max_players = max_players;
+        game.teams = teams as u8; // Seats 0 and 2 play against seats 1 and 3
+        game.wager = wager; // 0 = no stakes
+        game.vault_bump = ctx.bumps.vault;
+        game.player_count = 1;
+        game.set_state(GameState::WaitingForOpponent);
+        game.last_action_slot = Clock::get()?.slot;
+        game.bump = ctx.bumps.game;
+        
+        msg!(
+            "⚓ New {}-player {} #{} initialized by player: {} (wager: {} lamports)",
+            max_players,
+            if teams { "team game" } else { "free-for-all" },
+            game_id,
+            game.players[0],
+            wager
+        );
+        Ok(())
+    }
+
+    pub fn join_ffa_game(ctx: Context<JoinFfaGame>, board_commitment: [u8; 32]) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        
+        let game = &mut ctx.accounts.game.load_mut()?;
+        let player = ctx.accounts.player.key();
+        
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        require!(
+            !game.players[..game.player_count as usize].contains(&player),
+            ErrorCode::CannotPlayAgainstYourself
+        );
+        
+        if game.wager > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                game.wager,
+            )?;
+        }
+        
+        let index = game.player_count as usize;
+        game.players[index] = player;
+        game.referrers[index] = referrer_of(&ctx.accounts.player_profile);
+        game.board_commits[index] = board_commitment;
+        game.player_count += 1;
+        game.last_action_slot = Clock::get()?.slot;
+        
+        // The creator fires first once every seat is taken
+        if game.player_count == game.max_players {
+            game.set_state(GameState::InProgress);
+            msg!("🎮 Free-for-all is full! Player {} goes first", game.players[0]);
+        } else {
+            msg!("🎮 Player {} joined the free-for-all ({}/{})", player, game.player_count, game.max_players);
+        }
+        Ok(())
+    }
+
+    pub fn fire_ffa_shot(ctx: Context<FireFfaShot>, target: u8, x: u8, y: u8) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_ffa_in_progress(game)?;
+        require!(game.has_pending_shot == 0, ErrorCode::ShotPending);
+        require!(x < 10 && y < 10, ErrorCode::InvalidCoordinate);
+        let shooter = ffa_shooter(game);
+        require!(ctx.accounts.player.key() == game.players[shooter], ErrorCode::NotYourTurn);
+        
+        // Any player still afloat other than the shooter can be targeted; in team games
+        // only the opposing pair
+        require!(
+            target < game.player_count
+                && target as usize != shooter
+                && game.eliminated[target as usize] == 0
+                && (game.teams == 0 || target % 2 != shooter as u8 % 2),
+            ErrorCode::InvalidTarget
+        );
+        
+        let coordinate_index = (y as usize) * 10 + (x as usize);
+        require!(
+            hit_cell(&game.board_hits[target as usize], coordinate_index) == 0,
+            ErrorCode::AlreadyShotHere
+        );
+        
+        game.pending_shot = [x, y];
+        game.pending_target = target;
+        game.has_pending_shot = 1;
+        game.last_action_slot = Clock::get()?.slot;
+        
+        msg!(
+            "💥 Player {} fired at player {}'s coordinate ({}, {})",
+            game.players[shooter],
+            game.players[target as usize],
+            x,
+            y
+        );
+        Ok(())
+    }
+
+    pub fn reveal_ffa_shot_result(
+        ctx: Context<RevealFfaShotResult>,
+        ship_id: u8,
+        cell_salt: [u8; 32],
+        proof: Vec<[u8; 32]>
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_ffa_in_progress(game)?;
+        require!(game.has_pending_shot != 0, ErrorCode::NoPendingShot);
+        
+        let target = game.pending_target as usize;
+        require!(ctx.accounts.player.key() == game.players[target], ErrorCode::NotDefender);
+        
+        // Every free-for-all board is 10x10 with the standard fleet, so no ship takes more hits
+        // than it has cells
+        require!((ship_id as usize) <= STANDARD_FLEET.len(), ErrorCode::InvalidShipId);
+        require!(
+            ship_id == 0 || game.ship_hits[target][(ship_id - 1) as usize] < STANDARD_FLEET[(ship_id - 1) as usize],
+            ErrorCode::InvalidShipId
+        );
+        let [x, y] = game.pending_shot;
+        let coordinate_index = (y as usize) * 10 + (x as usize);
+        let hash = CommitHash::Sha256;
+        let leaf = cell_leaf(hash, coordinate_index, ship_id, &cell_salt);
+        let root = merkle_root_from_proof(hash, leaf, coordinate_index, &proof);
+        require!(
+            proof.len() == merkle_depth(100)
+                && bound_commitment(hash, root, &game_key, &game.players[target]) == game.board_commits[target],
+            ErrorCode::InvalidCellProof
+        );
+        
+        let shooter = game.players[ffa_shooter(game)];
+        if ship_id != 0 {
+            set_hit_cell(&mut game.board_hits[target], coordinate_index, 2); // 2 = hit
+            game.hits_count[target] += 1;
+            msg!("🎯 HIT! Player {} hit player {}'s ship!", shooter, game.players[target]);
+            
+            let ship = (ship_id - 1) as usize;
+            game.ship_hits[target][ship] += 1;
+            if game.ship_hits[target][ship] == STANDARD_FLEET[ship] {
+                emit_cpi!(ShipSunk {
+                    game: game_key,
+                    owner: game.players[target],
+                    ship_id,
+                    ship_size: STANDARD_FLEET[ship],
+                    spectators: 0,
+                });
+            }
+            
+            if game.hits_count[target] as usize >= STANDARD_FLEET.iter().map(|&size| size as usize).sum::<usize>() {
+                eliminate_ffa_player(game, game_key, target, events)?;
+            }
+        } else {
+            set_hit_cell(&mut game.board_hits[target], coordinate_index, 1); // 1 = miss
+            msg!("💦 MISS! Player {} missed.", shooter);
+        }
+        
+        game.has_pending_shot = 0;
+        game.pending_shot = [0; 2];
+        game.last_action_slot = Clock::get()?.slot;
+        advance_ffa_turn(game);
+        
+        Ok(())
+    }
+
+    pub fn eliminate_idle_ffa_player(ctx: Context<EliminateIdleFfaPlayer>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_ffa_in_progress(game)?;
+        
+        let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+        require!(elapsed > TURN_TIMEOUT_SLOTS, ErrorCode::TimeoutNotReached);
+        
+        // Whoever owes the next action is out: the target of a pending shot, else the shooter
+        let stalling = if game.has_pending_shot != 0 {
+            game.pending_target as usize
+        } else {
+            ffa_shooter(game)
+        };
+        
+        msg!("⏰ Player {} eliminated after {} idle slots", game.players[stalling], elapsed);
+        eliminate_ffa_player(game, game_key, stalling, events)?;
+        
+        game.has_pending_shot = 0;
+        game.pending_shot = [0; 2];
+        game.last_action_slot = Clock::get()?.slot;
+        advance_ffa_turn(game);
+        
+        Ok(())
+    }
+
+    pub fn reveal_ffa_board(
+        ctx: Context<RevealFfaBoard>,
+        original_board: Vec<u8>,
+        salt: [u8; 32]
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let player = ctx.accounts.player.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(
+            matches!(game.state(), GameState::Finished | GameState::Cancelled),
+            ErrorCode::GameNotOver
+        );
+        require!(
+            Clock::get()?.slot <= game.last_action_slot.saturating_add(REVEAL_DEADLINE_SLOTS),
+            ErrorCode::RevealWindowClosed
+        );
+        let seat = game.players[..game.player_count as usize]
+            .iter()
+            .position(|&seated| seated == player)
+            .ok_or(ErrorCode::NotAPlayer)?;
+        require!(game.revealed[seat] == 0, ErrorCode::AlreadyRevealed);
+        require!(original_board.len() == 100, ErrorCode::InvalidBoardSize);
+        
+        let hash = CommitHash::Sha256;
+        let computed_root = board_merkle_root(hash, &original_board, &salt);
+        require!(
+            bound_commitment(hash, computed_root, &game_key, &player) == game.board_commits[seat],
+            ErrorCode::CommitmentMismatch
+        );
+        
+        // The board is bound to the commitment, so a fleet that isn't the standard one, or a
+        // shot result the board contradicts, is proof of cheating rather than a bad argument
+        let (ships, mines) = board_masks(&original_board);
+        let ship_count = original_board.iter().filter(|&&cell| cell != 0).count();
+        let honest = ship_count == STANDARD_FLEET.iter().map(|&size| size as usize).sum::<usize>()
+            && mines.iter().all(|&word| word == 0)
+            && validate_fleet_placement(&original_board, 10, &STANDARD_FLEET, false)
+            && shots_match_board(&game.board_hits[seat], &ships, &mines);
+        
+        if honest {
+            game.revealed[seat] = 1;
+            msg!("📋 Player {} board revealed and verified!", player);
+            return Ok(());
+        }
+        
+        game.revealed[seat] = 2;
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        events.emit(CheatingDetected {
+            game: game_key,
+            cheater: player,
+        })?;
+        
+        // A cheat on the winning side voids the result, and every honest board gets its stake back
+        if game.state() == GameState::Finished && ffa_winning_seats(game).contains(&seat) {
+            game.winner = 0;
+            game.set_state(GameState::Cancelled);
+            msg!("🚨 Winner {} cheated! The game is void.", player);
+        } else {
+            msg!("🚨 Player {} cheated!", player);
+        }
+        Ok(())
+    }
+
+    pub fn claim_ffa_winnings<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimFfaWinnings<'info>>
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.state() == GameState::Finished, ErrorCode::GameNotOver);
+        require!(game.wager > 0, ErrorCode::NoWager);
+        require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
+        
+        // The pot waits until every winning board has been opened and checked
+        require!(
+            ffa_winning_seats(game).iter().all(|&seat| game.revealed[seat] == 1),
+            ErrorCode::BoardNotRevealed
+        );
+        
+        // In team games either winner claims, and the pot is split with their teammate
+        let winner = (game.winner - 1) as usize;
+        let signer = ctx.accounts.player.key();
+        let teammate = if game.teams != 0 {
+            let seat = if signer == game.players[winner] { winner } else { (winner + 2) % 4 };
+            require!(signer == game.players[seat], ErrorCode::NotWinner);
+            let teammate = ctx.accounts.teammate.as_ref().ok_or(ErrorCode::WrongTeammate)?;
+            require!(teammate.key() == game.players[(seat + 2) % 4], ErrorCode::WrongTeammate);
+            Some(teammate)
+        } else {
+            require!(signer == game.players[winner], ErrorCode::NotWinner);
+            None
+        };
+        
+        // Pay out the whole vault so it closes cleanly
+        let pot = ctx.accounts.vault.lamports();
+        let fee = protocol_fee(&ctx.accounts.config, pot);
+        let teammate_share = if teammate.is_some() { (pot - fee) / 2 } else { 0 };
+        let game_key = ctx.accounts.game.key();
+        
+        // The rake goes to the treasury first, then the teammate's half, the rest to the signer
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.treasury.to_account_info(),
+            game_key,
+            game.vault_bump,
+            fee,
+        )?;
+        if let Some(teammate) = teammate {
+            pay_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                teammate.to_account_info(),
+                game_key,
+                game.vault_bump,
+                teammate_share,
+            )?;
+        }
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player.to_account_info(),
+            game_key,
+            game.vault_bump,
+            pot - fee - teammate_share,
+        )?;
+        
+        // Each seat's stake generated an equal part of the rake; a referred seat's part pays its
+        // referrer a share. remaining_accounts hold the profile of every referrer stamped on the game
+        let referral_share = (fee / game.player_count as u64) * REFERRAL_SHARE_BPS / 10_000;
+        let mut referrer_profiles = ctx
+            .remaining_accounts
+            .iter()
+            .map(Account::<PlayerProfile>::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        for referrer in game.referrers.into_iter().filter(|referrer| *referrer != Pubkey::default()) {
+            let referrer_profile = referrer_profiles
+                .iter_mut()
+                .find(|profile| profile.wallet == referrer)
+                .ok_or(ErrorCode::ReferrerAccountRequired)?;
+            let address = Pubkey::create_program_address(
+                &[b"profile", referrer.as_ref(), &[referrer_profile.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| ErrorCode::ReferrerAccountRequired)?;
+            require!(referrer_profile.key() == address, ErrorCode::ReferrerAccountRequired);
+            referrer_profile.referral_rewards += referral_share;
+            ctx.accounts.config.referral_rewards_owed += referral_share;
+        }
+        for referrer_profile in referrer_profiles {
+            referrer_profile.exit(ctx.program_id)?;
+        }
+        
+        game.winnings_claimed = 1;
+        
+        msg!("💰 Player {} claimed {} lamports in winnings ({} fee)!", signer, pot - fee, fee);
+        Ok(())
+    }
+
+    pub fn claim_ffa_refund(ctx: Context<ClaimFfaRefund>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        let player = ctx.accounts.player.key();
+        
+        require!(game.wager > 0, ErrorCode::NoWager);
+        require!(ffa_voided(game, Clock::get()?.slot), ErrorCode::GameNotVoided);
+        let seat = game.players[..game.player_count as usize]
+            .iter()
+            .position(|&seated| seated == player)
+            .ok_or(ErrorCode::NotAPlayer)?;
+        
+        // Only a board shown to be honest earns its stake back; a cheater's stays in the vault
+        require!(game.revealed[seat] == 1, ErrorCode::BoardNotRevealed);
+        require!(game.refunded[seat] == 0, ErrorCode::WinningsAlreadyClaimed);
+        
+        pay_from_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.vault,
+            ctx.accounts.player.to_account_info(),
+            ctx.accounts.game.key(),
+            game.vault_bump,
+            game.wager,
+        )?;
+        game.refunded[seat] = 1;
+        
+        msg!("↩️ Player {} reclaimed their {} lamport stake", player, game.wager);
+        Ok(())
+    }
+
+    pub fn close_ffa_game(ctx: Context<CloseFfaGame>) -> Result<()> {
+        let game = &ctx.accounts.game.load()?;
+        
+        // The creator can back out before anyone joins, or tidy up once the pot is settled: paid
+        // to the winners, or left over from a voided game once the reveal window has closed
+        let unjoined = game.state() == GameState::WaitingForOpponent && game.player_count == 1;
+        let over = matches!(game.state(), GameState::Finished | GameState::Cancelled);
+        require!(unjoined || over, ErrorCode::GameNotOver);
+        let slot = Clock::get()?.slot;
+        let voided = over
+            && slot > game.last_action_slot.saturating_add(REVEAL_DEADLINE_SLOTS)
+            && ffa_voided(game, slot);
+        require!(
+            game.wager == 0 || unjoined || game.winnings_claimed != 0 || voided,
+            ErrorCode::WinningsNotClaimed
+        );
+        
+        // Backing out refunds the creator's stake; the stakes of cheaters and of anyone who never
+        // revealed go to the treasury
+        if unjoined && game.wager > 0 {
+            pay_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                ctx.accounts.creator.to_account_info(),
+                ctx.accounts.game.key(),
+                game.vault_bump,
+                ctx.accounts.vault.lamports(),
+            )?;
+        } else if voided && ctx.accounts.vault.lamports() > 0 {
+            pay_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.game.key(),
+                game.vault_bump,
+                ctx.accounts.vault.lamports(),
+            )?;
+        }
+        
+        msg!("🧹 Free-for-all #{} closed", game.game_id);
+        Ok(())
+    }
+
+    pub fn enter_queue(ctx: Context<EnterQueue>, wager: u64, rating_band: u8) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        require!(
+            wager == 0 || wager >= Rent::get()?.minimum_balance(0),
+            ErrorCode::WagerTooSmall
+        );
+        check_wager_limits(&ctx.accounts.config, Pubkey::default(), wager)?;
+        
+        // The ticket escrows everything the match will need: stake, bond and half the game's rent
+        let escrow = wager + HONESTY_BOND_LAMPORTS + matched_game_rent_share()?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.player.to_account_info(),
+                    to: ctx.accounts.ticket.to_account_info(),
+                },
+            ),
+            escrow,
+        )?;
+        
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.player = ctx.accounts.player.key();
+        ticket.wager = wager;
+        ticket.rating_band = rating_band;
+        ticket.entered_slot = Clock::get()?.slot;
+        ticket.bump = ctx.bumps.ticket;
+        
+        msg!("⏳ Player {} queued for a match (wager: {} lamports, band {})", ticket.player, wager, rating_band);
+        Ok(())
+    }
+
+    pub fn leave_queue(ctx: Context<LeaveQueue>) -> Result<()> {
+        // Closing the ticket hands its whole escrow back
+        msg!("🚪 Player {} left the queue", ctx.accounts.player.key());
+        Ok(())
+    }
+
+    pub fn match_players(ctx: Context<MatchPlayers>, game_id: u64) -> Result<()> {
+        require!(!ctx.accounts.config.is_paused, ErrorCode::Paused);
+        
+        let (ticket1, ticket2) = (&ctx.accounts.ticket1, &ctx.accounts.ticket2);
+        require!(
+            ticket1.wager == ticket2.wager && ticket1.rating_band == ticket2.rating_band,
+            ErrorCode::QueueMismatch
+        );
+        require!(
+            ratings_within(&ctx.accounts.config, &ctx.accounts.profile1, &ctx.accounts.profile2),
+            ErrorCode::RatingGapTooWide
+        );
+        
+        // Stakes and bonds go to the vault; the rent shares pay back whoever fronted the game account
+        let stake = ticket1.wager + HONESTY_BOND_LAMPORTS;
+        let rent_share = matched_game_rent_share()?;
+        for ticket in [ticket1.to_account_info(), ticket2.to_account_info()] {
+            ticket.sub_lamports(stake + rent_share)?;
+            ctx.accounts.vault.add_lamports(stake)?;
+            ctx.accounts.keeper.add_lamports(rent_share)?;
+        }
+        
+        let game = &mut ctx.accounts.game.load_init()?;
+        
+        // Boards are committed once the game's address is known, so each binds to this game
+        init_game_state(game, ticket1.player, game_id, ctx.bumps.game);
+        open_board(&mut ctx.accounts.board1, ctx.accounts.game.key(), 1, [0; 32], ctx.bumps.board1);
+        open_board(&mut ctx.accounts.board2, ctx.accounts.game.key(), 2, [0; 32], ctx.bumps.board2);
+        game.player2 = ticket2.player;
+        game.wager = ticket1.wager;
+        game.vault_bump = ctx.bumps.vault;
+        game.bond = HONESTY_BOND_LAMPORTS;
+        game.ranked = 1; // The queue pairs by rating, so its games count
+        // Ticket order is the keeper's pick, so VRF randomness decides who moves first
+        game.rules.vrf_first_turn = 1;
+        game.season = ctx.accounts.config.season;
+        game.strike_limit = ctx.accounts.config.timeout_strike_limit;
+        game.abandon_refund_bps = ctx.accounts.config.abandon_refund_bps;
+        game.last_action_slot = Clock::get()?.slot;
+        
+        emit_cpi!(PlayersMatched {
+            game: ctx.accounts.game.key(),
+            player1: game.player1,
+            player2: game.player2,
+            wager: game.wager,
+        });
+        
+        msg!("🤝 Matched {} with {} in game #{}", game.player1, game.player2, game_id);
+        Ok(())
+    }
+
+    pub fn commit_matched_board(ctx: Context<CommitMatchedBoard>, board_commitment: [u8; 32]) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        require!(game.player2 != Pubkey::default(), ErrorCode::NotAMatchedGame);
+        
+        let player = ctx.accounts.player.key();
+        let board = if player == game.player1 { &mut ctx.accounts.board1 } else { &mut ctx.accounts.board2 };
+        require!(board.commitment == [0u8; 32], ErrorCode::BoardAlreadyCommitted);
+        board.commitment = board_commitment;
+        
+        // The game starts once both boards are in, waiting on VRF for the first turn
+        if ctx.accounts.board1.commitment != [0u8; 32] && ctx.accounts.board2.commitment != [0u8; 32] {
+            game.set_state(GameState::InProgress);
+            game.turn_pending = 1;
+            msg!("🚢 Both boards committed! Game is now active.");
+        }
+        game.last_action_slot = Clock::get()?.slot;
+        
+        Ok(())
+    }
+
+    pub fn cancel_match(ctx: Context<CancelMatch>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        require!(game.player2 != Pubkey::default(), ErrorCode::NotAMatchedGame);
+        
+        // A player who has done their part - committed their board and, in a VARIANT_ZK_FLEET
+        // game, proven their fleet - can walk away once the other has sat on theirs too long
+        let (board, proven) = if ctx.accounts.player.key() == game.player1 {
+            (&ctx.accounts.board1, game.fleet_proven1)
+        } else {
+            (&ctx.accounts.board2, game.fleet_proven2)
+        };
+        require!(
+            board.commitment != [0u8; 32] && (game.rules.variants & VARIANT_ZK_FLEET == 0 || proven != 0),
+            ErrorCode::SetupNotDone
+        );
+        
+        let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+        require!(elapsed > TURN_TIMEOUT_SLOTS, ErrorCode::TimeoutNotReached);
+        
+        game.set_state(GameState::Cancelled);
+        
+        let refund = game.wager + game.bond;
+        for player in [ctx.accounts.player1.to_account_info(), ctx.accounts.player2.to_account_info()] {
+            pay_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                player,
+                game_key,
+                game.vault_bump,
+                refund,
+            )?;
+        }
+        
+        // Each player fronted half the rent, so each gets half back
+        let game_info = ctx.accounts.game.to_account_info();
+        let player2_share = game_info.lamports() / 2;
+        game_info.sub_lamports(player2_share)?;
+        ctx.accounts.player2.add_lamports(player2_share)?;
+        
+        msg!("🗑️ Match cancelled, {} lamports refunded to each player", refund);
+        Ok(())
+    }
+
+    pub fn init_board_commitments(ctx: Context<InitBoardCommitments>) -> Result<()> {
+        let board_commitments = &mut ctx.accounts.board_commitments.load_init()?;
+        
+        board_commitments.game = ctx.accounts.game.key();
+        board_commitments.player = ctx.accounts.player.key();
+        board_commitments.bump = ctx.bumps.board_commitments;
+        
+        msg!("🧾 Board hash account created for {}", board_commitments.player);
+        Ok(())
+    }
+
+    pub fn upload_cell_hashes(ctx: Context<UpdateBoardCommitments>, offset: u16, leaves: Vec<[u8; 32]>) -> Result<()> {
+        let cells = ctx.accounts.game.load()?.rules.cells();
+        let board_commitments = &mut ctx.accounts.board_commitments.load_mut()?;
+        
+        // A whole board doesn't fit in one transaction, so hashes arrive in slices
+        let start = offset as usize;
+        require!(start + leaves.len() <= cells, ErrorCode::InvalidCoordinate);
+        board_commitments.leaves[start..start + leaves.len()].copy_from_slice(&leaves);
+        // Any change has to be checked against the commitment again
+        board_commitments.commitment = [0; 32];
+        
+        msg!("🧾 Uploaded cell hashes {}..{}", start, start + leaves.len());
+        Ok(())
+    }
+
+    pub fn seal_board_commitments(ctx: Context<UpdateBoardCommitments>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let game = ctx.accounts.game.load()?;
+        let board_commitments = &mut ctx.accounts.board_commitments.load_mut()?;
+        
+        let player = board_commitments.player;
+        let commitment = if player == game.player1 { ctx.accounts.board1.commitment } else { ctx.accounts.board2.commitment };
+        let hash = game.rules.commit_hash();
+        let root = merkle_root_from_leaves(hash, &board_commitments.leaves[..game.rules.cells()]);
+        require!(
+            bound_commitment(hash, root, &game_key, &player) == commitment,
+            ErrorCode::CellHashesMismatch
+        );
+        board_commitments.commitment = commitment;
+        
+        msg!("🧾 Cell hashes sealed against {}'s board commitment", player);
+        Ok(())
+    }
+
+    pub fn reveal_shot_result_stored(
+        ctx: Context<RevealShotResultStored>,
+        ship_id: u8,
+        cell_salt: [u8; 32]
+    ) -> Result<ShotOutcome> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let player = acting_player(ctx.accounts.player.key(), &ctx.accounts.profile)?;
+        let (game, history) = &mut load_game_with_history(&ctx.accounts.game)?;
+        let board_commitments = ctx.accounts.board_commitments.load()?;
+        
+        let (x, y) = game.pending_shot().ok_or(ErrorCode::NoPendingShot)?;
+        // Moving a ship re-commits the board, leaving the hashes stale until they're resealed
+        let commitment = if player == game.player1 { ctx.accounts.board1.commitment } else { ctx.accounts.board2.commitment };
+        require!(
+            board_commitments.player == player && board_commitments.commitment == commitment,
+            ErrorCode::CellHashesNotSealed
+        );
+        let stored_leaf = board_commitments.leaves[cell_index(game, x, y)];
+        let boards = &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2];
+        resolve_pending_shot(game, boards, game_key, player, ship_id, &cell_salt, CellProof::Stored(&stored_leaf), history, events)?;
+        
+        let defender_is_player1 = player == game.player1;
+        Ok(shot_outcome(game, read_boards(boards), x, y, defender_is_player1))
+    }
+
+    pub fn initialize_zk_verifier(
+        ctx: Context<InitializeZkVerifier>,
+        circuit: u8,
+        verifying_key: ZkVerifyingKey
+    ) -> Result<()> {
+        let inputs = match circuit {
+            ZK_CIRCUIT_FLEET => 2,
+            ZK_CIRCUIT_SHOT => 4,
+            _ => return err!(ErrorCode::InvalidVerifyingKey),
+        };
+        require!(verifying_key.ic.len() == inputs + 1, ErrorCode::InvalidVerifyingKey);
+        
+        let verifier = &mut ctx.accounts.zk_verifier;
+        
+        verifier.alpha_g1 = verifying_key.alpha_g1;
+        verifier.beta_g2 = verifying_key.beta_g2;
+        verifier.gamma_g2 = verifying_key.gamma_g2;
+        verifier.delta_g2 = verifying_key.delta_g2;
+        verifier.ic = [[0; 64]; MAX_ZK_INPUTS + 1];
+        verifier.ic[..=inputs].copy_from_slice(&verifying_key.ic);
+        verifier.inputs = inputs as u8;
+        verifier.circuit = circuit;
+        verifier.bump = ctx.bumps.zk_verifier;
+        
+        msg!("🔐 Verifier key for circuit {} installed by admin {}", circuit, ctx.accounts.admin.key());
+        Ok(())
+    }
+
+    pub fn submit_fleet_proof(ctx: Context<SubmitFleetProof>, proof: ZkProof) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require!(game.rules.variants & VARIANT_ZK_FLEET != 0, ErrorCode::NotAZkGame);
+        // Player1 can prove right after creating the game; the game starts with the second proof
+        require!(game.state() == GameState::WaitingForOpponent, ErrorCode::GameAlreadyFull);
+        
+        let player = ctx.accounts.player.key();
+        let (commitment, proven) = if player == game.player1 {
+            (ctx.accounts.board1.commitment, game.fleet_proven1)
+        } else {
+            (ctx.accounts.board2.commitment, game.fleet_proven2)
+        };
+        require!(proven == 0, ErrorCode::FleetAlreadyProven);
+        require!(
+            verify_zk_proof(&ctx.accounts.zk_verifier, &proof, &commitment_inputs(&commitment))?,
+            ErrorCode::InvalidFleetProof
+        );
+        
+        if player == game.player1 {
+            game.fleet_proven1 = 1;
+        } else {
+            game.fleet_proven2 = 1;
+        }
+        
+        if game.player2 != Pubkey::default() {
+            start_when_fleets_proven(game);
+            game.last_action_slot = Clock::get()?.slot;
+        }
+        
+        msg!("🔐 Fleet proof verified for {}", player);
+        Ok(())
+    }
+
+    pub fn reveal_shot_result_zk(
+        ctx: Context<RevealShotResultZk>,
+        ship_id: u8,
+        proof: ZkProof
+    ) -> Result<ShotOutcome> {
+        let game_key = ctx.accounts.game.key();
+        let events = &EventEmitter::new(&ctx.accounts.event_authority, ctx.bumps.event_authority);
+        let player = acting_player(ctx.accounts.player.key(), &ctx.accounts.profile)?;
+        let (game, history) = &mut load_game_with_history(&ctx.accounts.game)?;
+        
+        let (x, y) = game.pending_shot().ok_or(ErrorCode::NoPendingShot)?;
+        let commitment = if player == game.player1 { ctx.accounts.board1.commitment } else { ctx.accounts.board2.commitment };
+        let [high, low] = commitment_inputs(&commitment);
+        let inputs = [high, low, zk_scalar(cell_index(game, x, y) as u64), zk_scalar(ship_id as u64)];
+        require!(verify_zk_proof(&ctx.accounts.zk_verifier, &proof, &inputs)?, ErrorCode::InvalidShotProof);
+        
+        // The proof stands in for the cell's salt and Merkle path
+        let boards = &mut [&mut *ctx.accounts.board1, &mut *ctx.accounts.board2];
+        resolve_pending_shot(game, boards, game_key, player, ship_id, &[0; 32], CellProof::Verified, history, events)?;
+        
+        let defender_is_player1 = player == game.player1;
+        Ok(shot_outcome(game, read_boards(boards), x, y, defender_is_player1))
+    }
+
+    pub fn propose_abort(ctx: Context<ProposeAbort>) -> Result<()> {
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        
+        let player = ctx.accounts.player.key();
+        game.abort_proposed_by = if player == game.player1 {
+            1
+        } else if player == game.player2 {
+            2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        
+        msg!("🛑 Player {} proposed voiding the game", player);
+        Ok(())
+    }
+
+    pub fn confirm_abort(ctx: Context<ConfirmAbort>) -> Result<()> {
+        let game_key = ctx.accounts.game.key();
+        let game = &mut ctx.accounts.game.load_mut()?;
+        
+        require_in_progress(game)?;
+        require!(game.abort_proposed_by != 0, ErrorCode::NoAbortProposed);
+        require!(
+            game.wager_mint == Pubkey::default() && game.nft_mint1 == Pubkey::default(),
+            ErrorCode::WrongWagerType
+        );
+        require!(game.spectator_bets == 0, ErrorCode::BetsOutstanding);
+        
+        let player = ctx.accounts.player.key();
+        let confirming_player = if player == game.player1 {
+            1
+        } else if player == game.player2 {
+            2
+        } else {
+            return err!(ErrorCode::NotAPlayer);
+        };
+        require!(confirming_player != game.abort_proposed_by, ErrorCode::CannotConfirmOwnAbort);
+        
+        game.set_state(GameState::Cancelled);
+        
+        // Both staked the same, so the stakes split evenly; each bond goes back to its owner
+        let pot = vault_pot(&ctx.accounts.vault, game)?;
+        let player1_refund = pot / 2 + bond_left(game, 1);
+        let player2_refund = ctx.accounts.vault.lamports() - player1_refund;
+        for (to, refund) in [
+            (ctx.accounts.player1.to_account_info(), player1_refund),
+            (ctx.accounts.player2.to_account_info(), player2_refund),
+        ] {
+            pay_from_vault(
+                &ctx.accounts.system_program,
+                &ctx.accounts.vault,
+                to,
+                game_key,
+                game.vault_bump,
+                refund,
+            )?;
+        }
+        
+        msg!("🛑 Game voided by both players ({} and {} lamports refunded)", player1_refund, player2_refund);
+        Ok(())
+    }
+
+    pub fn start_house_game(ctx: Context<StartHouseGame>, game_id: u64, secret_hash: [u8; 32]) -> Result<()> {
+        // Only randomness committed in the previous slot is still unknown to everyone, so the
+        // house's secret is fixed before it could steer the deal
+        let randomness_data = RandomnessAccountData::parse(ctx.accounts.randomness_account.data.borrow())
+            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+        require!(
+            randomness_data.seed_slot == Clock::get()?.slot.saturating_sub(1),
+            ErrorCode::StaleRandomness
+        );
+        
+        let house_game = &mut ctx.accounts.house_game;
+        house_game.player = ctx.accounts.player.key();
+        house_game.game_id = game_id;
+        house_game.house = ctx.accounts.admin.key();
+        house_game.randomness_account = ctx.accounts.randomness_account.key();
+        house_game.secret_hash = secret_hash;
+        house_game.commitment = [0; 32];
+        house_game.seed = [0; 32];
+        house_game.board_hits = [0; PACKED_CELLS];
+        house_game.ship_hits = [0; 5];
+        house_game.hits = 0;
+        house_game.shots = 0;
+        house_game.pending_shot = [0; 2];
+        house_game.has_pending_shot = 0;
+        house_game.state = GameState::WaitingForOpponent as u8; // Until the board is dealt
+        house_game.verdict = 0;
+        house_game.last_action_slot = Clock::get()?.slot;
+        house_game.bump = ctx.bumps.house_game;
+        
+        msg!("🏠 Player {} challenged the house (game #{})", house_game.player, game_id);
+        Ok(())
+    }
+
+    pub fn deal_house_board(ctx: Context<DealHouseBoard>, commitment: [u8; 32]) -> Result<()> {
+        let house_game = &mut ctx.accounts.house_game;
+        require!(house_game.state == GameState::WaitingForOpponent as u8, ErrorCode::HouseBoardDealt);
+        
+        // The board is dealt off-chain from the VRF value and the house's secret, and only its
+        // commitment is stored; the value alone doesn't give the board away
+        let randomness_data = RandomnessAccountData::parse(ctx.accounts.randomness_account.data.borrow())
+            .map_err(|_| ErrorCode::InvalidRandomnessAccount)?;
+        house_game.seed = randomness_data
+            .get_value(&Clock::get()?)
+            .map_err(|_| ErrorCode::RandomnessNotRevealed)?;
+        house_game.commitment = commitment; // Bound to this game's address and the house
+        house_game.state = GameState::InProgress as u8;
+        house_game.last_action_slot = Clock::get()?.slot;
+        
+        msg!("🏠 House board dealt for player {}", house_game.player);
+        Ok(())
+    }
+
+    pub fn fire_at_house(ctx: Context<FireAtHouse>, x: u8, y: u8) -> Result<()> {
+        let house_game = &mut ctx.accounts.house_game;
+        require!(house_game.state != GameState::WaitingForOpponent as u8, ErrorCode::GameNotReady);
+        require!(house_game.state == GameState::InProgress as u8, ErrorCode::GameOver);
+        require!(x < 10 && y < 10, ErrorCode::InvalidCoordinate);
+        require!(house_game.has_pending_shot == 0, ErrorCode::ShotPending);
+        
+        let coordinate_index = (y as usize) * 10 + (x as usize);
+        require!(hit_cell(&house_game.board_hits, coordinate_index) == 0, ErrorCode::AlreadyShotHere);
+        
+        house_game.shots += 1;
+        house_game.pending_shot = [x, y];
+        house_game.has_pending_shot = 1;
+        house_game.last_action_slot = Clock::get()?.slot; // The house's answer is due from here
+        
+        msg!("💥 Player {} fired at ({}, {}) on the house's board", house_game.player, x, y);
+        Ok(())
+    }
+
+    pub fn answer_house_shot(
+        ctx: Context<AnswerHouseShot>,
+        ship_id: u8,
+        cell_salt: [u8; 32],
+        proof: Vec<[u8; 32]>
+    ) -> Result<ShotOutcome> {
+        let house_game_key = ctx.accounts.house_game.key();
+        let house_game: &mut HouseGame = &mut ctx.accounts.house_game;
+        require!(house_game.has_pending_shot != 0, ErrorCode::NoPendingShot);
+        
+        let [x, y] = house_game.pending_shot;
+        let sunk = answer_solo_shot(
+            &house_game_key,
+            &house_game.house,
+            &house_game.commitment,
+            &mut house_game.board_hits,
+            &mut house_game.ship_hits,
+            &mut house_game.hits,
+            (y as usize) * 10 + (x as usize),
+            ship_id,
+            &cell_salt,
+            &proof,
+        )?;
+        house_game.has_pending_shot = 0;
+        house_game.pending_shot = [0; 2];
+        if sunk {
+            house_game.state = GameState::Finished as u8;
+            msg!("🏆 Player {} sank the house's fleet in {} shots", house_game.player, house_game.shots);
+        }
+        
+        // Every cell proven and the fleet still afloat: the committed board never held it
+        if house_game.state == GameState::InProgress as u8 && house_game.shots as usize == 100 {
+            house_game.state = GameState::Finished as u8;
+            house_game.verdict = 2;
+            msg!("🚨 The house's board for player {} is short of a fleet", house_game.player);
+        }
+        house_game.last_action_slot = Clock::get()?.slot;
+        
+        Ok(solo_shot_outcome(&house_game.board_hits, &house_game.ship_hits, x, y, house_game.state))
+    }
+
+    pub fn reveal_house_board(
+        ctx: Context<RevealHouseBoard>,
+        secret: [u8; 32],
+        original_board: Vec<u8>,
+        salt: [u8; 32]
+    ) -> Result<()> {
+        let house_game_key = ctx.accounts.house_game.key();
+        let house_game = &mut ctx.accounts.house_game;
+        require!(house_game.state == GameState::Finished as u8, ErrorCode::GameNotOver);
+        require!(house_game.verdict == 0, ErrorCode::AlreadyRevealed);
+        require!(original_board.len() == 100, ErrorCode::InvalidBoardSize);
+        
+        // Every answer was already proven against the commitment; the reveal shows the committed
+        // board is the one the VRF value dealt, not one the house picked
+        require!(hashv(&[&secret]).to_bytes() == house_game.secret_hash, ErrorCode::CommitmentMismatch);
+        let hash = CommitHash::Sha256;
+        let computed_root = board_merkle_root(hash, &original_board, &salt);
+        require!(
+            bound_commitment(hash, computed_root, &house_game_key, &house_game.house) == house_game.commitment,
+            ErrorCode::CommitmentMismatch
+        );
+        let dealt = deal_house_cells(&hashv(&[&house_game.seed, &secret]).to_bytes());
+        require!(original_board[..] == dealt[..], ErrorCode::HouseBoardNotDealt);
+        
+        house_game.verdict = 1;
+        
+        msg!("🏠 House board for player {} revealed and verified", house_game.player);
+        Ok(())
+    }
+    
+    // The player wins by forfeit when the house leaves a shot unanswered, or a finished
+    // game unrevealed, past its deadline
+    pub fn claim_house_forfeit(ctx: Context<ClaimHouseForfeit>) -> Result<()> {
+        let house_game = &mut ctx.accounts.house_game;
+        require!(house_game.verdict == 0, ErrorCode::GameOver);
+        let deadline = if house_game.state == GameState::Finished as u8 {
+            REVEAL_DEADLINE_SLOTS
+        } else {
+            require!(house_game.state == GameState::InProgress as u8, ErrorCode::GameNotReady);
+            require!(house_game.has_pending_shot != 0, ErrorCode::NotOpponentsMove);
+            TURN_TIMEOUT_SLOTS
+        };
+        let elapsed = Clock::get()?.slot.saturating_sub(house_game.last_action_slot);
+        require!(elapsed > deadline, ErrorCode::TimeoutNotReached);
+        
+        house_game.state = GameState::Finished as u8;
+        house_game.verdict = 3;
+        
+        msg!("⏰ The house forfeited player {}'s game after {} idle slots", house_game.player, elapsed);
+        Ok(())
+    }
+
+    pub fn close_house_game(ctx: Context<CloseHouseGame>) -> Result<()> {
+        // A dealt board has to be settled first, so the result can't be swept away unchecked
+        let house_game = &ctx.accounts.house_game;
+        require!(
+            house_game.state == GameState::WaitingForOpponent as u8 || house_game.verdict != 0,
+            ErrorCode::HouseGameUnsettled
+        );
+        
+        msg!("🏠 House game closed");
+        Ok(())
+    }
+
+    pub fn publish_puzzle(ctx: Context<PublishPuzzle>, puzzle_id: u64, commitment: [u8; 32], par: u16) -> Result<()> {
+        require!((MIN_PUZZLE_PAR..=MAX_PUZZLE_PAR).contains(&par), ErrorCode::InvalidPar);
+        
+        let puzzle = &mut ctx.accounts.puzzle;
+        puzzle.creator = ctx.accounts.creator.key();
+        puzzle.puzzle_id = puzzle_id;
+        puzzle.commitment = commitment; // Bound to this puzzle's address and its creator
+        puzzle.par = par;
+        puzzle.attempts = 0;
+        puzzle.solves = 0;
+        puzzle.forfeits = 0;
+        puzzle.revealed = 0;
+        puzzle.bump = ctx.bumps.puzzle;
+        
+        msg!("🧩 Puzzle #{} published by {} (par {})", puzzle_id, puzzle.creator, par);
+        Ok(())
+    }
+
+    pub fn start_puzzle_attempt(ctx: Context<StartPuzzleAttempt>) -> Result<()> {
+        let puzzle = &mut ctx.accounts.puzzle;
+        require!(puzzle.revealed == 0, ErrorCode::PuzzleRetired);
+        puzzle.attempts += 1;
+        
+        let attempt = &mut ctx.accounts.attempt;
+        attempt.puzzle = puzzle.key();
+        attempt.player = ctx.accounts.player.key();
+        attempt.board_hits = [0; PACKED_CELLS];
+        attempt.ship_hits = [0; 5];
+        attempt.hits = 0;
+        attempt.shots = 0;
+        attempt.pending_shot = [0; 2];
+        attempt.has_pending_shot = 0;
+        attempt.state = GameState::InProgress as u8;
+        attempt.forfeited = 0;
+        attempt.last_action_slot = Clock::get()?.slot;
+        attempt.bump = ctx.bumps.attempt;
+        
+        msg!("🧩 Player {} took on puzzle {}", attempt.player, attempt.puzzle);
+        Ok(())
+    }
+
+    pub fn fire_at_puzzle(ctx: Context<FireAtPuzzle>, x: u8, y: u8) -> Result<()> {
+        let attempt = &mut ctx.accounts.attempt;
+        require!(attempt.state == GameState::InProgress as u8, ErrorCode::GameOver);
+        require!(x < 10 && y < 10, ErrorCode::InvalidCoordinate);
+        require!(attempt.has_pending_shot == 0, ErrorCode::ShotPending);
+        
+        let coordinate_index = (y as usize) * 10 + (x as usize);
+        require!(hit_cell(&attempt.board_hits, coordinate_index) == 0, ErrorCode::AlreadyShotHere);
+        
+        attempt.shots += 1;
+        attempt.pending_shot = [x, y];
+        attempt.has_pending_shot = 1;
+        attempt.last_action_slot = Clock::get()?.slot;
+        
+        msg!("💥 Player {} fired at ({}, {}) on puzzle {}", attempt.player, x, y, attempt.puzzle);
+        Ok(())
+    }
+
+    pub fn answer_puzzle_shot(
+        ctx: Context<AnswerPuzzleShot>,
+        ship_id: u8,
+        cell_salt: [u8; 32],
+        proof: Vec<[u8; 32]>
+    ) -> Result<ShotOutcome> {
+        let puzzle_key = ctx.accounts.puzzle.key();
+        let puzzle = &mut ctx.accounts.puzzle;
+        let attempt: &mut PuzzleAttempt = &mut ctx.accounts.attempt;
+        require!(attempt.has_pending_shot != 0, ErrorCode::NoPendingShot);
+        // Once the board is public there is nothing left to solve
+        require!(puzzle.revealed == 0, ErrorCode::PuzzleRetired);
+        
+        let [x, y] = attempt.pending_shot;
+        let sunk = answer_solo_shot(
+            &puzzle_key,
+            &puzzle.creator,
+            &puzzle.commitment,
+            &mut attempt.board_hits,
+            &mut attempt.ship_hits,
+            &mut attempt.hits,
+            (y as usize) * 10 + (x as usize),
+            ship_id,
+            &cell_salt,
+            &proof,
+        )?;
+        attempt.has_pending_shot = 0;
+        attempt.pending_shot = [0; 2];
+        attempt.last_action_slot = Clock::get()?.slot;
+        if sunk {
+            attempt.state = GameState::Finished as u8;
+            
+            // Solving within par scores a point, plus one for every shot to spare
+            if attempt.shots <= puzzle.par {
+                let profile = &mut ctx.accounts.player_profile;
+                profile.puzzles_solved += 1;
+                profile.puzzle_score += (puzzle.par - attempt.shots) as u32 + 1;
+                puzzle.solves += 1;
+            }
+            msg!("🧩 Player {} solved puzzle {} in {} shots (par {})", attempt.player, puzzle_key, attempt.shots, puzzle.par);
+        }
+        
+        Ok(solo_shot_outcome(&attempt.board_hits, &attempt.ship_hits, x, y, attempt.state))
+    }
+
+    // Ends an attempt whose shot the creator left unanswered past the turn timeout. The attempt
+    // scores nothing, but the stall is counted against the puzzle
+    pub fn claim_puzzle_forfeit(ctx: Context<ClaimPuzzleForfeit>) -> Result<()> {
+        let attempt = &mut ctx.accounts.attempt;
+        require!(attempt.state == GameState::InProgress as u8, ErrorCode::GameOver);
+        require!(attempt.has_pending_shot != 0, ErrorCode::NotOpponentsMove);
+        let elapsed = Clock::get()?.slot.saturating_sub(attempt.last_action_slot);
+        require!(elapsed > TURN_TIMEOUT_SLOTS, ErrorCode::TimeoutNotReached);
+        
+        attempt.has_pending_shot = 0;
+        attempt.pending_shot = [0; 2];
+        attempt.state = GameState::Finished as u8;
+        attempt.forfeited = 1;
+        ctx.accounts.puzzle.forfeits += 1;
+        
+        msg!("⏰ Puzzle {} forfeited player {}'s attempt after {} idle slots", attempt.puzzle, attempt.player, elapsed);
+        Ok(())
+    }
+    
+    pub fn reveal_puzzle_board(
+        ctx: Context<RevealPuzzleBoard>,
+        original_board: Vec<u8>,
+        salt: [u8; 32]
+    ) -> Result<()> {
+        let puzzle_key = ctx.accounts.puzzle.key();
+        let puzzle = &mut ctx.accounts.puzzle;
+        require!(puzzle.revealed == 0, ErrorCode::AlreadyRevealed);
+        
+        // Retires the puzzle, showing every answer came from a legal fleet that could be sunk
+        verify_solo_board(&puzzle_key, &puzzle.creator, &puzzle.commitment, &original_board, &salt)?;
+        puzzle.revealed = 1;
+        
+        msg!("🧩 Puzzle #{} retired, its board revealed and verified", puzzle.puzzle_id);
+        Ok(())
+    }
+
+    pub fn open_challenge(ctx: Context<OpenChallenge>, day: u32, commitment: [u8; 32]) -> Result<()> {
+        require!(day as i64 >= current_day()?, ErrorCode::ChallengeDayOver);
+        
+        let challenge = &mut ctx.accounts.challenge;
+        challenge.day = day;
+        challenge.host = ctx.accounts.admin.key();
+        challenge.commitment = commitment; // Bound to this challenge's address and its host
+        challenge.players = 0;
+        challenge.leaderboard = Vec::new();
+        challenge.revealed = 0;
+        challenge.bump = ctx.bumps.challenge;
+        
+        msg!("📅 Daily challenge opened for day {}", day);
+        Ok(())
+    }
+
+    pub fn join_challenge(ctx: Context<JoinChallenge>) -> Result<()> {
+        let challenge = &mut ctx.accounts.challenge;
+        require!(challenge.day as i64 == current_day()?, ErrorCode::ChallengeNotToday);
+        challenge.players += 1;
+        
+        let entry = &mut ctx.accounts.entry;
+        entry.challenge = challenge.key();
+        entry.player = ctx.accounts.player.key();
+        entry.board_hits = [0; PACKED_CELLS];
+        entry.ship_hits = [0; 5];
+        entry.hits = 0;
+        entry.shots = 0;
+        entry.pending_shot = [0; 2];
+        entry.has_pending_shot = 0;
+        entry.state = GameState::InProgress as u8;
+        entry.bump = ctx.bumps.entry;
+        
+        msg!("📅 Player {} joined the challenge for day {}", entry.player, challenge.day);
+        Ok(())
+    }
+
+    pub fn fire_at_challenge(ctx: Context<FireAtChallenge>, x: u8, y: u8) -> Result<()> {
+        require!(ctx.accounts.challenge.day as i64 == current_day()?, ErrorCode::ChallengeNotToday);
+        
+        let entry = &mut ctx.accounts.entry;
+        require!(entry.state == GameState::InProgress as u8, ErrorCode::GameOver);
+        require!(x < 10 && y < 10, ErrorCode::InvalidCoordinate);
+        require!(entry.has_pending_shot == 0, ErrorCode::ShotPending);
+        
+        let coordinate_index = (y as usize) * 10 + (x as usize);
+        require!(hit_cell(&entry.board_hits, coordinate_index) == 0, ErrorCode::AlreadyShotHere);
+        
+        entry.shots += 1;
+        entry.pending_shot = [x, y];
+        entry.has_pending_shot = 1;
+        
+        msg!("💥 Player {} fired at ({}, {}) in the daily challenge", entry.player, x, y);
+        Ok(())
+    }
+
+    pub fn answer_challenge_shot(
+        ctx: Context<AnswerChallengeShot>,
+        ship_id: u8,
+        cell_salt: [u8; 32],
+        proof: Vec<[u8; 32]>
+    ) -> Result<ShotOutcome> {
+        let challenge_key = ctx.accounts.challenge.key();
+        let challenge = &mut ctx.accounts.challenge;
+        let entry: &mut ChallengeEntry = &mut ctx.accounts.entry;
+        require!(entry.has_pending_shot != 0, ErrorCode::NoPendingShot);
+        
+        let [x, y] = entry.pending_shot;
+        let sunk = answer_solo_shot(
+            &challenge_key,
+            &challenge.host,
+            &challenge.commitment,
+            &mut entry.board_hits,
+            &mut entry.ship_hits,
+            &mut entry.hits,
+            (y as usize) * 10 + (x as usize),
+            ship_id,
+            &cell_salt,
+            &proof,
+        )?;
+        entry.has_pending_shot = 0;
+        entry.pending_shot = [0; 2];
+        if sunk {
+            entry.state = GameState::Finished as u8;
+            
+            // Fewest shots first; on a tie, whoever finished earlier keeps their place
+            challenge.leaderboard.push(ChallengeScore { player: entry.player, shots: entry.shots });
+            challenge.leaderboard.sort_by_key(|score| score.shots);
+            challenge.leaderboard.truncate(MAX_CHALLENGE_LEADERS);
+            msg!("📅 Player {} sank the daily fleet in {} shots", entry.player, entry.shots);
+        }
+        
+        Ok(solo_shot_outcome(&entry.board_hits, &entry.ship_hits, x, y, entry.state))
+    }
+
+    pub fn reveal_challenge_board(
+        ctx: Context<RevealChallengeBoard>,
+        original_board: Vec<u8>,
+        salt: [u8; 32]
+    ) -> Result<()> {
+        let challenge_key = ctx.accounts.challenge.key();
+        let challenge = &mut ctx.accounts.challenge;
+        require!((challenge.day as i64) < current_day()?, ErrorCode::ChallengeNotOver);
+        require!(challenge.revealed == 0, ErrorCode::AlreadyRevealed);
+        
+        // Every answer was already proven against the commitment; the reveal shows the fleet was legal
+        verify_solo_board(&challenge_key, &challenge.host, &challenge.commitment, &original_board, &salt)?;
+        challenge.revealed = 1;
+        
+        msg!("📅 Board for day {} revealed and verified", challenge.day);
+        Ok(())
+    }
+
+    pub fn close_challenge_entry(ctx: Context<CloseChallengeEntry>) -> Result<()> {
+        // The day's leaderboard is already settled, so the entry's rent can go back to the player
+        require!((ctx.accounts.challenge.day as i64) < current_day()?, ErrorCode::ChallengeNotOver);
+        
+        msg!("📅 Challenge entry for day {} closed", ctx.accounts.challenge.day);
+        Ok(())
+    }
+}
+
+// Helper function giving each queued player's half of a matched game's rent, rounded up
+fn matched_game_rent_share() -> Result<u64> {
+    let rent = Rent::get()?;
+    Ok((rent.minimum_balance(Game::LEN) + 2 * rent.minimum_balance(PlayerBoard::LEN)).div_ceil(2))
+}
+
+// Helper function checking two players' ratings are close enough for the configured delta
+fn ratings_within(config: &Config, profile1: &PlayerProfile, profile2: &PlayerProfile) -> bool {
+    config.max_rating_delta == 0 || profile1.rating.abs_diff(profile2.rating) <= config.max_rating_delta
+}
+
+// Helper function holding a ranked game's joiner to the configured rating delta
+fn check_ranked_join(
+    game: &Game,
+    config: &Config,
+    player1_profile: &Option<Account<PlayerProfile>>,
+    player_profile: &Option<Account<PlayerProfile>>
+) -> Result<()> {
+    if game.ranked == 0 || config.max_rating_delta == 0 {
+        return Ok(());
+    }
+    match (player1_profile, player_profile) {
+        (Some(profile1), Some(profile2)) => {
+            require!(ratings_within(config, profile1, profile2), ErrorCode::RatingGapTooWide);
+            Ok(())
+        }
+        _ => err!(ErrorCode::ProfileRequired),
+    }
+}
+
+// Helper function holding the joiner to the creator's minimum reputation
+fn check_min_reputation(game: &Game, player_profile: &Option<Account<PlayerProfile>>) -> Result<()> {
+    if game.rules.min_reputation == 0 {
+        return Ok(());
+    }
+    let profile = player_profile.as_ref().ok_or(ErrorCode::ProfileRequired)?;
+    require!(profile.reputation >= game.rules.min_reputation, ErrorCode::ReputationTooLow);
+    Ok(())
+}
+
+// Helper function giving the honesty bond each player posts: none when proven shots leave
+// nothing for a reveal to catch
+fn honesty_bond(rules: &GameRules) -> u64 {
+    if rules.variants & VARIANT_ZK_SHOTS != 0 { 0 } else { HONESTY_BOND_LAMPORTS }
+}
+
+// Helper function checking a new game's settings and filling in its freshly created account,
+// whatever the stakes; the caller has already escrowed player1's stake and bond
+#[allow(clippy::too_many_arguments)]
+fn init_player1_game(
+    game: &mut Game,
+    config: &Config,
+    player1: Pubkey,
+    game_id: u64,
+    game_bump: u8,
+    vault_bump: u8,
+    wager: u64,
+    invited_opponent: Pubkey,
+    rules: GameRules,
+    fleet: &[u8],
+    turn_commitment: [u8; 32],
+    ranked: bool,
+    arbiter: Pubkey,
+) -> Result<()> {
+    require!(invited_opponent != player1, ErrorCode::CannotPlayAgainstYourself);
+    require!(arbiter != player1, ErrorCode::PlayerCannotArbitrate);
+    validate_rules(&rules, fleet, &turn_commitment)?;
+    
+    init_game_state(game, player1, game_id, game_bump);
+    game.invited_opponent = invited_opponent; // Default = open to anyone
+    game.rules = rules;
+    game.clock1 = rules.clock_slots as u64; // Both clocks start full; 0 = untimed
+    game.clock2 = rules.clock_slots as u64;
+    game.turn_commitment = turn_commitment; // All zeros = player1 always starts
+    if !fleet.is_empty() {
+        game.set_fleet(fleet); // Empty = standard fleet
+    }
+    game.wager = wager; // 0 = no stakes
+    game.vault_bump = vault_bump;
+    game.bond = honesty_bond(&rules);
+    game.ranked = ranked as u8; // Casual games leave profiles untouched
+    game.arbiter = arbiter; // Default = no arbiter; the joiner accepts it by joining
+    game.season = config.season;
+    game.strike_limit = config.timeout_strike_limit;
+    game.abandon_refund_bps = config.abandon_refund_bps;
+    Ok(())
+}
+
+// Helper function computing the share of a pot (after the fee) returned to a player who timed
+// out mid-game, at the rate stamped on the game when it was created
+fn abandon_refund(game: &Game, pot: u64) -> u64 {
+    if game.end_reason == 2 {
+        pot * game.abandon_refund_bps as u64 / 10_000
+    } else {
+        0
+    }
+}
+
+// Helper function checking a new game's rules, fleet and coin flip fit together
+fn validate_rules(rules: &GameRules, fleet: &[u8], turn_commitment: &[u8; 32]) -> Result<()> {
+    require!(matches!(rules.board_size, 8 | 10 | 12), ErrorCode::InvalidBoardSize);
+    require!(rules.layers() <= MAX_LAYERS && rules.cells() <= MAX_CELLS, ErrorCode::InvalidBoardSize);
+    // Scans, sliding ships and the fleet circuit all assume a single flat square grid
+    require!(
+        (rules.layers() == 1 && !rules.hex())
+            || rules.variants & (VARIANT_RADAR | VARIANT_MOVING_SHIPS | VARIANT_ZK_FLEET) == 0,
+        ErrorCode::ConflictingVariants
+    );
+    require!(matches!(rules.best_of, 1 | 3 | 5), ErrorCode::InvalidSeriesLength);
+    require!(validate_fleet(fleet, rules), ErrorCode::InvalidFleet);
+    // Days-long deadlines and a ticking chess clock can't both apply
+    require!(rules.correspondence_days() == 0 || rules.clock_slots == 0, ErrorCode::ConflictingVariants);
+    require!(*turn_commitment == [0u8; 32] || rules.vrf_first_turn == 0, ErrorCode::ConflictingCoinFlip);
+    // A radar answer is checked against the final board, which a moved ship would contradict
+    require!(
+        rules.variants & VARIANT_RADAR == 0 || rules.variants & VARIANT_MOVING_SHIPS == 0,
+        ErrorCode::ConflictingVariants
+    );
+    require!(
+        rules.variants & VARIANT_KECCAK_COMMITMENTS == 0 || rules.variants & VARIANT_BLAKE3_COMMITMENTS == 0,
+        ErrorCode::ConflictingVariants
+    );
+    // The fleet circuit hashes with SHA-256 and proves one board, which a moved ship would replace
+    require!(
+        rules.variants & VARIANT_ZK_FLEET == 0
+            || rules.variants & (VARIANT_KECCAK_COMMITMENTS | VARIANT_BLAKE3_COMMITMENTS | VARIANT_MOVING_SHIPS) == 0,
+        ErrorCode::ConflictingVariants
+    );
+    // Proven shots stand in for the reveal, so nothing else may be answered unproven
+    require!(
+        rules.variants & VARIANT_ZK_SHOTS == 0
+            || (rules.variants & VARIANT_ZK_FLEET != 0
+                && rules.variants & (VARIANT_RADAR | VARIANT_AREABOMB) == 0
+                && rules.salvo == 0),
+        ErrorCode::ConflictingVariants
+    );
+    // The fleet circuit doesn't encode the gap between ships, so only a reveal can check it
+    require!(
+        rules.variants & VARIANT_ZK_SHOTS == 0 || rules.variants & VARIANT_NO_TOUCH == 0,
+        ErrorCode::ConflictingVariants
+    );
+    Ok(())
+}
+
+// Helper function behind initialize_game and initialize_game_cpi: the payer escrows the stake
+// and bond and pays the rent, while player1 is the seated key
+#[allow(clippy::too_many_arguments)]
+fn initialize_lamport_game<'info>(
+    game: &AccountLoader<'info, Game>,
+    board1: &mut Account<'info, PlayerBoard>,
+    vault: &SystemAccount<'info>,
+    player_profile: &mut Option<Account<'info, PlayerProfile>>,
+    referrer_profile: &Option<Account<'info, PlayerProfile>>,
+    config: &Account<'info, Config>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    player1: Pubkey,
+    [game_bump, board1_bump, vault_bump]: [u8; 3],
+    game_id: u64,
+    board_commitment: [u8; 32],
+    wager: u64,
+    invited_opponent: Pubkey,
+    rules: GameRules,
+    fleet: &[u8],
+    turn_commitment: [u8; 32],
+    ranked: bool,
+    arbiter: Pubkey,
+) -> Result<()> {
+    require!(!config.is_paused, ErrorCode::Paused);
+    
+    // An empty vault must end up rent-exempt, so tiny wagers are rejected
+    require!(
+        wager == 0 || wager >= Rent::get()?.minimum_balance(0),
+        ErrorCode::WagerTooSmall
+    );
+    check_wager_limits(config, Pubkey::default(), wager)?;
+    
+    if wager > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            wager,
+        )?;
+    }
+    
+    post_bond(system_program, payer, vault, honesty_bond(&rules))?;
+    record_referral(player_profile, referrer_profile);
+    
+    let game_key = game.key();
+    let game = &mut game.load_init()?;
+    init_player1_game(
+        game,
+        config,
+        player1,
+        game_id,
+        game_bump,
+        vault_bump,
+        wager,
+        invited_opponent,
+        rules,
+        fleet,
+        turn_commitment,
+        ranked,
+        arbiter,
+    )?;
+    open_board(board1, game_key, 1, board_commitment, board1_bump);
+    game.referrer1 = referrer_of(player_profile);
+    Ok(())
+}
+
+// Helper function behind join_game and join_game_cpi: the payer matches the stake and posts the
+// bond, while player2 is the seated key
+#[allow(clippy::too_many_arguments)]
+fn join_lamport_game<'info>(
+    game: &AccountLoader<'info, Game>,
+    board2: &mut Account<'info, PlayerBoard>,
+    vault: &SystemAccount<'info>,
+    player1_profile: &Option<Account<'info, PlayerProfile>>,
+    player_profile: &mut Option<Account<'info, PlayerProfile>>,
+    referrer_profile: &Option<Account<'info, PlayerProfile>>,
+    config: &Account<'info, Config>,
+    lobby: &mut Option<Account<'info, Lobby>>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    player2: Pubkey,
+    board2_bump: u8,
+    board_commitment: [u8; 32],
+    turn_nonce: [u8; 32],
+) -> Result<()> {
+    require!(!config.is_paused, ErrorCode::Paused);
+    
+    let game_key = game.key();
+    let game = &mut game.load_mut()?;
+    
+    check_lamport_join(game, player2)?;
+    check_ranked_join(game, config, player1_profile, player_profile)?;
+    check_min_reputation(game, player_profile)?;
+    record_referral(player_profile, referrer_profile);
+    
+    // Player2 must match player1's stake
+    if game.wager > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            game.wager,
+        )?;
+    }
+    
+    post_bond(system_program, payer, vault, game.bond)?;
+    seat_player2(game, player2, turn_nonce)?;
+    open_board(board2, game_key, 2, board_commitment, board2_bump);
+    game.referrer2 = referrer_of(player_profile);
+    
+    // Public games leave the lobby as soon as they fill up
+    if game.is_public != 0 {
+        let lobby = lobby.as_mut().ok_or(ErrorCode::LobbyRequired)?;
+        remove_lobby_entry(lobby, game_key);
+    }
+    Ok(())
+}
+
+// Helper function checking a player may take the open seat of a lamport game
+fn check_lamport_join(game: &Game, player2: Pubkey) -> Result<()> {
+    require!(
+        game.wager_mint == Pubkey::default() && game.nft_mint1 == Pubkey::default(),
+        ErrorCode::WrongWagerType
+    );
+    check_open_seat(game, player2)
+}
+
+// Helper function checking a player may take a game's open seat, whatever the stakes
+fn check_open_seat(game: &Game, player2: Pubkey) -> Result<()> {
+    require!(
+        game.state() == GameState::WaitingForOpponent && game.player2 == Pubkey::default(),
+        ErrorCode::GameAlreadyFull
+    );
+    require!(game.player1 != player2, ErrorCode::CannotPlayAgainstYourself);
+    require!(
+        game.invited_opponent == Pubkey::default() || game.invited_opponent == player2,
+        ErrorCode::NotInvited
+    );
+    require!(game.arbiter != player2, ErrorCode::PlayerCannotArbitrate);
+    Ok(())
+}
+
+// Helper function seating player2 once their stake is in, starting the game unless fleet
+// proofs are still owed
+fn seat_player2(game: &mut Game, player2: Pubkey, turn_nonce: [u8; 32]) -> Result<()> {
+    game.player2 = player2;
+    start_when_fleets_proven(game);
+    game.last_action_slot = Clock::get()?.slot;
+    
+    // A coin flip is pending until player1 reveals the nonce behind turn_commitment
+    // (or, with vrf_first_turn, until VRF randomness is consumed)
+    game.turn_nonce = turn_nonce;
+    game.turn_pending = (game.turn_commitment != [0u8; 32] || game.rules.vrf_first_turn != 0) as u8;
+    Ok(())
+}
+
+// Helper function filling in a player's board account as they take their seat
+fn open_board(board: &mut PlayerBoard, game: Pubkey, seat: u8, commitment: [u8; 32], bump: u8) {
+    board.game = game;
+    board.commitment = commitment;
+    board.hits = [0; PACKED_CELLS]; // Packed row-major grid for hits on the board
+    board.ship_mask = [0; MASK_WORDS];
+    board.seat = seat;
+    board.bump = bump;
+}
+
+// Both players' boards, player1's first
+type Boards<'a> = [&'a mut PlayerBoard; 2];
+
+// Helper function giving a player's index into a game's pair of boards
+fn seat_index(player1: bool) -> usize {
+    if player1 { 0 } else { 1 }
+}
+
+// Helper function reborrowing a game's boards read-only
+fn read_boards<'a>(boards: &'a Boards) -> [&'a PlayerBoard; 2] {
+    [&*boards[0], &*boards[1]]
+}
+
+// Helper function to reset every field of a freshly created game
+fn init_game_state(
+    game: &mut Game,
+    player1: Pubkey,
+    game_id: u64,
+    bump: u8
+) {
+    game.player1 = player1;
+    game.player2 = Pubkey::default(); // Will be set when second player joins
+    game.turn = 1; // Player1 starts unless a coin flip says otherwise
+    game.turn_commitment = [0; 32];
+    game.turn_nonce = [0; 32];
+    game.turn_pending = 0;
+    game.hits_count1 = 0; // How many hits player1's fleet has taken
+    game.hits_count2 = 0; // How many hits player2's fleet has taken
+    game.set_state(GameState::WaitingForOpponent);
+    game.winner = 0; // 0 = none, 1 = player1, 2 = player2
+    game.set_pending_shot(None);
+    game.pending_shot_by = Pubkey::default();
+    game.player1_revealed = 0;
+    game.player2_revealed = 0;
+    game.bump = bump;
+    game.game_id = game_id; // Lets player1 run several games at once
+    game.invited_opponent = Pubkey::default();
+    game.wager = 0;
+    game.vault_bump = 0;
+    game.winnings_claimed = 0;
+    game.wager_mint = Pubkey::default(); // Default = lamport wager
+    game.last_action_slot = 0; // Set once the game starts
+    game.end_reason = 0; // 0 = in progress, 1 = fleet sunk, 2 = timeout, 3 = resignation, 4 = reveal forfeit, 5 = draw, 6 = out of ammo, 7 = board exhausted, 8 = arbiter ruling
+    game.is_public = 0; // Set by list_game
+    game.ship_hits1 = [0; MAX_SHIPS]; // Hits per ship (indexed like fleet) on player1's board
+    game.ship_hits2 = [0; MAX_SHIPS];
+    game.rules = GameRules::default();
+    game.set_pending_salvo(&[]);
+    game.set_fleet(&STANDARD_FLEET);
+    game.fleet2 = [0; MAX_SHIPS]; // Both players use fleet unless set_handicap_fleet says otherwise
+    game.fleet2_len = 0;
+    game.cheater = 0; // 0 = none, 1 = player1, 2 = player2
+    game.bond = 0; // Lamports each player posts, set by the creating instruction
+    game.bond1_settled = 0;
+    game.bond2_settled = 0;
+    game.randomness_account = Pubkey::default(); // Default = no randomness request outstanding
+    game.randomness = [0; 32];
+    game.first_turn = 1; // Who moved first, so a rematch can swap it
+    game.rematch_proposer = 0; // 0 = none, 1 = player1, 2 = player2
+    game.rematch_commitment = [0; 32];
+    game.rematch_roll_pot = 0;
+    game.round_wins1 = 0; // Rounds won earlier in a best-of-N series
+    game.round_wins2 = 0;
+    game.draw_offered_by = 0; // 0 = none, 1 = player1, 2 = player2
+    game.stats_recorded = 0; // Set once record_game_result updates both profiles
+    game.ranked = 0; // Set by the creating instruction
+    game.season = 0; // Stamped from the config by the creating instruction
+    game.strike_limit = 0; // Stamped from the config by the creating instruction
+    game.abandon_refund_bps = 0; // Stamped from the config by the creating instruction
+    game.referrer1 = Pubkey::default(); // Stamped from the players' profiles as they take their seats
+    game.referrer2 = Pubkey::default();
+    game.strikes1 = 0;
+    game.strikes2 = 0;
+    game.fleet_proven1 = 0; // Both boards are fresh, so any fleet proof is owed again
+    game.fleet_proven2 = 0;
+    game.carrier_finale = 0;
+    game.offense_recorded = 0; // Set once record_offense runs for this round
+    game.abort_proposed_by = 0;
+    game.trophy_minted = 0; // Set once mint_trophy runs for this round
+    game.nft_mint1 = Pubkey::default(); // Default = stakes (if any) are SOL or SPL tokens
+    game.nft_mint2 = Pubkey::default();
+    game.arbiter = Pubkey::default();
+    game.spectator_bets = 0; // Unsettled bets hold off rematches and closing
+    game.spectators = 0;
+    game.clock1 = 0; // Filled from rules.clock_slots by the creating instruction
+    game.clock2 = 0;
+    game.bond_docked1 = 0; // Keeper bounties come out of the defaulting player's bond
+    game.bond_docked2 = 0;
+    game.relay_nonce1 = 0; // Signed relayed moves replay-protect with per-player nonces
+    game.relay_nonce2 = 0;
+    game.rematch_top_up = 0;
+    game.move_count = 0; // Any move log is overwritten from the front
+    game.last_move = MoveRecord::default(); // shooter 0 = no shot resolved yet
+    game.last_emote1 = 0;
+    game.last_emote2 = 0;
+    game.radar_scan1 = [0; 3];
+    game.radar_scan2 = [0; 3];
+    game.radar_state1 = 0; // Each player gets one scan per round when VARIANT_RADAR is on
+    game.radar_state2 = 0;
+    game.bomb_used1 = 0; // Each player gets one areabomb per round when VARIANT_AREABOMB is on
+    game.bomb_used2 = 0;
+    game.depth_charge_used1 = 0; // And one depth charge on a layered board
+    game.depth_charge_used2 = 0;
+    game.mine_penalty = 0;
+    game.shots_fired1 = 0; // Counted against the ammo limit when VARIANT_LIMITED_AMMO is on
+    game.shots_fired2 = 0;
+}
+
+// Helper function taking a player's stake for a rematch: a fresh bond and any top-up, plus
+// a fresh wager unless the unclaimed pot rolls forward
+fn post_rematch_stake<'info>(
+    game: &Game,
+    system_program: &Program<'info, System>,
+    player: &Signer<'info>,
+    vault: &SystemAccount<'info>,
+    roll_pot: bool,
+    top_up: u64,
+) -> Result<()> {
+    if roll_pot {
+        require!(game.wager > 0, ErrorCode::NoWager);
+        require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
+    } else if game.wager > 0 {
+        // Fresh lamport stakes only - a token pot can only roll forward
+        require!(game.winnings_claimed != 0, ErrorCode::WinningsNotClaimed);
+        require!(game.wager_mint == Pubkey::default(), ErrorCode::WrongWagerType);
+        
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: player.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+            ),
+            game.wager,
+        )?;
+    }
+    
+    post_bond(system_program, player, vault, game.bond + top_up)
+}
+
+// Helper function starting a rematch in place: same players, stakes and rules, fresh
+// boards, and the other player moves first
+fn reset_for_rematch(game: &mut Game, boards: &mut Boards, board_commit1: [u8; 32], board_commit2: [u8; 32]) {
+    let player2 = game.player2;
+    let rules = game.rules;
+    let (fleet, fleet_len) = (game.fleet, game.fleet_len);
+    let (fleet2, fleet2_len) = (game.fleet2, game.fleet2_len);
+    let (wager, wager_mint, vault_bump, bond) = (game.wager, game.wager_mint, game.vault_bump, game.bond);
+    let (ranked, season, arbiter, strike_limit) = (game.ranked, game.season, game.arbiter, game.strike_limit);
+    let abandon_refund_bps = game.abandon_refund_bps;
+    let (referrer1, referrer2) = (game.referrer1, game.referrer2);
+    let spectators = game.spectators;
+    let first_turn = if game.first_turn == 1 { 2 } else { 1 };
+    // Bank the finished round toward the series, or start a new series once it's decided
+    let (round_wins1, round_wins2) = if series_over(game) {
+        (0, 0)
+    } else {
+        (
+            game.round_wins1 + (game.winner == 1) as u8,
+            game.round_wins2 + (game.winner == 2) as u8,
+        )
+    };
+    
+    init_game_state(game, game.player1, game.game_id, game.bump);
+    game.player2 = player2;
+    for (board, commitment) in boards.iter_mut().zip([board_commit1, board_commit2]) {
+        board.commitment = commitment;
+        board.hits = [0; PACKED_CELLS];
+        board.ship_mask = [0; MASK_WORDS];
+    }
+    game.rules = rules;
+    game.clock1 = rules.clock_slots as u64; // Both clocks start full; 0 = untimed
+    game.clock2 = rules.clock_slots as u64;
+    game.fleet = fleet;
+    game.fleet_len = fleet_len;
+    game.fleet2 = fleet2;
+    game.fleet2_len = fleet2_len;
+    game.wager = wager;
+    game.wager_mint = wager_mint;
+    game.vault_bump = vault_bump;
+    game.bond = bond;
+    game.ranked = ranked;
+    game.season = season;
+    game.arbiter = arbiter;
+    game.strike_limit = strike_limit;
+    game.abandon_refund_bps = abandon_refund_bps;
+    game.referrer1 = referrer1;
+    game.referrer2 = referrer2;
+    game.spectators = spectators;
+    game.turn = first_turn;
+    game.first_turn = first_turn;
+    game.round_wins1 = round_wins1;
+    game.round_wins2 = round_wins2;
+    start_when_fleets_proven(game);
+}
+
+// Helper function starting a game once both players are seated, or holding it in
+// WaitingForOpponent until both fleet proofs verify when the game requires them
+fn start_when_fleets_proven(game: &mut Game) {
+    let proofs_pending = game.rules.variants & VARIANT_ZK_FLEET != 0
+        && (game.fleet_proven1 == 0 || game.fleet_proven2 == 0);
+    game.set_state(if proofs_pending { GameState::WaitingForOpponent } else { GameState::InProgress });
+}
+
+// Helper function splitting a board commitment into two big-endian 128-bit public inputs,
+// since a whole 256-bit hash can overflow the scalar field
+fn commitment_inputs(commitment: &[u8; 32]) -> [[u8; 32]; 2] {
+    let mut halves = [[0u8; 32]; 2];
+    halves[0][16..].copy_from_slice(&commitment[..16]);
+    halves[1][16..].copy_from_slice(&commitment[16..]);
+    halves
+}
+
+// Helper function encoding a small number as a big-endian public input
+fn zk_scalar(value: u64) -> [u8; 32] {
+    let mut scalar = [0u8; 32];
+    scalar[24..].copy_from_slice(&value.to_be_bytes());
+    scalar
+}
+
+// Helper function checking a Groth16 proof against a verifier key and its public inputs.
+// The client negates proof.a so the whole check is one pairing product
+fn verify_zk_proof(verifier: &ZkVerifier, proof: &ZkProof, inputs: &[[u8; 32]]) -> Result<bool> {
+    require!(inputs.len() == verifier.inputs as usize, ErrorCode::InvalidVerifyingKey);
+    
+    let mut vk_x = verifier.ic[0].to_vec();
+    for (input, ic) in inputs.iter().zip(&verifier.ic[1..]) {
+        let term = alt_bn128_multiplication(&[&ic[..], &input[..]].concat())
+            .map_err(|_| ErrorCode::InvalidVerifyingKey)?;
+        vk_x = alt_bn128_addition(&[&vk_x[..], &term[..]].concat()).map_err(|_| ErrorCode::InvalidVerifyingKey)?;
+    }
+    
+    let pairing = alt_bn128_pairing(
+        &[
+            &proof.a[..], &proof.b[..],
+            &verifier.alpha_g1[..], &verifier.beta_g2[..],
+            &vk_x[..], &verifier.gamma_g2[..],
+            &proof.c[..], &verifier.delta_g2[..],
+        ]
+        .concat(),
+    )
+    .map_err(|_| ErrorCode::InvalidVerifyingKey)?;
+    Ok(pairing[31] == 1)
+}
+
+// Helper function sending an escrowed NFT out of its game-owned vault, then closing
+// the vault so its rent goes back to rent_to
+fn release_nft<'info>(
+    token_program: &Program<'info, Token>,
+    vault_nft_account: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    rent_to: AccountInfo<'info>,
+    game: AccountInfo<'info>,
+    game_seeds: &[&[u8]],
+) -> Result<()> {
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::Transfer {
+                from: vault_nft_account.to_account_info(),
+                to,
+                authority: game.clone(),
+            },
+            &[game_seeds],
+        ),
+        1,
+    )?;
+    
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::CloseAccount {
+            account: vault_nft_account.to_account_info(),
+            destination: rent_to,
+            authority: game,
+        },
+        &[game_seeds],
+    ))
+}
+
+// Helper function naming who the game is waiting on: the defender while shots or a
+// radar scan are pending, otherwise whoever's turn it is
+fn player_to_move(game: &Game) -> u8 {
+    if game.pending_shot_by != Pubkey::default() {
+        if game.pending_shot_by == game.player1 { 2 } else { 1 }
+    } else if game.radar_state1 == 1 {
+        2
+    } else if game.radar_state2 == 1 {
+        1
+    } else {
+        game.turn
+    }
+}
+
+// Helper function giving the player to move a strike for acting after the turn deadline
+// their opponent let pass; reaching the game's strike limit forfeits on the spot, so the
+// caller must skip the move when this returns true
+fn record_strike(game: &mut Game) -> Result<bool> {
+    let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+    if elapsed <= game.rules.turn_timeout_slots() {
+        return Ok(false);
+    }
+    
+    let mover = player_to_move(game);
+    let strikes = if mover == 1 { &mut game.strikes1 } else { &mut game.strikes2 };
+    *strikes = strikes.saturating_add(1);
+    let strikes = *strikes;
+    
+    let struck_out = game.strike_limit != 0 && strikes >= game.strike_limit;
+    if struck_out {
+        end_by_timeout(game, 3 - mover)?;
+        msg!("⏰ Player{} forfeits after {} missed deadlines", mover, strikes);
+    }
+    Ok(struck_out)
+}
+
+// Helper function charging the time since the last action to the player on the clock
+fn charge_clock(game: &mut Game) -> Result<()> {
+    if game.rules.clock_slots == 0 {
+        return Ok(());
+    }
+    
+    let elapsed = Clock::get()?.slot.saturating_sub(game.last_action_slot);
+    let clock = if player_to_move(game) == 1 { &mut game.clock1 } else { &mut game.clock2 };
+    // Out of time means out of moves; the opponent (or anyone) calls flag_timeout instead
+    require!(elapsed <= *clock, ErrorCode::ClockExpired);
+    *clock -= elapsed;
+    Ok(())
+}
+
+// Helper function resolving who a move is made for: the signer itself, or the wallet
+// whose profile names the signer as its live session key
+fn acting_player(signer: Pubkey, profile: &Option<Account<PlayerProfile>>) -> Result<Pubkey> {
+    match profile {
+        Some(profile) if profile.session_key == signer => {
+            require!(Clock::get()?.slot <= profile.session_expires_slot, ErrorCode::SessionExpired);
+            Ok(profile.wallet)
+        }
+        Some(_) => err!(ErrorCode::InvalidSessionKey),
+        None => Ok(signer),
+    }
+}
+
+// Helper function building the bytes a player signs for a relayed move:
+// game || signer's board commitment || action || payload || nonce (little-endian).
+// The commitment is fresh every round, so signatures can't be replayed into a rematch
+// or a later game reusing the same PDA
+fn relayed_move_message(
+    game_key: Pubkey,
+    commitment: [u8; 32],
+    action: u8,
+    payload: &[u8],
+    nonce: u64
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 32 + 1 + payload.len() + 8);
+    message.extend_from_slice(game_key.as_ref());
+    message.extend_from_slice(&commitment);
+    message.push(action);
+    message.extend_from_slice(payload);
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+// Helper function checking that the instruction just before this one is an ed25519
+// program verification of `signer` signing exactly `message`
+fn verify_ed25519_signature(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current_index = instructions_sysvar::load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::InvalidRelaySignature);
+    let ix = instructions_sysvar::load_instruction_at_checked(current_index as usize - 1, instructions)?;
+    require!(ix.program_id == ed25519_program::ID, ErrorCode::InvalidRelaySignature);
+    
+    // One signature, with its offsets header pointing back into the same instruction
+    // (index u16::MAX) so nothing can be borrowed from elsewhere in the transaction
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidRelaySignature);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let (public_key_offset, message_offset, message_size) =
+        (read_u16(6) as usize, read_u16(10) as usize, read_u16(12) as usize);
+    require!(
+        read_u16(4) == u16::MAX && read_u16(8) == u16::MAX && read_u16(14) == u16::MAX,
+        ErrorCode::InvalidRelaySignature
+    );
+    
+    let signed_key = data.get(public_key_offset..public_key_offset + 32);
+    let signed_message = data.get(message_offset..message_offset + message_size);
+    require!(
+        signed_key == Some(signer.as_ref()) && signed_message == Some(message),
+        ErrorCode::InvalidRelaySignature
+    );
+    Ok(())
+}
+
+// Helper function accepting each relayed nonce exactly once, in order, per player
+fn consume_relay_nonce(game: &mut Game, player_num: u8, nonce: u64) -> Result<()> {
+    let expected = if player_num == 1 { &mut game.relay_nonce1 } else { &mut game.relay_nonce2 };
+    require!(nonce == *expected, ErrorCode::InvalidRelayNonce);
+    *expected += 1;
+    Ok(())
+}
+
+// Helper function counting resolved shots on both boards
+fn shots_taken(game: &Game, boards: [&PlayerBoard; 2]) -> usize {
+    let cells = game.rules.cells();
+    boards
+        .iter()
+        .map(|board| (0..cells).filter(|&i| hit_cell(&board.hits, i) != 0).count())
+        .sum()
+}
+
+// Helper function checking whether the finished round decides the best-of-N series
+fn series_over(game: &Game) -> bool {
+    let needed = game.rules.best_of / 2 + 1;
+    let wins1 = game.round_wins1 + (game.winner == 1) as u8;
+    let wins2 = game.round_wins2 + (game.winner == 2) as u8;
+    game.is_over() && (wins1 >= needed || wins2 >= needed)
+}
+
+// Helper function checking the game is being played (both joined, not yet over)
+fn require_in_progress(game: &Game) -> Result<()> {
+    match game.state() {
+        GameState::InProgress => Ok(()),
+        GameState::WaitingForOpponent => err!(ErrorCode::GameNotReady),
+        _ => err!(ErrorCode::GameOver),
+    }
+}
+
+// Helper function moving a game out of AwaitingReveal once the result can be paid out
+fn settle_reveals(game: &mut Game) {
+    if game.state() == GameState::AwaitingReveal && reveals_complete(game) {
+        game.set_state(GameState::Finished);
+    }
+}
+
+// Helper function deciding whether enough boards are revealed to pay out
+fn reveals_complete(game: &Game) -> bool {
+    match game.end_reason {
+        // The loser of a forfeit or an exhausted board may never reveal, so only the winner's
+        // board is required
+        2..=4 | 7 => (game.winner == 1 && game.player1_revealed != 0) || (game.winner == 2 && game.player2_revealed != 0),
+        _ => game.player1_revealed != 0 && game.player2_revealed != 0,
+    }
+}
+
+// Helper function reading one 2-bit cell of a packed hit board
+fn hit_cell(board: &[u8; PACKED_CELLS], index: usize) -> u8 {
+    (board[index / 4] >> (index % 4 * 2)) & 0b11
+}
+
+// Helper function writing one 2-bit cell of a packed hit board
+fn set_hit_cell(board: &mut [u8; PACKED_CELLS], index: usize, value: u8) {
+    let shift = index % 4 * 2;
+    board[index / 4] = (board[index / 4] & !(0b11 << shift)) | (value << shift);
+}
+
+// Helper function checking a single shot is legal for the shooter and marking it pending
+fn fire_at(
+    game: &mut Game,
+    boards: [&PlayerBoard; 2],
+    game_key: Pubkey,
+    current_player: Pubkey,
+    x: u8,
+    y: u8,
+    events: &EventEmitter,
+) -> Result<()> {
+    require!(game.rules.salvo == 0, ErrorCode::SalvoGame);
+    require_in_progress(game)?;
+    require!(game.turn_pending == 0, ErrorCode::TurnNotDecided);
+    require!(
+        x < game.rules.board_size && y < game.rules.rows(),
+        ErrorCode::InvalidCoordinate
+    );
+    require!(game.pending_shot().is_none() && game.pending_salvo().is_empty(), ErrorCode::ShotPending);
+    require!(game.radar_state1 != 1 && game.radar_state2 != 1, ErrorCode::ScanPending);
+    
+    let is_player1 = current_player == game.player1;
+    let is_player2 = current_player == game.player2;
+    
+    require!(is_player1 || is_player2, ErrorCode::NotAPlayer);
+    
+    // Check if it's the player's turn
+    require!(
+        (game.turn == 1 && is_player1) || (game.turn == 2 && is_player2),
+        ErrorCode::NotYourTurn
+    );
+    
+    let coordinate_index = cell_index(game, x, y);
+    
+    // Check the opponent's board to ensure this coordinate hasn't been shot before
+    let opponent_board = &boards[seat_index(!is_player1)].hits;
+    
+    require!(hit_cell(opponent_board, coordinate_index) == 0, ErrorCode::AlreadyShotHere);
+    require!(ammo_left(game, game.turn) >= 1, ErrorCode::OutOfAmmo);
+    
+    if record_strike(game)? {
+        return Ok(());
+    }
+    charge_clock(game)?;
+    
+    // Set pending shot
+    record_shots_fired(game, 1);
+    game.set_pending_shot(Some((x, y)));
+    game.pending_shot_by = current_player;
+    game.draw_offered_by = 0; // Firing declines any standing draw offer or abort proposal
+    game.abort_proposed_by = 0;
+    game.last_action_slot = Clock::get()?.slot;
+    
+    msg!("💥 Player {} fired at coordinate ({}, {})", current_player, x, y);
+    notify_player_to_move(game, game_key, events)
+}
+
+// Helper function resolving the pending single shot for its defender and passing the turn
+#[allow(clippy::too_many_arguments)]
+fn resolve_pending_shot(
+    game: &mut Game,
+    boards: &mut Boards,
+    game_key: Pubkey,
+    current_player: Pubkey,
+    ship_id: u8,
+    cell_salt: &[u8; 32],
+    proof: CellProof,
+    history: &mut [MoveRecord],
+    events: &EventEmitter,
+) -> Result<()> {
+    require_in_progress(game)?;
+    require!(game.pending_shot().is_some(), ErrorCode::NoPendingShot);
+    
+    let is_player1 = current_player == game.player1;
+    let is_player2 = current_player == game.player2;
+    
+    require!(is_player1 || is_player2, ErrorCode::NotAPlayer);
+    
+    // Ensure this is the defending player (opposite of who fired)
+    let is_defender = if game.pending_shot_by == game.player1 {
+        is_player2
+    } else {
+        is_player1
+    };
+    
+    require!(is_defender, ErrorCode::NotDefender);
+    
+    if record_strike(game)? {
+        return Ok(());
+    }
+    charge_clock(game)?;
+    
+    let (x, y) = game.pending_shot().unwrap();
+    let coordinate_index = cell_index(game, x, y);
+    
+    resolve_shot(game, boards, game_key, is_player1, coordinate_index, ship_id, cell_salt, proof, history, events)?;
+    
+    // Clear pending shot and switch turns
+    game.set_pending_shot(None);
+    game.set_pending_salvo(&[]);
+    game.pending_shot_by = Pubkey::default();
+    game.last_action_slot = Clock::get()?.slot;
+    
+    pass_turn(game, read_boards(boards), game_key, events)
+}
+
+// Helper function resolving some of a pending salvo's shots for its defender, passing the
+// turn once none are left; announce emits a ShotResolved for each shot as it's resolved
+#[allow(clippy::too_many_arguments)]
+fn resolve_pending_salvo(
+    game: &mut Game,
+    boards: &mut Boards,
+    game_key: Pubkey,
+    current_player: Pubkey,
+    results: &[ShotReveal],
+    announce: bool,
+    history: &mut [MoveRecord],
+    events: &EventEmitter,
+) -> Result<()> {
+    require_in_progress(game)?;
+    require!(!game.pending_salvo().is_empty(), ErrorCode::NoPendingShot);
+    
+    let is_player1 = current_player == game.player1;
+    let is_player2 = current_player == game.player2;
+    
+    require!(is_player1 || is_player2, ErrorCode::NotAPlayer);
+    
+    let is_defender = if game.pending_shot_by == game.player1 {
+        is_player2
+    } else {
+        is_player1
+    };
+    
+    require!(is_defender, ErrorCode::NotDefender);
+    
+    if record_strike(game)? {
+        return Ok(());
+    }
+    charge_clock(game)?;
+    
+    // A full salvo's proofs may not fit in one transaction, so any subset can be resolved
+    let shooter = game.pending_shot_by;
+    for result in results.iter() {
+        let position = game
+            .pending_salvo()
+            .iter()
+            .position(|&shot| shot == [result.x, result.y])
+            .ok_or(ErrorCode::NoPendingShot)?;
+        game.remove_pending_salvo(position);
+        
+        let coordinate_index = cell_index(game, result.x, result.y);
+        resolve_shot(
+            game,
+            boards,
+            game_key,
+            is_player1,
+            coordinate_index,
+            result.ship_id,
+            &result.cell_salt,
+            CellProof::Merkle(&result.proof),
+            history,
+            events,
+        )?;
+        if announce {
+            emit_shot_resolved(game, game_key, shooter, events)?;
+        }
+        
+        if game.is_over() {
+            break;
+        }
+    }
+    
+    game.last_action_slot = Clock::get()?.slot;
+    
+    // The turn passes once every shot in the salvo is resolved
+    if game.is_over() || game.pending_salvo().is_empty() {
+        game.set_pending_salvo(&[]);
+        game.pending_shot_by = Pubkey::default();
+        pass_turn(game, read_boards(boards), game_key, events)?;
+    }
+    
+    Ok(())
+}
+
+// Helper function summarizing a single shot for the instruction's return data
+fn shot_outcome(game: &Game, boards: [&PlayerBoard; 2], x: u8, y: u8, defender_is_player1: bool) -> ShotOutcome {
+    let board = &boards[seat_index(defender_is_player1)].hits;
+    let ship_hits = if defender_is_player1 { &game.ship_hits1 } else { &game.ship_hits2 };
+    let ships_remaining = game
+        .fleet_of(defender_is_player1)
+        .iter()
+        .zip(ship_hits.iter())
+        .filter(|(&len, &hits)| hits < len)
+        .count();
+    
+    ShotOutcome {
+        x,
+        y,
+        result: hit_cell(board, cell_index(game, x, y)),
+        ships_remaining: ships_remaining as u8,
+        turn: game.turn,
+        state: game.state,
+    }
+}
+
+// Helper function handing the turn to the defender once a shot or salvo is resolved. A struck
+// mine first costs the attacker a random open cell of their own board, fired as if by the
+// defender; the turn passes once the attacker answers it
+fn pass_turn(game: &mut Game, boards: [&PlayerBoard; 2], game_key: Pubkey, events: &EventEmitter) -> Result<()> {
+    // A board shot out without its fleet sinking never held a valid fleet, so rather than
+    // deadlock, its owner is caught cheating and only the honest board needs revealing
+    for defender_is_player1 in [true, false] {
+        let board = &boards[seat_index(defender_is_player1)].hits;
+        let exhausted = (0..game.rules.cells()).all(|i| hit_cell(board, i) != 0);
+        if exhausted && !game.is_over() {
+            game.set_state(game_over_state(game));
+            game.end_reason = 7; // 7 = board exhausted
+            flag_cheater(game, game_key, defender_is_player1, events)?;
+        }
+    }
+    
+    if game.is_over() {
+        game.mine_penalty = 0;
+        return Ok(());
+    }
+    
+    if game.mine_penalty == 1 {
+        let attacker_is_player1 = game.turn == 1;
+        let attacker_board = &boards[seat_index(attacker_is_player1)].hits;
+        let open_cells: Vec<usize> = (0..game.rules.cells())
+            .filter(|&i| hit_cell(attacker_board, i) == 0)
+            .collect();
+        
+        // Slot-derived, so a validator could bias which cell - but never whether one is hit
+        let seed = hashv(&[
+            game_key.as_ref(),
+            &Clock::get()?.slot.to_le_bytes(),
+            &game.move_count.to_le_bytes(),
+        ]).to_bytes();
+        let roll = u64::from_le_bytes(seed[..8].try_into().unwrap()) as usize;
+        
+        if let Some(&index) = open_cells.get(roll % open_cells.len().max(1)) {
+            let size = game.rules.board_size as usize;
+            game.set_pending_shot(Some(((index % size) as u8, (index / size) as u8)));
+            game.pending_shot_by = if attacker_is_player1 { game.player2 } else { game.player1 };
+            game.mine_penalty = 2;
+            msg!("💥 The mine blasts cell ({}, {}) of the attacker's board", index % size, index / size);
+            return notify_player_to_move(game, game_key, events);
+        }
+    }
+    
+    game.mine_penalty = 0;
+    game.turn = if game.turn == 1 { 2 } else { 1 };
+    
+    // A player out of ammo sits out; once both are, the game ends on hits landed
+    if ammo_left(game, game.turn) == 0 {
+        let other = if game.turn == 1 { 2 } else { 1 };
+        if ammo_left(game, other) == 0 {
+            end_by_ammo(game);
+        } else {
+            game.turn = other;
+        }
+    }
+    notify_player_to_move(game, game_key, events)
+}
+
+// Helper function announcing, in a correspondence game, who the game now waits on and by
+// when, so a notifier watching the events can ping that player
+fn notify_player_to_move(game: &Game, game_key: Pubkey, events: &EventEmitter) -> Result<()> {
+    if game.rules.correspondence_days() == 0 || game.is_over() {
+        return Ok(());
+    }
+    let player_num = player_to_move(game);
+    events.emit(MoveAwaited {
+        game: game_key,
+        player: if player_num == 1 { game.player1 } else { game.player2 },
+        player_num,
+        deadline_slot: game.last_action_slot.saturating_add(game.rules.turn_timeout_slots()),
+    })
+}
+
+// Helper function counting shots the player to move has just fired against their ammo
+fn record_shots_fired(game: &mut Game, shots: usize) {
+    if game.turn == 1 {
+        game.shots_fired1 += shots as u8;
+    } else {
+        game.shots_fired2 += shots as u8;
+    }
+}
+
+// Helper function giving how many more shots a player may fire (usize::MAX = unlimited ammo)
+fn ammo_left(game: &Game, player_num: u8) -> usize {
+    if game.rules.variants & VARIANT_LIMITED_AMMO == 0 {
+        return usize::MAX;
+    }
+    let fired = if player_num == 1 { game.shots_fired1 } else { game.shots_fired2 };
+    (game.rules.cells() * AMMO_PERCENT_OF_CELLS / 100).saturating_sub(fired as usize)
+}
+
+// Helper function ending a limited-ammo game once both players have fired their last shot:
+// most hits landed wins, then most ships sunk, otherwise it's a draw
+fn end_by_ammo(game: &mut Game) {
+    let ships_sunk = |player1: bool, ship_hits: &[u8; MAX_SHIPS]| {
+        game.fleet_of(player1).iter().zip(ship_hits.iter()).filter(|(&len, &hits)| hits == len).count()
+    };
+    let score1 = (game.hits_count2, ships_sunk(false, &game.ship_hits2));
+    let score2 = (game.hits_count1, ships_sunk(true, &game.ship_hits1));
+    
+    game.set_state(game_over_state(game));
+    if score1 == score2 {
+        game.winner = 0;
+        game.end_reason = 5; // 5 = draw, so it settles like an agreed one
+        msg!("🤝 Both players are out of ammo - the game is drawn");
+    } else {
+        game.winner = if score1 > score2 { 1 } else { 2 };
+        game.end_reason = 6; // 6 = out of ammo
+        msg!("🏆 Player {} wins on hits once both players ran out of ammo!", game.winner);
+    }
+}
+
+// Helper function verifying one shot result against the defender's commitment and
+// applying it to their board
+#[allow(clippy::too_many_arguments)]
+fn resolve_shot(
+    game: &mut Game,
+    boards: &mut Boards,
+    game_key: Pubkey,
+    defender_is_player1: bool,
+    coordinate_index: usize,
+    ship_id: u8,
+    cell_salt: &[u8; 32],
+    proof: CellProof,
+    history: &mut [MoveRecord],
+    events: &EventEmitter,
+) -> Result<()> {
+    let defender = if defender_is_player1 { game.player1 } else { game.player2 };
+    
+    // The claimed result must be proven against the defender's committed Merkle root
+    let mines_allowed = game.rules.variants & VARIANT_MINES != 0;
+    let fleet = game.fleet_of(defender_is_player1).to_vec();
+    require!(
+        (ship_id as usize) <= fleet.len() || (ship_id == MINE_CELL && mines_allowed),
+        ErrorCode::InvalidShipId
+    );
+    let fleet_cells = fleet_cells(game, defender_is_player1);
+    let was_hit = ship_id != 0 && ship_id != MINE_CELL;
+    let defender_board = &mut boards[seat_index(defender_is_player1)];
+    let commitment = defender_board.commitment;
+    let hash = game.rules.commit_hash();
+    let leaf = cell_leaf(hash, coordinate_index, ship_id, cell_salt);
+    let proven = match proof {
+        CellProof::Merkle(proof) => {
+            let root = merkle_root_from_proof(hash, leaf, coordinate_index, proof);
+            proof.len() == merkle_depth(game.rules.cells())
+                && bound_commitment(hash, root, &game_key, &defender) == commitment
+        }
+        // Sealing already tied every uploaded leaf to this commitment
+        CellProof::Stored(stored_leaf) => leaf == *stored_leaf,
+        CellProof::Verified => true,
+    };
+    require!(proven, ErrorCode::InvalidCellProof);
+    // With no reveal at the end, an answer that isn't proven in zero knowledge is never checked
+    // against the fleet proof's board
+    require!(
+        game.rules.variants & VARIANT_ZK_SHOTS == 0 || matches!(proof, CellProof::Verified),
+        ErrorCode::ShotProofRequired
+    );
+    
+    if ship_id == MINE_CELL {
+        let attacker_player_num = if defender_is_player1 { 2 } else { 1 };
+        set_hit_cell(&mut defender_board.hits, coordinate_index, 3); // 3 = mine
+        msg!("💣 BOOM! Player {} struck a mine!", game.pending_shot_by);
+        // A mine struck by a penalty shot is only marked; it doesn't set off another penalty
+        if game.mine_penalty == 0 {
+            game.mine_penalty = 1;
+        }
+        return record_move(game, history, coordinate_index, attacker_player_num, 3);
+    }
+    
+    // Update the defender's board
+    let (defender_hits_count, defender_ship_hits, attacker_player_num) = if defender_is_player1 {
+        (&mut game.hits_count1, &mut game.ship_hits1, 2)
+    } else {
+        (&mut game.hits_count2, &mut game.ship_hits2, 1)
+    };
+    
+    if was_hit {
+        set_hit_cell(&mut defender_board.hits, coordinate_index, 2); // 2 = hit
+        *defender_hits_count += 1;
+        msg!("🎯 HIT! Player {} hit a ship!", game.pending_shot_by);
+        
+        let ship = (ship_id - 1) as usize;
+        defender_ship_hits[ship] += 1;
+        if defender_ship_hits[ship] == fleet[ship] {
+            msg!("🔥 Player {} sunk a ship of size {}!", game.pending_shot_by, fleet[ship]);
+            events.emit(ShipSunk {
+                game: game_key,
+                owner: defender,
+                ship_id,
+                ship_size: fleet[ship],
+                spectators: game.spectators,
+            })?;
+        }
+        
+        // Check for win condition (every square of the fleet hit)
+        if *defender_hits_count as usize >= fleet_cells {
+            game.set_state(game_over_state(game));
+            game.winner = attacker_player_num;
+            game.end_reason = 1; // 1 = fleet sunk
+            game.carrier_finale = (fleet[ship] == fleet.iter().copied().max().unwrap_or(0)) as u8;
+            // The caller's last_action_slot update now marks when the game ended
+            msg!("🏆 Player {} wins! All ships sunk!", game.pending_shot_by);
+        }
+    } else {
+        set_hit_cell(&mut defender_board.hits, coordinate_index, 1); // 1 = miss
+        msg!("💦 MISS! Player {} missed.", game.pending_shot_by);
+    }
+    
+    record_move(game, history, coordinate_index, attacker_player_num, if was_hit { 2 } else { 1 })
+}
+
+// Helper function appending a closing game's summary to the archive tree, if one is set up.
+// The leaf hashes the GameArchived event, so indexers can rebuild and prove it
+#[allow(clippy::too_many_arguments)]
+fn archive_game<'info>(
+    game: &Game,
+    boards: [&PlayerBoard; 2],
+    game_key: Pubkey,
+    config: &Account<'info, Config>,
+    merkle_tree: &Option<UncheckedAccount<'info>>,
+    compression_program: &Option<UncheckedAccount<'info>>,
+    noop_program: &Option<UncheckedAccount<'info>>,
+    events: &EventEmitter<'info>,
+) -> Result<()> {
+    if config.archive_tree == Pubkey::default() {
+        return Ok(());
+    }
+    let (Some(merkle_tree), Some(compression_program), Some(noop_program)) =
+        (merkle_tree, compression_program, noop_program)
+    else {
+        return err!(ErrorCode::ArchiveAccountsRequired);
+    };
+    
+    let summary = GameArchived {
+        game: game_key,
+        player1: game.player1,
+        player2: game.player2,
+        winner: game.winner,
+        end_reason: game.end_reason,
+        boards_hash: hashv(&[&boards[0].hits, &boards[1].hits]).to_bytes(),
+        shots: game.move_count,
+        ended_slot: game.last_action_slot,
+    };
+    let leaf = hashv(&[&summary.try_to_vec()?]).to_bytes();
+    
+    invoke_compression("append", &leaf, merkle_tree, config, noop_program, compression_program)?;
+    events.emit(summary)
+}
+
+// The event authority PDA an instruction self-invokes through, so helpers without the
+// instruction's Context can emit events the way emit_cpi! does. Events then travel as
+// instruction data, which indexers can still read when a long transaction's logs get truncated
+struct EventEmitter<'info> {
+    authority: AccountInfo<'info>,
+    bump: u8,
+}
+
+impl<'info> EventEmitter<'info> {
+    fn new(authority: &AccountInfo<'info>, bump: u8) -> Self {
+        Self { authority: authority.clone(), bump }
+    }
+    
+    fn emit<E: anchor_lang::Event>(&self, event: E) -> Result<()> {
+        let data: Vec<u8> = anchor_lang::event::EVENT_IX_TAG_LE
+            .into_iter()
+            .chain(event.data())
+            .collect();
+        let ix = Instruction::new_with_bytes(crate::ID, &data, vec![AccountMeta::new_readonly(self.authority.key(), true)]);
+        invoke_signed(&ix, std::slice::from_ref(&self.authority), &[&[b"__event_authority", &[self.bump]]])?;
+        Ok(())
+    }
+}
+
+// Helper function calling an SPL account compression instruction on the archive tree,
+// signed by the config PDA as tree authority
+fn invoke_compression<'info>(
+    name: &str,
+    args: &[u8],
+    merkle_tree: &UncheckedAccount<'info>,
+    config: &Account<'info, Config>,
+    noop_program: &UncheckedAccount<'info>,
+    compression_program: &UncheckedAccount<'info>,
+) -> Result<()> {
+    // Anchor instruction layout: sighash discriminator, then the Borsh-encoded args
+    let mut data = hashv(&[format!("global:{}", name).as_bytes()]).to_bytes()[..8].to_vec();
+    data.extend_from_slice(args);
+    let instruction = Instruction {
+        program_id: ACCOUNT_COMPRESSION_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(merkle_tree.key(), false),
+            AccountMeta::new_readonly(config.key(), true),
+            AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        ],
+        data,
+    };
+    
+    invoke_signed(
+        &instruction,
+        &[
+            merkle_tree.to_account_info(),
+            config.to_account_info(),
+            noop_program.to_account_info(),
+            compression_program.to_account_info(),
+        ],
+        &[&[b"config", &[config.bump]]],
+    )?;
+    Ok(())
+}
+
+// Helper function announcing the shot just resolved, the game's last move
+fn emit_shot_resolved(game: &Game, game_key: Pubkey, shooter: Pubkey, events: &EventEmitter) -> Result<()> {
+    events.emit(ShotResolved {
+        game: game_key,
+        shooter,
+        x: game.last_move.x,
+        y: game.last_move.y,
+        result: game.last_move.result,
+    })
+}
+
+// Helper function recording a resolved shot as the game's last move and, if the game keeps a
+// move log, appending it
+fn record_move(game: &mut Game, history: &mut [MoveRecord], coordinate_index: usize, shooter: u8, result: u8) -> Result<()> {
+    let size = game.rules.board_size as usize;
+    let record = MoveRecord {
+        slot: Clock::get()?.slot,
+        x: (coordinate_index % size) as u8,
+        y: (coordinate_index / size) as u8,
+        shooter,
+        result,
+        _padding: [0; 4],
+    };
+    if let Some(entry) = history.get_mut(game.move_count as usize) {
+        *entry = record;
+    }
+    game.last_move = record;
+    game.move_count += 1;
+    Ok(())
+}
+
+// Helper function loading a game together with the move log after it in the account, which
+// is only there once enable_move_history has grown it (an empty slice otherwise)
+fn load_game_with_history<'a>(game: &'a AccountLoader<Game>) -> Result<(RefMut<'a, Game>, RefMut<'a, [MoveRecord]>)> {
+    let data = game.as_ref().try_borrow_mut_data()?;
+    bytemuck::try_cast_slice::<u8, MoveRecord>(&data[Game::LEN..]).map_err(|_| ErrorCode::InvalidMoveHistory)?;
+    Ok(RefMut::map_split(data, |data| {
+        let (head, history) = data.split_at_mut(Game::LEN);
+        (bytemuck::from_bytes_mut(&mut head[8..]), bytemuck::cast_slice_mut(history))
+    }))
+}
+
+// Helper function mapping an in-bounds coordinate to its row-major cell index
+fn cell_index(game: &Game, x: u8, y: u8) -> usize {
+    x as usize + game.rules.board_size as usize * y as usize
+}
+
+// Helper function to drop a game from the lobby registry, if it's listed
+fn remove_lobby_entry(lobby: &mut Lobby, game: Pubkey) {
+    lobby.entries.retain(|entry| entry.game != game);
+}
+
+// Helper function moving a player's honesty bond into the game's vault
+fn post_bond<'info>(
+    system_program: &Program<'info, System>,
+    player: &Signer<'info>,
+    vault: &SystemAccount<'info>,
+    amount: u64,
+) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: player.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        ),
+        amount,
+    )
+}
+
+// Helper function paying lamports out of the game's vault, signed by the vault PDA
+fn pay_from_vault<'info>(
+    system_program: &Program<'info, System>,
+    vault: &SystemAccount<'info>,
+    to: AccountInfo<'info>,
+    game_key: Pubkey,
+    vault_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let vault_seeds: &[&[u8]] = &[b"vault", game_key.as_ref(), &[vault_bump]];
+    
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: vault.to_account_info(),
+                to,
+            },
+            &[vault_seeds],
+        ),
+        amount,
+    )
+}
+
+// Helper function totalling the bonds still held in the vault, along with a proposed rematch's
+// top-up, which stays the proposer's until the rematch is accepted
+fn outstanding_bonds(game: &Game) -> u64 {
+    bond_left(game, 1) + bond_left(game, 2) + game.rematch_top_up
+}
+
+// Helper function giving the stakes in a game's vault, i.e. everything but the unsettled bonds
+fn vault_pot(vault: &SystemAccount, game: &Game) -> Result<u64> {
+    vault.lamports().checked_sub(outstanding_bonds(game)).ok_or_else(|| error!(ErrorCode::VaultShortfall))
+}
+
+// Helper function giving what remains of a player's bond in the vault (0 once settled)
+fn bond_left(game: &Game, player_num: u8) -> u64 {
+    let (settled, docked) = if player_num == 1 {
+        (game.bond1_settled != 0, game.bond_docked1)
+    } else {
+        (game.bond2_settled != 0, game.bond_docked2)
+    };
+    if settled { 0 } else { game.bond - docked }
+}
+
+// Helper function paying a keeper's bounty out of the defaulting player's bond
+fn pay_keeper_bounty<'info>(
+    system_program: &Program<'info, System>,
+    vault: &SystemAccount<'info>,
+    keeper: AccountInfo<'info>,
+    game: &mut Game,
+    game_key: Pubkey,
+    defaulter: u8,
+) -> Result<u64> {
+    let bounty = KEEPER_BOUNTY_LAMPORTS.min(bond_left(game, defaulter));
+    if defaulter == 1 {
+        game.bond_docked1 += bounty;
+    } else {
+        game.bond_docked2 += bounty;
+    }
+    
+    pay_from_vault(system_program, vault, keeper, game_key, game.vault_bump, bounty)?;
+    Ok(bounty)
+}
+
+// Helper function dealing the house's 10x10 board from a seed (the VRF value hashed with the
+// house's secret), as a ship id per cell (0 = water). Each standard-fleet ship, largest first, takes the first placement drawn from
+// hashv(seed, draw) that fits without overlapping the ships already placed
+fn deal_house_cells(seed: &[u8; 32]) -> [u8; 100] {
+    let mut board = [0u8; 100];
+    let mut draw: u32 = 0;
+    for (ship, &size) in STANDARD_FLEET.iter().enumerate() {
+        loop {
+            let bytes = hashv(&[seed, &draw.to_le_bytes()]).to_bytes();
+            draw += 1;
+            
+            let (along, across) = ((bytes[1] % (11 - size)) as usize, (bytes[2] % 10) as usize);
+            let horizontal = bytes[0] & 1 == 0;
+            let cells: Vec<usize> = (along..along + size as usize)
+                .map(|i| if horizontal { across * 10 + i } else { i * 10 + across })
+                .collect();
+            if cells.iter().all(|&cell| board[cell] == 0) {
+                for cell in cells {
+                    board[cell] = ship as u8 + 1;
+                }
+                break;
+            }
+        }
+    }
+    board
+}
+
+// Helper function checking a solo shot's answer against a puzzle or challenge board, whose
+// commitment is bound to that account and to whoever answers for it
+fn prove_solo_cell(
+    board: &Pubkey,
+    answerer: &Pubkey,
+    commitment: &[u8; 32],
+    index: usize,
+    ship_id: u8,
+    cell_salt: &[u8; 32],
+    proof: &[[u8; 32]],
+) -> Result<()> {
+    let hash = CommitHash::Sha256;
+    let leaf = cell_leaf(hash, index, ship_id, cell_salt);
+    let root = merkle_root_from_proof(hash, leaf, index, proof);
+    require!(
+        proof.len() == merkle_depth(100) && bound_commitment(hash, root, board, answerer) == *commitment,
+        ErrorCode::InvalidCellProof
+    );
+    Ok(())
+}
+
+// Helper function answering a solo shot (house, puzzle or challenge). Every solo board is 10x10
+// with the standard fleet, so the answer must name one of its ships and no ship can take more
+// hits than it has cells; the answer is proven against the board's commitment, then marked.
+// Returns whether the fleet is now sunk
+#[allow(clippy::too_many_arguments)]
+fn answer_solo_shot(
+    board: &Pubkey,
+    answerer: &Pubkey,
+    commitment: &[u8; 32],
+    board_hits: &mut [u8; PACKED_CELLS],
+    ship_hits: &mut [u8; 5],
+    hits: &mut u8,
+    index: usize,
+    ship_id: u8,
+    cell_salt: &[u8; 32],
+    proof: &[[u8; 32]],
+) -> Result<bool> {
+    require!((ship_id as usize) <= STANDARD_FLEET.len(), ErrorCode::InvalidShipId);
+    require!(
+        ship_id == 0 || ship_hits[(ship_id - 1) as usize] < STANDARD_FLEET[(ship_id - 1) as usize],
+        ErrorCode::InvalidShipId
+    );
+    prove_solo_cell(board, answerer, commitment, index, ship_id, cell_salt, proof)?;
+    
+    if ship_id == 0 {
+        set_hit_cell(board_hits, index, 1); // 1 = miss
+        return Ok(false);
+    }
+    set_hit_cell(board_hits, index, 2); // 2 = hit
+    *hits += 1;
+    let ship = (ship_id - 1) as usize;
+    ship_hits[ship] += 1;
+    if ship_hits[ship] == STANDARD_FLEET[ship] {
+        msg!("🔥 A {}-square ship went down!", STANDARD_FLEET[ship]);
+    }
+    Ok(*hits as usize >= STANDARD_FLEET.iter().map(|&size| size as usize).sum::<usize>())
+}
+
+// Helper function building a solo shot's outcome; only the player ever fires
+fn solo_shot_outcome(board_hits: &[u8; PACKED_CELLS], ship_hits: &[u8; 5], x: u8, y: u8, state: u8) -> ShotOutcome {
+    let ships_remaining = STANDARD_FLEET
+        .iter()
+        .zip(ship_hits)
+        .filter(|&(&size, &hits)| hits < size)
+        .count() as u8;
+    ShotOutcome {
+        x,
+        y,
+        result: hit_cell(board_hits, (y as usize) * 10 + (x as usize)),
+        ships_remaining,
+        turn: 1,
+        state,
+    }
+}
+
+// Helper function checking a revealed puzzle or challenge board against its commitment, and
+// that it holds the standard fleet legally placed
+fn verify_solo_board(
+    board: &Pubkey,
+    answerer: &Pubkey,
+    commitment: &[u8; 32],
+    original_board: &[u8],
+    salt: &[u8; 32],
+) -> Result<()> {
+    require!(original_board.len() == 100, ErrorCode::InvalidBoardSize);
+    
+    let hash = CommitHash::Sha256;
+    let computed_root = board_merkle_root(hash, original_board, salt);
+    require!(
+        bound_commitment(hash, computed_root, board, answerer) == *commitment,
+        ErrorCode::CommitmentMismatch
+    );
+    let ship_count = original_board.iter().filter(|&&cell| cell != 0).count();
+    require!(
+        ship_count == STANDARD_FLEET.iter().map(|&size| size as usize).sum::<usize>(),
+        ErrorCode::InvalidFleetConfiguration
+    );
+    require!(
+        validate_fleet_placement(original_board, 10, &STANDARD_FLEET, false),
+        ErrorCode::InvalidShipPlacement
+    );
+    Ok(())
+}
+
+// Helper function giving today's date as whole days since the Unix epoch (UTC)
+fn current_day() -> Result<i64> {
+    Ok(Clock::get()?.unix_timestamp.div_euclid(SECONDS_PER_DAY))
+}
+
+// Helper function checking a new game's wager against the admin's limits for its mint;
+// casual games stake nothing and aren't limited
+fn check_wager_limits(config: &Config, mint: Pubkey, wager: u64) -> Result<()> {
+    if let Some(limit) = config.wager_limits.iter().find(|limit| limit.mint == mint) {
+        if wager > 0 {
+            require!(wager >= limit.min_wager, ErrorCode::WagerTooSmall);
+            require!(limit.max_wager == 0 || wager <= limit.max_wager, ErrorCode::WagerTooLarge);
+        }
+    }
+    Ok(())
+}
+
+// Helper function giving the state a game ends in: awaiting the board reveals, or straight
+// to Finished when every answer was proven in zero knowledge as it was given
+fn game_over_state(game: &Game) -> GameState {
+    if game.rules.variants & VARIANT_ZK_SHOTS != 0 {
+        GameState::Finished
+    } else {
+        GameState::AwaitingReveal
+    }
+}
+
+// Helper function ending the game in the opponent's favour when a player runs out of time
+fn end_by_timeout(game: &mut Game, winner: u8) -> Result<()> {
+    // Abandoned before anyone fired: no contest, so the stakes go back as in a draw
+    let shots_fired = game.move_count > 0 || game.pending_shot().is_some() || !game.pending_salvo().is_empty();
+    if shots_fired {
+        game.set_state(game_over_state(game));
+        game.winner = winner;
+        game.end_reason = 2; // 2 = timeout
+    } else {
+        game.set_state(GameState::Finished); // No shot results for a reveal to check
+        game.winner = 0;
+        game.end_reason = 9; // 9 = abandoned before the first shot
+    }
+    game.set_pending_shot(None);
+    game.set_pending_salvo(&[]);
+    game.pending_shot_by = Pubkey::default();
+    game.last_action_slot = Clock::get()?.slot;
+    Ok(())
+}
+
+// Helper function checking a finished game has nothing left to pay out before closing
+fn require_closeable(game: &Game) -> Result<()> {
+    require!(game.is_over(), ErrorCode::GameNotOver);
+    require!(
+        (game.wager == 0 && game.nft_mint1 == Pubkey::default()) || game.winnings_claimed != 0,
+        ErrorCode::WinningsNotClaimed
+    );
+    
+    let reveal_deadline_passed = Clock::get()?.slot
+        > game.last_action_slot.saturating_add(REVEAL_DEADLINE_SLOTS);
+    require!(
+        (game.player1_revealed != 0 && game.player2_revealed != 0) || reveal_deadline_passed,
+        ErrorCode::BoardsNotRevealed
+    );
+    
+    // Don't close away a bond an honest revealer can still collect
+    let player1_can_claim = game.player1_revealed != 0 && game.cheater != 1 && game.bond2_settled == 0;
+    let player2_can_claim = game.player2_revealed != 0 && game.cheater != 2 && game.bond1_settled == 0;
+    require!(!player1_can_claim && !player2_can_claim, ErrorCode::BondsNotSettled);
+    require!(game.rematch_proposer == 0, ErrorCode::RematchPending);
+    require!(game.spectator_bets == 0, ErrorCode::BetsOutstanding);
+    Ok(())
+}
+
+// Helper function checking an agreed draw is ready to split the pot
+fn require_draw_settleable(game: &Game) -> Result<()> {
+    require!(game.is_over() && matches!(game.end_reason, 5 | 9), ErrorCode::NotADraw);
+    // A board caught cheating at reveal turns the draw into a win for the honest player
+    require!(game.winner == 0 && game.cheater == 0, ErrorCode::NotADraw);
+    require!(game.state() == GameState::Finished, ErrorCode::BoardsNotRevealed);
+    require!(game.wager > 0 || game.nft_mint1 != Pubkey::default(), ErrorCode::NoWager);
+    require!(game.winnings_claimed == 0, ErrorCode::WinningsAlreadyClaimed);
+    require!(game.rules.best_of == 1, ErrorCode::SeriesInProgress);
+    Ok(())
+}
+
+// Helper function checking a display or clan name is 3-20 lowercase letters, digits or underscores
+fn require_valid_name(name: &str) -> Result<()> {
+    require!(
+        (MIN_NAME_LEN..=MAX_NAME_LEN).contains(&name.len())
+            && name.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'_'),
+        ErrorCode::InvalidName
+    );
+    Ok(())
+}
+
+// Helper function adding a member's ranked result to their clan's totals
+fn record_clan_result(clan: &mut Clan, game: &Game, player_num: u8) {
+    match game.winner {
+        0 => clan.draws += 1,
+        winner if winner == player_num => clan.wins += 1,
+        _ => clan.losses += 1,
+    }
+}
+
+// Helper function scoring a game for a clan war when it was between opposing rosters and
+// finished inside the war's window; a win is worth a point, a draw nothing. Returns whether
+// the game counted for the war at all
+fn score_war_game(war: &mut ClanWar, game: &Game) -> bool {
+    let in_window = war.state == 1 && (war.start_slot..=war.end_slot).contains(&game.last_action_slot);
+    let side = |player: Pubkey| {
+        if war.roster_a.contains(&player) {
+            1
+        } else if war.roster_b.contains(&player) {
+            2
+        } else {
+            0
+        }
+    };
+    let (side1, side2) = (side(game.player1), side(game.player2));
+    if !in_window || side1 == 0 || side2 == 0 || side1 == side2 {
+        return false;
+    }
+    match game.winner {
+        1 if side1 == 1 => war.points_a += 1,
+        1 => war.points_b += 1,
+        2 if side2 == 1 => war.points_a += 1,
+        2 => war.points_b += 1,
+        _ => {}
+    }
+    true
+}
+
+// Helper function folding one finished game into a player's lifetime stats
+fn record_profile_stats(profile: &mut PlayerProfile, game: &Game, boards: [&PlayerBoard; 2], is_player1: bool) {
+    let opponent_hits = &boards[seat_index(!is_player1)].hits;
+    let (player_num, opponent_ship_hits) = if is_player1 {
+        (1, &game.ship_hits2)
+    } else {
+        (2, &game.ship_hits1)
+    };
+    
+    profile.games_played += 1;
+    match game.winner {
+        0 => profile.draws += 1,
+        winner if winner == player_num => profile.wins += 1,
+        _ => profile.losses += 1,
+    }
+    
+    // The opponent's hit board records every shot this player fired (1 = miss, 2 = hit)
+    let cells = (0..game.rules.cells()).map(|i| hit_cell(opponent_hits, i));
+    profile.shots_fired += cells.clone().filter(|&cell| cell != 0).count() as u32;
+    profile.hits += cells.filter(|&cell| cell == 2).count() as u32;
+    profile.ships_sunk += game
+        .fleet_of(!is_player1)
+        .iter()
+        .zip(opponent_ship_hits.iter())
+        .filter(|(&len, &hits)| hits == len)
+        .count() as u32;
+}
+
+// Helper function crediting a finished game and an honest reveal, and docking a game lost
+// to the clock or to a missing reveal, then rederiving the reputation score
+fn update_reputation(profile: &mut PlayerProfile, game: &Game, player_num: u8) {
+    let revealed = if player_num == 1 { game.player1_revealed } else { game.player2_revealed };
+    if revealed != 0 && game.cheater != player_num {
+        profile.timely_reveals += 1;
+    }
+    if matches!(game.end_reason, 2 | 4) && game.winner == 3 - player_num {
+        profile.timeouts += 1;
+    }
+    profile.reputation = (profile.games_played + profile.timely_reveals)
+        .saturating_sub(profile.timeouts * REPUTATION_TIMEOUT_PENALTY);
+}
+
+// Helper function counting a result toward the game's season, first restarting the
+// profile's season stats if this is its first game of that season
+fn record_season_stats(profile: &mut PlayerProfile, game: &Game, player_num: u8) {
+    if profile.season != game.season {
+        profile.season = game.season;
+        profile.season_rating = INITIAL_RATING;
+        profile.season_wins = 0;
+        profile.season_losses = 0;
+        profile.season_draws = 0;
+    }
+    
+    match game.winner {
+        0 => profile.season_draws += 1,
+        winner if winner == player_num => profile.season_wins += 1,
+        _ => profile.season_losses += 1,
+    }
+}
+
+// Helper function extending or breaking the player's streaks; a draw breaks both
+fn update_streaks(
+    profile: &mut PlayerProfile,
+    game: &Game,
+    game_key: Pubkey,
+    player_num: u8,
+    events: &EventEmitter,
+) -> Result<()> {
+    match game.winner {
+        0 => {
+            profile.win_streak = 0;
+            profile.loss_streak = 0;
+        }
+        winner if winner == player_num => {
+            profile.win_streak += 1;
+            profile.loss_streak = 0;
+            profile.best_win_streak = profile.best_win_streak.max(profile.win_streak);
+            if profile.win_streak % STREAK_MILESTONE == 0 {
+                events.emit(StreakMilestone {
+                    player: profile.wallet,
+                    game: game_key,
+                    streak: profile.win_streak,
+                })?;
+            }
+        }
+        _ => {
+            profile.win_streak = 0;
+            profile.loss_streak += 1;
+        }
+    }
+    Ok(())
+}
+
+// Helper function unlocking whatever achievements a recorded game earned the player
+fn award_achievements(
+    profile: &mut PlayerProfile,
+    game: &Game,
+    game_key: Pubkey,
+    player_num: u8,
+    events: &EventEmitter,
+) -> Result<()> {
+    if game.winner != player_num {
+        return Ok(());
+    }
+    let own_ship_hits = if player_num == 1 { &game.ship_hits1 } else { &game.ship_hits2 };
+    let ships_lost = game.fleet_of(player_num == 1).iter().zip(own_ship_hits.iter()).filter(|(&len, &hits)| hits == len).count();
+    
+    let earned = [
+        (ACHIEVEMENT_FIRST_WIN, true),
+        (ACHIEVEMENT_FLAWLESS_VICTORY, ships_lost == 0),
+        (ACHIEVEMENT_WIN_STREAK, profile.win_streak >= WIN_STREAK_ACHIEVEMENT),
+        (ACHIEVEMENT_CARRIER_FINALE, game.carrier_finale != 0),
+    ];
+    for (achievement, unlocked) in earned {
+        if unlocked && profile.achievements & achievement == 0 {
+            profile.achievements |= achievement;
+            events.emit(AchievementUnlocked {
+                player: profile.wallet,
+                game: game_key,
+                achievement,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+// Helper function applying one ELO update (score: 1 = win, 0.5 = draw, 0 = loss)
+fn elo_rating(rating: u32, opponent_rating: u32, score: f64) -> u32 {
+    let expected = 1.0 / (1.0 + 10f64.powf((opponent_rating as f64 - rating as f64) / 400.0));
+    let delta = (ELO_K_FACTOR * (score - expected)).round() as i64;
+    (rating as i64 + delta).max(0) as u32
+}
+
+// Helper function ordering Swiss entrants by score, ties broken by registration
+// order, so adjacent players (2i vs 2i+1) form the next round's pairings
+fn swiss_standings(tournament: &Tournament) -> Vec<Pubkey> {
+    let mut seats: Vec<usize> = (0..tournament.entrants.len()).collect();
+    seats.sort_by(|&a, &b| tournament.scores[b].cmp(&tournament.scores[a]).then(a.cmp(&b)));
+    seats.into_iter().map(|seat| tournament.entrants[seat]).collect()
+}
+
+// Helper function reading who referred a player from their profile, if they passed it
+fn referrer_of(profile: &Option<Account<PlayerProfile>>) -> Pubkey {
+    profile.as_ref().map_or(Pubkey::default(), |profile| profile.referrer)
+}
+
+// Helper function noting who referred a player, on their first game only
+fn record_referral(
+    player_profile: &mut Option<Account<PlayerProfile>>,
+    referrer_profile: &Option<Account<PlayerProfile>>
+) {
+    if let (Some(profile), Some(referrer)) = (player_profile.as_mut(), referrer_profile) {
+        let first_game = profile.games_played == 0 && profile.referrer == Pubkey::default();
+        if first_game && referrer.wallet != profile.wallet {
+            profile.referrer = referrer.wallet;
+            msg!("🤝 Player {} was referred by {}", profile.wallet, referrer.wallet);
+        }
+    }
+}
+
+// Helper function computing the protocol's rake on a payout
+fn protocol_fee(config: &Config, pot: u64) -> u64 {
+    (pot as u128 * config.fee_bps as u128 / 10_000) as u64
+}
+
+// Helper function placing a profile on the leaderboard by season rating, dropping whoever
+// falls off the bottom
+fn update_leaderboard(leaderboard: &mut Leaderboard, profile: &PlayerProfile) {
+    leaderboard.entries.retain(|entry| entry.player != profile.wallet);
+    leaderboard.entries.push(LeaderboardEntry {
+        player: profile.wallet,
+        rating: profile.season_rating,
+        wins: profile.season_wins,
+    });
+    leaderboard.entries.sort_by(|a, b| b.rating.cmp(&a.rating).then(b.wins.cmp(&a.wins)));
+    leaderboard.entries.truncate(MAX_LEADERBOARD_ENTRIES);
+}
+
+// Helper function to resolve the winner's wallet from the winner slot
+fn game_winner(game: &Game) -> Result<Pubkey> {
+    match game.winner {
+        1 => Ok(game.player1),
+        2 => Ok(game.player2),
+        _ => err!(ErrorCode::NotWinner),
+    }
+}
+
+// Helper function deriving a cell's salt from the board's master salt, so revealing
+// one cell at shot time never exposes the others
+fn cell_salt(hash: CommitHash, salt: &[u8; 32], index: usize) -> [u8; 32] {
+    hash.hashv(&[salt, &(index as u16).to_le_bytes()])
+}
+
+// Helper function binding a board's Merkle root to its game and owner, so a commitment can't
+// be replayed from another game or copied from the opponent (the board salt is the nonce)
+fn bound_commitment(hash: CommitHash, root: [u8; 32], game: &Pubkey, player: &Pubkey) -> [u8; 32] {
+    hash.hashv(&[&root, game.as_ref(), player.as_ref()])
+}
+
+// Helper function hashing one committed cell (0 = water, n = ship n) into a Merkle leaf
+fn cell_leaf(hash: CommitHash, index: usize, value: u8, cell_salt: &[u8; 32]) -> [u8; 32] {
+    hash.hashv(&[&(index as u16).to_le_bytes(), &[value], cell_salt])
+}
+
+// Helper function folding a leaf and its sibling path up to the root it proves
+fn merkle_root_from_proof(hash: CommitHash, leaf: [u8; 32], index: usize, proof: &[[u8; 32]]) -> [u8; 32] {
+    let mut node = leaf;
+    let mut position = index;
+    
+    for sibling in proof {
+        node = if position & 1 == 0 {
+            hash.hashv(&[&node, sibling])
+        } else {
+            hash.hashv(&[sibling, &node])
+        };
+        position /= 2;
+    }
+    
+    node
+}
+
+// Helper function giving the proof length for a board: leaves are padded to a power of two
+fn merkle_depth(cells: usize) -> usize {
+    cells.next_power_of_two().trailing_zeros() as usize
+}
+
+// Helper function checking a creator-chosen fleet fits the board
+fn validate_fleet(fleet: &[u8], rules: &GameRules) -> bool {
+    // Empty = standard fleet, which fits every supported board
+    fleet.is_empty()
+        || (fleet.len() <= MAX_SHIPS
+            && fleet.iter().all(|&len| len >= 1 && len <= rules.board_size)
+            && fleet.iter().map(|&len| len as usize).sum::<usize>() <= rules.cells() / 2)
+}
+
+// Helper function counting the squares a player's full fleet occupies (the win condition
+// against them)
+fn fleet_cells(game: &Game, player1: bool) -> usize {
+    game.fleet_of(player1).iter().map(|&len| len as usize).sum()
+}
+
+// Helper function computing the Merkle root of a fully revealed board
+fn board_merkle_root(hash: CommitHash, board: &[u8], salt: &[u8; 32]) -> [u8; 32] {
+    let leaves: Vec<[u8; 32]> = board
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| cell_leaf(hash, i, value, &cell_salt(hash, salt, i)))
+        .collect();
+    merkle_root_from_leaves(hash, &leaves)
+}
+
+// Helper function folding a board's cell leaves up to their Merkle root
+fn merkle_root_from_leaves(hash: CommitHash, leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = (0..leaves.len().next_power_of_two())
+        .map(|i| leaves.get(i).copied().unwrap_or([0; 32])) // Zeroed padding leaves
+        .collect();
+    
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash.hashv(&[&pair[0], &pair[1]]))
+            .collect();
+    }
+    
+    level[0]
+}
+
+// Helper function checking that each ship id on a revealed board covers exactly its
+// fleet length in one straight, unbroken line on a single layer (on a hex grid, along any
+// of its three axes)
+fn validate_fleet_placement(board: &[u8], size: usize, fleet: &[u8], hex: bool) -> bool {
+    if board.iter().any(|&cell| cell as usize > fleet.len() && cell != MINE_CELL) {
+        return false;
+    }
+    
+    fleet.iter().enumerate().all(|(ship, &len)| {
+        let ship_id = (ship + 1) as u8;
+        let cells: Vec<usize> = (0..board.len()).filter(|&i| board[i] == ship_id).collect();
+        
+        let horizontal = cells.windows(2).all(|w| w[1] == w[0] + 1 && w[1] / size == w[0] / size);
+        let vertical = cells.windows(2).all(|w| w[1] == w[0] + size && w[1] / (size * size) == w[0] / (size * size));
+        // Hex ships can also step to (x - 1, y + 1), which is size - 1 cells further on
+        let third_axis = hex
+            && cells.windows(2).all(|w| {
+                w[1] == w[0] + size - 1 && w[1] / size == w[0] / size + 1 && w[1] / (size * size) == w[0] / (size * size)
+            });
+        
+        cells.len() == len as usize && (horizontal || vertical || third_axis)
+    })
+}
+
+// Helper function checking that on a layered board the fleet's last ship, its submarine,
+// lies below the surface and every other ship floats on it
+fn valid_depths(game: &Game, board: &[u8], fleet: &[u8]) -> bool {
+    let layer_cells = game.rules.board_size as usize * game.rules.board_size as usize;
+    game.rules.layers() == 1
+        || board.iter().enumerate().all(|(i, &cell)| {
+            cell == 0 || cell == MINE_CELL || (cell as usize == fleet.len()) == (i >= layer_cells)
+        })
+}
+
+// Helper function checking whether two different ships sit on neighbouring cells of the
+// same layer, diagonals included (mines don't count as ships)
+fn ships_touch(board: &[u8], size: usize, hex: bool) -> bool {
+    let is_ship = |cell: u8| cell != 0 && cell != MINE_CELL;
+    // Looking right, down and both down-diagonals covers every pair once; a hex cell's
+    // forward neighbours are just right, down and down-left
+    let forward: &[(usize, isize)] = if hex { &[(0, 1), (1, -1), (1, 0)] } else { &[(0, 1), (1, -1), (1, 0), (1, 1)] };
+    (0..board.len()).filter(|&i| is_ship(board[i])).any(|i| {
+        let (row, col) = (i / size, i % size);
+        forward.iter().any(|&(dr, dc)| {
+            let (r, c) = (row + dr, col as isize + dc);
+            if r * size >= board.len() || r / size != row / size || c < 0 || c >= size as isize {
+                return false;
+            }
+            let neighbour = board[r * size + c as usize];
+            is_ship(neighbour) && neighbour != board[i]
+        })
+    })
+}
+
+// Helper function to verify shot consistency after both boards are revealed
+fn verify_shot_consistency(
+    game: &Game, 
+    hits_board: &[u8; PACKED_CELLS],
+    ships: &CellMask,
+    mines: &CellMask,
+    is_player1_board: bool
+) -> bool {
+    let shots_consistent = shots_match_board(hits_board, ships, mines);
+    
+    // The opponent's answered radar scan must match the ship cells around its center
+    let (scan, scan_state) = if is_player1_board {
+        (game.radar_scan2, game.radar_state2)
+    } else {
+        (game.radar_scan1, game.radar_state1)
+    };
+    let scan_consistent = scan_state != 2
+        || radar_ship_cells(ships, game.rules.board_size, scan[0], scan[1]) == scan[2];
+    
+    shots_consistent && scan_consistent
+}
+
+// Helper function checking a hit board against a revealed board, a word at a time: a miss
+// must be open water, a hit a ship and a struck mine a mine, while unshot cells aren't checked
+fn shots_match_board(hits_board: &[u8; PACKED_CELLS], ships: &CellMask, mines: &CellMask) -> bool {
+    let [misses, hits, mines_struck] = hit_masks(hits_board);
+    (0..MASK_WORDS).all(|w| {
+        misses[w] & (ships[w] | mines[w]) == 0
+            && hits[w] & !ships[w] == 0
+            && mines_struck[w] & !mines[w] == 0
+    })
+}
+
+// Helper function gathering the even bits of a word into its low 32 bits
+fn even_bits(mut x: u64) -> u64 {
+    x &= 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x >> 4)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x >> 8)) & 0x0000_ffff_0000_ffff;
+    (x | (x >> 16)) & 0x0000_0000_ffff_ffff
+}
+
+// Helper function splitting a packed hit board into masks of its misses, hits and mines,
+// 32 cells (8 packed bytes) at a time
+fn hit_masks(hits: &[u8; PACKED_CELLS]) -> [CellMask; 3] {
+    let mut masks = [[0u64; MASK_WORDS]; 3];
+    for (chunk_index, chunk) in hits.chunks(8).enumerate() {
+        let mut bytes = [0u8; 8];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let packed = u64::from_le_bytes(bytes);
+        let (low, high) = (even_bits(packed), even_bits(packed >> 1));
+        
+        let (word, shift) = (chunk_index / 2, chunk_index % 2 * 32);
+        masks[0][word] |= (low & !high) << shift; // 1 = miss
+        masks[1][word] |= (high & !low) << shift; // 2 = hit
+        masks[2][word] |= (low & high) << shift;  // 3 = mine
+    }
+    masks
+}
+
+// Helper function reading one cell of a cell mask
+fn mask_bit(mask: &CellMask, index: usize) -> bool {
+    mask[index / 64] >> (index % 64) & 1 != 0
+}
+
+// Helper function turning a revealed board into masks of its ship cells and its mines
+fn board_masks(board: &[u8]) -> (CellMask, CellMask) {
+    let mut ships = [0u64; MASK_WORDS];
+    let mut mines = [0u64; MASK_WORDS];
+    for (i, &cell) in board.iter().enumerate() {
+        let bit = 1u64 << (i % 64);
+        if cell == MINE_CELL {
+            mines[i / 64] |= bit;
+        } else if cell != 0 {
+            ships[i / 64] |= bit;
+        }
+    }
+    (ships, mines)
+}
+
+// Helper function checking a revealed board hides no more mines than the variant allows
+fn valid_mines(game: &Game, board: &[u8]) -> bool {
+    let mines = board.iter().filter(|&&cell| cell == MINE_CELL).count();
+    let allowed = if game.rules.variants & VARIANT_MINES != 0 { MAX_MINES } else { 0 };
+    mines <= allowed
+}
+
+// Helper function counting the ship cells in the 3x3 area around (x, y), clipped to the board
+fn radar_ship_cells(ships: &CellMask, size: u8, x: u8, y: u8) -> u8 {
+    let span = |center: u8| center.saturating_sub(1)..=(center + 1).min(size - 1);
+    span(y)
+        .flat_map(|row| span(x).map(move |col| row as usize * size as usize + col as usize))
+        .filter(|&index| mask_bit(ships, index))
+        .count() as u8
+}
+
+// Helper function handing the win to the honest player once a revealed board
+// contradicts the recorded shot results
+fn flag_cheater(game: &mut Game, game_key: Pubkey, cheater_is_player1: bool, events: &EventEmitter) -> Result<()> {
+    let (cheater, cheater_num, honest_num) = if cheater_is_player1 {
+        (game.player1, 1, 2)
+    } else {
+        (game.player2, 2, 1)
+    };
+    
+    game.cheater = cheater_num;
+    game.winner = honest_num;
+    
+    events.emit(CheatingDetected {
+        game: game_key,
+        cheater,
+    })?;
+    
+    msg!("🚨 Player {} cheated! Player{} wins.", cheater, honest_num);
+    Ok(())
+}
+
+// Helper function checking a free-for-all is being played
+fn require_ffa_in_progress(game: &FfaGame) -> Result<()> {
+    match game.state() {
+        GameState::InProgress => Ok(()),
+        GameState::WaitingForOpponent => err!(ErrorCode::GameNotReady),
+        _ => err!(ErrorCode::GameOver),
+    }
+}
+
+// Helper function knocking a player out of a free-for-all; the last fleet (or team) afloat wins
+fn eliminate_ffa_player(game: &mut FfaGame, game_key: Pubkey, index: usize, events: &EventEmitter) -> Result<()> {
+    game.eliminated[index] = 1;
+    events.emit(PlayerEliminated {
+        game: game_key,
+        player: game.players[index],
+    })?;
+    msg!("☠️ Player {} has been eliminated", game.players[index]);
+    
+    // Teammates share a side by seat parity; otherwise every seat is its own side
+    let side = |seat: usize| if game.teams != 0 { seat % 2 } else { seat };
+    let mut alive = (0..game.player_count as usize).filter(|&i| game.eliminated[i] == 0);
+    if let Some(first) = alive.next() {
+        if alive.all(|seat| side(seat) == side(first)) {
+            game.winner = first as u8 + 1;
+            game.set_state(GameState::Finished);
+            msg!("🏆 Player {} wins the game!", game.players[first]);
+        }
+    }
+    Ok(())
+}
+
+// Helper function giving the seat that fires for the current turn. A sunk team player's
+// teammate takes their turns, so the two pairs keep alternating
+fn ffa_shooter(game: &FfaGame) -> usize {
+    let seat = game.turn as usize;
+    if game.teams != 0 && game.eliminated[seat] != 0 {
+        (seat + 2) % 4
+    } else {
+        seat
+    }
+}
+
+// Helper function giving the seats that share a finished free-for-all's win
+fn ffa_winning_seats(game: &FfaGame) -> Vec<usize> {
+    if game.winner == 0 {
+        return Vec::new();
+    }
+    let seat = (game.winner - 1) as usize;
+    if game.teams != 0 {
+        vec![seat, (seat + 2) % 4]
+    } else {
+        vec![seat]
+    }
+}
+
+// Helper function telling whether a free-for-all's stakes go back to the honest boards: a
+// winner was caught cheating, or the reveal window closed before every winner showed their board
+fn ffa_voided(game: &FfaGame, slot: u64) -> bool {
+    match game.state() {
+        GameState::Cancelled => true,
+        GameState::Finished => {
+            game.winnings_claimed == 0
+                && slot > game.last_action_slot.saturating_add(REVEAL_DEADLINE_SLOTS)
+                && !ffa_winning_seats(game).iter().all(|&seat| game.revealed[seat] == 1)
+        }
+        _ => false,
+    }
+}
+
+// Helper function passing a free-for-all turn to the next player still afloat
+fn advance_ffa_turn(game: &mut FfaGame) {
+    if game.state() != GameState::InProgress {
+        return;
+    }
+    let count = game.player_count;
+    if game.teams != 0 {
+        game.turn = (game.turn + 1) % count;
+    } else if let Some(next) = (1..=count)
+        .map(|offset| (game.turn + offset) % count)
+        .find(|&i| game.eliminated[i as usize] == 0)
+    {
+        game.turn = next;
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
 pub struct InitializeGame<'info> {
     #[account(
         init,
         payer = player,
-        space = Game::LEN,
-        seeds = [b"game", player.key().as_ref()],
+        space = Game::LEN,
+        seeds = [b"game", player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player1's hit board and commitment
+    #[account(
+        init,
+        payer = player,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[1]],
+        bump
+    )]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    // Lamport escrow for the wager pot and both honesty bonds
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, seeds = [b"profile", player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Only on a new player's first game: whoever referred them
+    #[account(seeds = [b"profile", referrer_profile.wallet.as_ref()], bump = referrer_profile.bump)]
+    pub referrer_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeGameCpi<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Game::LEN,
+        seeds = [b"game", authority.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player1's hit board and commitment
+    #[account(
+        init,
+        payer = payer,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[1]],
+        bump
+    )]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    // Lamport escrow for both stakes and honesty bonds
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, seeds = [b"profile", authority.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Only on a new player's first game: whoever referred them
+    #[account(seeds = [b"profile", referrer_profile.wallet.as_ref()], bump = referrer_profile.bump)]
+    pub referrer_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    // Plays as player1; needn't hold lamports or be system-owned
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGameCpi<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player2's hit board and commitment
+    #[account(
+        init,
+        payer = payer,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[2]],
+        bump
+    )]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Both profiles, needed to join a ranked game once a rating delta is configured; the
+    // joiner's alone when the game sets a minimum reputation
+    #[account(seeds = [b"profile", game.load()?.player1.as_ref()], bump = player1_profile.bump)]
+    pub player1_profile: Option<Account<'info, PlayerProfile>>,
+    
+    #[account(mut, seeds = [b"profile", authority.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Only on a new player's first game: whoever referred them
+    #[account(seeds = [b"profile", referrer_profile.wallet.as_ref()], bump = referrer_profile.bump)]
+    pub referrer_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Option<Account<'info, Lobby>>,
+    
+    // Plays as player2; needn't hold lamports or be system-owned
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGame<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player2's hit board and commitment
+    #[account(
+        init,
+        payer = player,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[2]],
+        bump
+    )]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Both profiles, needed to join a ranked game once a rating delta is configured; the
+    // joiner's alone when the game sets a minimum reputation
+    #[account(seeds = [b"profile", game.load()?.player1.as_ref()], bump = player1_profile.bump)]
+    pub player1_profile: Option<Account<'info, PlayerProfile>>,
+    
+    #[account(mut, seeds = [b"profile", player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Only on a new player's first game: whoever referred them
+    #[account(seeds = [b"profile", referrer_profile.wallet.as_ref()], bump = referrer_profile.bump)]
+    pub referrer_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Option<Account<'info, Lobby>>,
+    
+    #[account(
+        mut,
+        constraint = game.load()?.player1 != player.key() @ ErrorCode::CannotPlayAgainstYourself
+    )]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeGameSpl<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = Game::LEN,
+        seeds = [b"game", player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player1's hit board and commitment
+    #[account(
+        init,
+        payer = player,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[1]],
+        bump
+    )]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    // Escrow for both stakes, owned by the game PDA
+    #[account(
+        init,
+        payer = player,
+        associated_token::mint = mint,
+        associated_token::authority = game
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    // Lamport escrow for both honesty bonds
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    // Only mints the admin has allowed can be wagered
+    #[account(seeds = [b"allowed_mint", mint.key().as_ref()], bump = allowed_mint.bump)]
+    pub allowed_mint: Account<'info, AllowedMint>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = player
+    )]
+    pub player_token_account: Account<'info, TokenAccount>,
+    
+    // Optional: the player's profile, so whoever referred them shares in the rake
+    #[account(seeds = [b"profile", player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeGameNft<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = Game::LEN,
+        seeds = [b"game", player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player1's hit board and commitment
+    #[account(
+        init,
+        payer = player,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[1]],
+        bump
+    )]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(constraint = nft_mint.decimals == 0 && nft_mint.supply == 1 @ ErrorCode::NotAnNft)]
+    pub nft_mint: Account<'info, Mint>,
+    
+    // Escrow for player1's NFT, owned by the game PDA
+    #[account(
+        init,
+        payer = player,
+        associated_token::mint = nft_mint,
+        associated_token::authority = game
+    )]
+    pub vault_nft_account: Account<'info, TokenAccount>,
+    
+    // Lamport escrow for both honesty bonds
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = player
+    )]
+    pub player_nft_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGameSpl<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.wager_mint == mint.key() @ ErrorCode::WrongWagerType
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player2's hit board and commitment
+    #[account(
+        init,
+        payer = player,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[2]],
+        bump
+    )]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = game
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = player
+    )]
+    pub player_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Both profiles, needed to join a ranked game once a rating delta is configured; the
+    // joiner's alone when the game sets a minimum reputation
+    #[account(seeds = [b"profile", game.load()?.player1.as_ref()], bump = player1_profile.bump)]
+    pub player1_profile: Option<Account<'info, PlayerProfile>>,
+    
+    #[account(seeds = [b"profile", player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Option<Account<'info, Lobby>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealTurnNonce<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestRandomness<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    /// CHECK: Switchboard On-Demand randomness account, parsed in the instruction
+    #[account(owner = SWITCHBOARD_ON_DEMAND_PROGRAM_ID)]
+    pub randomness_account: UncheckedAccount<'info>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeRandomness<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    /// CHECK: Must be the account bound by request_randomness, parsed in the instruction
+    #[account(
+        address = game.load()?.randomness_account @ ErrorCode::InvalidRandomnessAccount,
+        owner = SWITCHBOARD_ON_DEMAND_PROGRAM_ID
+    )]
+    pub randomness_account: UncheckedAccount<'info>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FireShot<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    // Only when a session key signs: the profile that authorized it
+    #[account(
+        seeds = [b"profile", profile.wallet.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+    
+    // The player's wallet, or their session key
+    #[account(
+        constraint = game.load()?.is_player(&profile.as_ref().map_or(player.key(), |profile| profile.wallet))
+            @ ErrorCode::NotAPlayer
+    )]
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealShotResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    // Only when a session key signs: the profile that authorized it
+    #[account(
+        seeds = [b"profile", profile.wallet.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+    
+    // The player's wallet, or their session key
+    #[account(
+        constraint = game.load()?.is_player(&profile.as_ref().map_or(player.key(), |profile| profile.wallet))
+            @ ErrorCode::NotAPlayer
+    )]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetGameSummary<'info> {
+    pub game: AccountLoader<'info, Game>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealAndFire<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    // Only when a session key signs: the profile that authorized it
+    #[account(
+        seeds = [b"profile", profile.wallet.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+    
+    // The player's wallet, or their session key
+    pub player: Signer<'info>,
+}
+
+// Permissionless - the player's authority comes from the ed25519 signature
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RelayedMove<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    /// CHECK: Instructions sysvar, read for the preceding ed25519 verification
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RadarScan<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealRadarScan<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MoveShip<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FireBomb<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FireDepthCharge<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FireSalvo<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealSalvoResults<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealBoard<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, constraint = game.load()?.is_player(&player.key()) @ ErrorCode::NotAPlayer)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    // Required for each referrer stamped on the game, who earns a share of the rake
+    #[account(mut, seeds = [b"profile", referrer1_profile.wallet.as_ref()], bump = referrer1_profile.bump)]
+    pub referrer1_profile: Option<Account<'info, PlayerProfile>>,
+    
+    #[account(mut, seeds = [b"profile", referrer2_profile.wallet.as_ref()], bump = referrer2_profile.bump)]
+    pub referrer2_profile: Option<Account<'info, PlayerProfile>>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = config.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    // Only when the opponent timed out mid-game and the game has an abandon refund
+    #[account(mut)]
+    pub loser: Option<SystemAccount<'info>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinningsSpl<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.wager_mint == mint.key() @ ErrorCode::WrongWagerType
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = game
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = player
+    )]
+    pub winner_token_account: Account<'info, TokenAccount>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        seeds = [b"treasury"],
+        bump = config.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    // The admin creates the treasury's ATA for each wager mint
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = treasury
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    // Only when the opponent timed out mid-game and the game has an abandon refund
+    #[account(mut, token::mint = mint)]
+    pub loser_token_account: Option<Account<'info, TokenAccount>>,
+    
+    // Required for each referrer stamped on the game, who is paid a share of the rake in the wager's mint
+    #[account(mut, token::mint = mint)]
+    pub referrer1_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut, token::mint = mint)]
+    pub referrer2_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetHandicapFleet<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.player1 == player.key() @ ErrorCode::NotPlayer1
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OfferDraw<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SendEmote<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SendMessage<'info> {
+    pub game: AccountLoader<'info, Game>,
+    
+    pub sender: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptDraw<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDraw<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGameNft<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player2's hit board and commitment
+    #[account(
+        init,
+        payer = player,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[2]],
+        bump
+    )]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(constraint = nft_mint.decimals == 0 && nft_mint.supply == 1 @ ErrorCode::NotAnNft)]
+    pub nft_mint: Account<'info, Mint>,
+    
+    // Escrow for player2's NFT, owned by the game PDA
+    #[account(
+        init,
+        payer = player,
+        associated_token::mint = nft_mint,
+        associated_token::authority = game
+    )]
+    pub vault_nft_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = player
+    )]
+    pub player_nft_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Both profiles, needed to join a ranked game once a rating delta is configured; the
+    // joiner's alone when the game sets a minimum reputation
+    #[account(seeds = [b"profile", game.load()?.player1.as_ref()], bump = player1_profile.bump)]
+    pub player1_profile: Option<Account<'info, PlayerProfile>>,
+    
+    #[account(seeds = [b"profile", player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Option<Account<'info, Lobby>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimNfts<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(address = game.load()?.nft_mint1 @ ErrorCode::WrongWagerType)]
+    pub nft_mint1: Account<'info, Mint>,
+    
+    #[account(address = game.load()?.nft_mint2 @ ErrorCode::WrongWagerType)]
+    pub nft_mint2: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint1,
+        associated_token::authority = game
+    )]
+    pub vault_nft_account1: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint2,
+        associated_token::authority = game
+    )]
+    pub vault_nft_account2: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = nft_mint1,
+        token::authority = player
+    )]
+    pub winner_nft_account1: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = nft_mint2,
+        token::authority = player
+    )]
+    pub winner_nft_account2: Account<'info, TokenAccount>,
+    
+    // Vault rent refunds
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDrawSpl<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.wager_mint == mint.key() @ ErrorCode::WrongWagerType
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = game
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = game.load()?.player1
+    )]
+    pub player1_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = game.load()?.player2
+    )]
+    pub player2_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTimeoutVictory<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+// Permissionless - anyone can flag a player whose clock has run out
+#[derive(Accounts)]
+pub struct FlagTimeout<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless - anyone can end a game the player to move has abandoned
+#[derive(Accounts)]
+pub struct CrankTimeout<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless - anyone can award the game once the reveal deadline has passed
+#[derive(Accounts)]
+pub struct CrankRevealForfeit<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Resign<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRevealForfeit<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimForfeitedBond<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGame<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.player1 == player.key() @ ErrorCode::NotPlayer1,
+        close = player
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player2 never joined, so only player1's board exists
+    #[account(mut, close = player, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Option<Account<'info, Lobby>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless - each NFT can only go back to its owner's token account
+#[derive(Accounts)]
+pub struct SettleDrawNft<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(address = game.load()?.nft_mint1 @ ErrorCode::WrongWagerType)]
+    pub nft_mint1: Account<'info, Mint>,
+    
+    #[account(address = game.load()?.nft_mint2 @ ErrorCode::WrongWagerType)]
+    pub nft_mint2: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint1,
+        associated_token::authority = game
+    )]
+    pub vault_nft_account1: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint2,
+        associated_token::authority = game
+    )]
+    pub vault_nft_account2: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = nft_mint1,
+        token::authority = game.load()?.player1
+    )]
+    pub player1_nft_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = nft_mint2,
+        token::authority = game.load()?.player2
+    )]
+    pub player2_nft_account: Account<'info, TokenAccount>,
+    
+    // Vault rent refunds
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGameSpl<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.player1 == player.key() @ ErrorCode::NotPlayer1,
+        constraint = game.load()?.wager_mint == mint.key() @ ErrorCode::WrongWagerType,
+        close = player
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player2 never joined, so only player1's board exists
+    #[account(mut, close = player, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = game
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = player
+    )]
+    pub player_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Option<Account<'info, Lobby>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGameNft<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.player1 == player.key() @ ErrorCode::NotPlayer1,
+        close = player
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Player2 never joined, so only player1's board exists
+    #[account(mut, close = player, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(address = game.load()?.nft_mint1 @ ErrorCode::WrongWagerType)]
+    pub nft_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = game
+    )]
+    pub vault_nft_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = player
+    )]
+    pub player_nft_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Option<Account<'info, Lobby>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    #[account(
+        mut,
+        constraint = player.key() == game.load()?.player1 || player.key() == game.load()?.player2 @ ErrorCode::NotAPlayer
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Each board's rent goes back to its player
+    #[account(mut, close = player1, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, close = player2, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    pub player: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    // The archive accounts are only needed once the admin has created an archive tree
+    /// CHECK: Must be the configured archive tree; the compression program validates its contents
+    #[account(mut, address = config.archive_tree @ ErrorCode::WrongArchiveTree)]
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: SPL account compression program
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: SPL noop program, which logs the tree's changelog
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub noop_program: Option<UncheckedAccount<'info>>,
+}
+
+// Permissionless - anyone can clean up a finished game with nothing left to settle
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CrankCloseGame<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Each board's rent goes back to its player
+    #[account(mut, close = player1, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, close = player2, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    // The archive accounts are only needed once the admin has created an archive tree
+    /// CHECK: Must be the configured archive tree; the compression program validates its contents
+    #[account(mut, address = config.archive_tree @ ErrorCode::WrongArchiveTree)]
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: SPL account compression program
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: SPL noop program, which logs the tree's changelog
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub noop_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeRematch<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptRematch<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRematch<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeProfile<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = PlayerProfile::LEN,
+        seeds = [b"profile", player.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterSessionKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterName<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    #[account(
+        init,
+        payer = player,
+        space = NameRecord::LEN,
+        seeds = [b"name", name.as_bytes()],
+        bump
+    )]
+    pub name_record: Account<'info, NameRecord>,
+    
+    // The profile's current name, released on rename; required once a name is held
+    #[account(
+        mut,
+        close = player,
+        seeds = [b"name", profile.name.as_bytes()],
+        bump = old_name_record.bump
+    )]
+    pub old_name_record: Option<Account<'info, NameRecord>>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LinkAvatar<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    #[account(constraint = nft_mint.decimals == 0 && nft_mint.supply == 1 @ ErrorCode::NotAnNft)]
+    pub nft_mint: Account<'info, Mint>,
+    
+    #[account(
+        token::mint = nft_mint,
+        token::authority = player,
+        constraint = player_nft_account.amount == 1 @ ErrorCode::AvatarNotOwned
+    )]
+    pub player_nft_account: Account<'info, TokenAccount>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateClan<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = Clan::LEN,
+        seeds = [b"clan", name.as_bytes()],
+        bump
+    )]
+    pub clan: Account<'info, Clan>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinClan<'info> {
+    #[account(
+        mut,
+        seeds = [b"clan", clan.name.as_bytes()],
+        bump = clan.bump
+    )]
+    pub clan: Account<'info, Clan>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveClan<'info> {
+    #[account(
+        mut,
+        seeds = [b"clan", clan.name.as_bytes()],
+        bump = clan.bump
+    )]
+    pub clan: Account<'info, Clan>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump,
+        constraint = profile.clan == clan.key() @ ErrorCode::NotInClan
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(war_id: u64)]
+pub struct DeclareClanWar<'info> {
+    #[account(
+        init,
+        payer = founder,
+        space = ClanWar::LEN,
+        seeds = [b"clan_war", clan_a.key().as_ref(), &war_id.to_le_bytes()],
+        bump
+    )]
+    pub clan_war: Account<'info, ClanWar>,
+    
+    // Lamport escrow for both clans' stakes
+    #[account(
+        mut,
+        seeds = [b"clan_war_vault", clan_war.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(has_one = founder @ ErrorCode::NotClanFounder)]
+    pub clan_a: Account<'info, Clan>,
+    
+    pub clan_b: Account<'info, Clan>,
+    
+    #[account(mut)]
+    pub founder: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptClanWar<'info> {
+    #[account(
+        mut,
+        constraint = clan_war.founder_b == founder.key() @ ErrorCode::NotClanFounder
+    )]
+    pub clan_war: Account<'info, ClanWar>,
+    
+    #[account(
+        mut,
+        seeds = [b"clan_war_vault", clan_war.key().as_ref()],
+        bump = clan_war.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub founder: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelClanWar<'info> {
+    #[account(
+        mut,
+        close = founder,
+        constraint = clan_war.founder_a == founder.key() @ ErrorCode::NotClanFounder
+    )]
+    pub clan_war: Account<'info, ClanWar>,
+    
+    #[account(
+        mut,
+        seeds = [b"clan_war_vault", clan_war.key().as_ref()],
+        bump = clan_war.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub founder: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnlistForWar<'info> {
+    #[account(mut)]
+    pub clan_war: Account<'info, ClanWar>,
+    
+    // The enlisting founder's clan, which the member must belong to
+    #[account(has_one = founder @ ErrorCode::NotClanFounder)]
+    pub clan: Account<'info, Clan>,
+    
+    #[account(
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump,
+        constraint = profile.clan == clan.key() @ ErrorCode::NotInClan
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    /// CHECK: The member being enlisted; only their profile is read
+    pub player: UncheckedAccount<'info>,
+    
+    pub founder: Signer<'info>,
+}
+
+// Permissionless - anyone can finalize a war whose window has passed
+#[derive(Accounts)]
+pub struct FinalizeClanWar<'info> {
+    #[account(mut)]
+    pub clan_war: Account<'info, ClanWar>,
+    
+    #[account(
+        mut,
+        seeds = [b"clan_war_vault", clan_war.key().as_ref()],
+        bump = clan_war.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Refunded their stakes if the war is drawn
+    #[account(mut, address = clan_war.founder_a)]
+    pub founder_a: SystemAccount<'info>,
+    
+    #[account(mut, address = clan_war.founder_b)]
+    pub founder_b: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWarPrize<'info> {
+    #[account(mut)]
+    pub clan_war: Account<'info, ClanWar>,
+    
+    #[account(
+        mut,
+        seeds = [b"clan_war_vault", clan_war.key().as_ref()],
+        bump = clan_war.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless - the marker stops a game scoring twice for the same war
+#[derive(Accounts)]
+pub struct RecordWarGame<'info> {
+    #[account(mut)]
+    pub clan_war: Account<'info, ClanWar>,
+    
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = WarGame::LEN,
+        seeds = [b"war_game", clan_war.key().as_ref(), game.key().as_ref()],
+        bump
+    )]
+    pub war_game: Account<'info, WarGame>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    pub player: Signer<'info>,
+}
+
+// Permissionless - anyone can record a finished game
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordGameResult<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", game.load()?.player1.as_ref()],
+        bump = player1_profile.bump
+    )]
+    pub player1_profile: Account<'info, PlayerProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", game.load()?.player2.as_ref()],
+        bump = player2_profile.bump
+    )]
+    pub player2_profile: Account<'info, PlayerProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"leaderboard"],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+    
+    // Each player's clan, needed when they're in one and the game wasn't between clanmates
+    #[account(
+        mut,
+        constraint = clan1.key() == player1_profile.clan @ ErrorCode::NotInClan
+    )]
+    pub clan1: Option<Account<'info, Clan>>,
+    
+    #[account(
+        mut,
+        constraint = clan2.key() == player2_profile.clan @ ErrorCode::NotInClan
+    )]
+    pub clan2: Option<Account<'info, Clan>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct MintTrophy<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    // A fresh keypair per trophy, so each rematch round can mint its own
+    #[account(
+        init,
+        payer = winner,
+        mint::decimals = 0,
+        mint::authority = game,
+        mint::freeze_authority = game
+    )]
+    pub trophy_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = winner,
+        associated_token::mint = trophy_mint,
+        associated_token::authority = winner
+    )]
+    pub winner_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Metadata PDA of the trophy mint, created by the token metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), trophy_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    /// CHECK: Master edition PDA of the trophy mint, created by the token metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), trophy_mint.key().as_ref(), b"edition"],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub winner: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(tournament_id: u64)]
+pub struct CreateTournament<'info> {
+    #[account(
+        init,
+        payer = organizer,
+        space = Tournament::LEN,
+        seeds = [b"tournament", organizer.key().as_ref(), &tournament_id.to_le_bytes()],
+        bump
+    )]
+    pub tournament: Account<'info, Tournament>,
+    
+    // Lamport escrow for entry fees
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Register<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump = tournament.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless - the game account itself proves the result
+#[derive(Accounts)]
+pub struct ReportResult<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    
+    pub game: AccountLoader<'info, Game>,
+}
+
+// Permissionless - anyone can advance a completed round
+#[derive(Accounts)]
+pub struct AdvanceRound<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump = tournament.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Only needed for the final, to receive the prize pool
+    #[account(mut)]
+    pub champion: Option<SystemAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Config::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    
+    // Lamport treasury collecting the rake
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    // Only the program's upgrade authority can become admin
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::Battleship>,
+    
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ErrorCode::NotAdmin)]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RolloverSeason<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = Season::LEN,
+        seeds = [b"season".as_ref(), &config.season.to_le_bytes()],
+        bump
+    )]
+    pub season: Account<'info, Season>,
+    
+    #[account(
+        mut,
+        seeds = [b"leaderboard"],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxRatingDelta<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTimeoutStrikeLimit<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AllowWagerMint<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = AllowedMint::LEN,
+        seeds = [b"allowed_mint", mint.key().as_ref()],
+        bump
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisallowWagerMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"allowed_mint", allowed_mint.mint.as_ref()],
+        bump = allowed_mint.bump,
+        close = admin
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetWagerLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAbandonRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdminChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdminChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.pending_admin == new_admin.key() @ ErrorCode::NotPendingAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    // A wallet or a multisig vault (e.g. Squads) signing through its program
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateArchiveTree<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Zeroed tree account the client allocated, owned by the compression program
+    #[account(mut, owner = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    /// CHECK: SPL account compression program
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL noop program, which logs the tree's changelog
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub noop_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = config.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", player.key().as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = config.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasurySpl<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        seeds = [b"treasury"],
+        bump = config.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = treasury
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = admin
+    )]
+    pub admin_token_account: Account<'info, TokenAccount>,
+    
+    pub admin: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLeaderboard<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Leaderboard::LEN,
+        seeds = [b"leaderboard"],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ArbitrateGame<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump,
+        constraint = game.load()?.arbiter == arbiter.key() @ ErrorCode::NotArbiter
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Both players, to hand back their bonds
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    pub arbiter: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCheaterRegistry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = CheaterRegistry::LEN,
+        seeds = [b"cheater_registry"],
+        bump
+    )]
+    pub registry: Account<'info, CheaterRegistry>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless - the offense is read off the game, once
+#[derive(Accounts)]
+pub struct RecordOffense<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"cheater_registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, CheaterRegistry>,
+}
+
+// Permissionless - anyone can crank a player's placement
+#[derive(Accounts)]
+pub struct RefreshLeaderboardEntry<'info> {
+    #[account(
+        mut,
+        seeds = [b"leaderboard"],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    
+    #[account(
+        seeds = [b"profile", profile.wallet.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Account<'info, PlayerProfile>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLobby<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Lobby::LEN,
+        seeds = [b"lobby"],
+        bump
+    )]
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ListGame<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.player1 == player.key() @ ErrorCode::NotPlayer1
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"lobby"],
+        bump = lobby.bump
+    )]
+    pub lobby: Account<'info, Lobby>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnableMoveHistory<'info> {
+    #[account(
+        mut,
+        constraint = game.load()?.is_player(&player.key()) @ ErrorCode::NotAPlayer,
+        realloc = Game::LEN + MOVE_HISTORY_LEN,
+        realloc::payer = player,
+        realloc::zero = true
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DesignateObservers<'info> {
+    #[account(constraint = game.load()?.player1 == player.key() @ ErrorCode::NotPlayer1)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        init,
+        payer = player,
+        space = GameObservers::LEN,
+        seeds = [b"observers", game.key().as_ref()],
+        bump
+    )]
+    pub observers: Account<'info, GameObservers>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SealBoardForObservers<'info> {
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"observers", game.key().as_ref()],
+        bump = observers.bump
+    )]
+    pub observers: Account<'info, GameObservers>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBettingPool<'info> {
+    #[account(constraint = !game.load()?.is_over() @ ErrorCode::GameOver)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Holds every spectator stake for the game alongside its own rent
+    #[account(
+        init,
+        payer = opener,
+        space = BettingPool::LEN,
+        seeds = [b"bet_pool", game.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, BettingPool>,
+    
+    #[account(mut)]
+    pub opener: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RegisterSpectator<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        init,
+        payer = spectator,
+        space = SpectatorMarker::LEN,
+        seeds = [b"spectator", game.key().as_ref(), spectator.key().as_ref()],
+        bump
+    )]
+    pub marker: Account<'info, SpectatorMarker>,
+    
+    #[account(mut)]
+    pub spectator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UnregisterSpectator<'info> {
+    #[account(
+        mut,
+        close = spectator,
+        seeds = [b"spectator", marker.game.as_ref(), spectator.key().as_ref()],
+        bump = marker.bump
+    )]
+    pub marker: Account<'info, SpectatorMarker>,
+    
+    /// CHECK: The marker's game; its count is decremented if it's still open
+    #[account(mut, address = marker.game)]
+    pub game: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub spectator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceSpectatorBet<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"bet_pool", game.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, BettingPool>,
+    
+    // One bet per spectator per game
+    #[account(
+        init,
+        payer = bettor,
+        space = SpectatorBet::LEN,
+        seeds = [b"bet", game.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub bet: Account<'info, SpectatorBet>,
+    
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless - bets and their bettors are passed as remaining accounts
+#[derive(Accounts)]
+pub struct SettleSpectatorBets<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        mut,
+        seeds = [b"bet_pool", game.key().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, BettingPool>,
+    
+    #[account(mut, address = pool.opener)]
+    pub opener: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct InitializeFfaGame<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = FfaGame::LEN,
+        seeds = [b"ffa_game", player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    // Lamport escrow for every player's stake
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Optional: the player's profile, so whoever referred them shares in the rake
+    #[account(seeds = [b"profile", player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinFfaGame<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Optional: the player's profile, so whoever referred them shares in the rake
+    #[account(seeds = [b"profile", player.key().as_ref()], bump = player_profile.bump)]
+    pub player_profile: Option<Account<'info, PlayerProfile>>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FireFfaShot<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealFfaShotResult<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    pub player: Signer<'info>,
+}
+
+// Permissionless - anyone can knock out a free-for-all player who stopped acting
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EliminateIdleFfaPlayer<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, FfaGame>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealFfaBoard<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFfaWinnings<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = config.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    // Only in team games: the signer's teammate, who gets half the pot
+    #[account(mut)]
+    pub teammate: Option<SystemAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFfaRefund<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseFfaGame<'info> {
+    #[account(
+        mut,
+        close = creator,
+        constraint = game.load()?.players[0] == creator.key() @ ErrorCode::NotAPlayer
+    )]
+    pub game: AccountLoader<'info, FfaGame>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    // Takes whatever a voided game leaves in the vault
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump = config.treasury_bump
+    )]
+    pub treasury: SystemAccount<'info>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnterQueue<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = QueueTicket::LEN,
+        seeds = [b"queue_ticket", player.key().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, QueueTicket>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveQueue<'info> {
+    #[account(
+        mut,
+        close = player,
+        seeds = [b"queue_ticket", player.key().as_ref()],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, QueueTicket>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+// Permissionless - the keeper fronts the game's rent and is paid back from both tickets
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct MatchPlayers<'info> {
+    #[account(
+        mut,
+        close = player1,
+        seeds = [b"queue_ticket", player1.key().as_ref()],
+        bump = ticket1.bump
+    )]
+    pub ticket1: Account<'info, QueueTicket>,
+    
+    #[account(
+        mut,
+        close = player2,
+        seeds = [b"queue_ticket", player2.key().as_ref()],
+        bump = ticket2.bump
+    )]
+    pub ticket2: Account<'info, QueueTicket>,
+    
+    #[account(mut)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, constraint = player2.key() != player1.key() @ ErrorCode::CannotPlayAgainstYourself)]
+    pub player2: SystemAccount<'info>,
+    
+    // Current ratings, checked against the configured delta
+    #[account(seeds = [b"profile", player1.key().as_ref()], bump = profile1.bump)]
+    pub profile1: Account<'info, PlayerProfile>,
+    
+    #[account(seeds = [b"profile", player2.key().as_ref()], bump = profile2.bump)]
+    pub profile2: Account<'info, PlayerProfile>,
+    
+    #[account(
+        init,
+        payer = keeper,
+        space = Game::LEN,
+        seeds = [b"game", player1.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both boards start uncommitted; commit_matched_board fills them in
+    #[account(
+        init,
+        payer = keeper,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[1]],
+        bump
+    )]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(
+        init,
+        payer = keeper,
+        space = PlayerBoard::LEN,
+        seeds = [b"player_board", game.key().as_ref(), &[2]],
+        bump
+    )]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    // Lamport escrow for the wager pot and both honesty bonds
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    // Global switches; new games and joins stop while paused
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitMatchedBoard<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(constraint = game.load()?.is_player(&player.key()) @ ErrorCode::NotAPlayer)]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMatch<'info> {
+    #[account(
+        mut,
+        close = player1,
+        constraint = game.load()?.is_player(&player.key()) @ ErrorCode::NotAPlayer
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Each board's rent goes back to its player
+    #[account(mut, close = player1, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, close = player2, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitBoardCommitments<'info> {
+    #[account(constraint = game.load()?.is_player(&player.key()) @ ErrorCode::NotAPlayer)]
+    pub game: AccountLoader<'info, Game>,
+    
+    #[account(
+        init,
+        payer = player,
+        space = BoardCommitments::LEN,
+        seeds = [b"board_commitments", game.key().as_ref(), player.key().as_ref()],
+        bump
+    )]
+    pub board_commitments: AccountLoader<'info, BoardCommitments>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBoardCommitments<'info> {
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"board_commitments", game.key().as_ref(), player.key().as_ref()],
+        bump = board_commitments.load()?.bump
+    )]
+    pub board_commitments: AccountLoader<'info, BoardCommitments>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealShotResultStored<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    // The defender's sealed cell hashes
+    #[account(
+        seeds = [b"board_commitments", game.key().as_ref(), board_commitments.load()?.player.as_ref()],
+        bump = board_commitments.load()?.bump
+    )]
+    pub board_commitments: AccountLoader<'info, BoardCommitments>,
+    
+    // Only when a session key signs: the profile that authorized it
+    #[account(
+        seeds = [b"profile", profile.wallet.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+    
+    // The player's wallet, or their session key
+    #[account(
+        constraint = game.load()?.is_player(&profile.as_ref().map_or(player.key(), |profile| profile.wallet))
+            @ ErrorCode::NotAPlayer
+    )]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(circuit: u8)]
+pub struct InitializeZkVerifier<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ZkVerifier::LEN,
+        seeds = [b"zk_verifier".as_ref(), &[circuit]],
+        bump
+    )]
+    pub zk_verifier: Account<'info, ZkVerifier>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitFleetProof<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump,
+        constraint = game.load()?.is_player(&player.key()) @ ErrorCode::NotAPlayer
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"zk_verifier".as_ref(), &[ZK_CIRCUIT_FLEET]], bump = zk_verifier.bump)]
+    pub zk_verifier: Account<'info, ZkVerifier>,
+    
+    pub player: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealShotResultZk<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game.load()?.player1.as_ref(), &game.load()?.game_id.to_le_bytes()],
+        bump = game.load()?.bump
+    )]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Both players' hit boards and commitments
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(seeds = [b"zk_verifier".as_ref(), &[ZK_CIRCUIT_SHOT]], bump = zk_verifier.bump)]
+    pub zk_verifier: Account<'info, ZkVerifier>,
+    
+    // Only when a session key signs: the profile that authorized it
+    #[account(
+        seeds = [b"profile", profile.wallet.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, PlayerProfile>>,
+    
+    // The player's wallet, or their session key
+    #[account(
+        constraint = game.load()?.is_player(&profile.as_ref().map_or(player.key(), |profile| profile.wallet))
+            @ ErrorCode::NotAPlayer
+    )]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAbort<'info> {
+    #[account(mut)]
+    pub game: AccountLoader<'info, Game>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmAbort<'info> {
+    // Player1 fronted the game's rent, so it goes back to them
+    #[account(mut, close = player1)]
+    pub game: AccountLoader<'info, Game>,
+    
+    // Each board's rent goes back to its player
+    #[account(mut, close = player1, seeds = [b"player_board", game.key().as_ref(), &[1]], bump = board1.bump)]
+    pub board1: Account<'info, PlayerBoard>,
+    
+    #[account(mut, close = player2, seeds = [b"player_board", game.key().as_ref(), &[2]], bump = board2.bump)]
+    pub board2: Account<'info, PlayerBoard>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", game.key().as_ref()],
+        bump = game.load()?.vault_bump
+    )]
+    pub vault: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player1)]
+    pub player1: SystemAccount<'info>,
+    
+    #[account(mut, address = game.load()?.player2)]
+    pub player2: SystemAccount<'info>,
+    
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(game_id: u64)]
+pub struct StartHouseGame<'info> {
+    #[account(
+        init,
+        payer = player,
+        space = HouseGame::LEN,
+        seeds = [b"house_game", player.key().as_ref(), &game_id.to_le_bytes()],
+        bump
+    )]
+    pub house_game: Account<'info, HouseGame>,
+    
+    /// CHECK: Switchboard On-Demand randomness account, parsed in the instruction
+    #[account(owner = SWITCHBOARD_ON_DEMAND_PROGRAM_ID)]
+    pub randomness_account: UncheckedAccount<'info>,
+    
+    // The admin plays the house, co-signing to commit its secret
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// The board's value can only come from the randomness bound at the start
+#[derive(Accounts)]
+pub struct DealHouseBoard<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_game", house_game.player.as_ref(), &house_game.game_id.to_le_bytes()],
+        bump = house_game.bump,
+        has_one = house @ ErrorCode::NotAdmin
+    )]
+    pub house_game: Account<'info, HouseGame>,
+    
+    pub house: Signer<'info>,
+    
+    /// CHECK: Must be the account bound by start_house_game, parsed in the instruction
+    #[account(
+        address = house_game.randomness_account @ ErrorCode::InvalidRandomnessAccount,
+        owner = SWITCHBOARD_ON_DEMAND_PROGRAM_ID
+    )]
+    pub randomness_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FireAtHouse<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_game", house_game.player.as_ref(), &house_game.game_id.to_le_bytes()],
+        bump = house_game.bump,
+        has_one = player @ ErrorCode::NotAPlayer
+    )]
+    pub house_game: Account<'info, HouseGame>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AnswerHouseShot<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_game", house_game.player.as_ref(), &house_game.game_id.to_le_bytes()],
+        bump = house_game.bump,
+        has_one = house @ ErrorCode::NotAdmin
+    )]
+    pub house_game: Account<'info, HouseGame>,
+    
+    pub house: Signer<'info>,
+}
+
+// Permissionless - the secret and board check themselves against the commitments
+#[derive(Accounts)]
+pub struct RevealHouseBoard<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_game", house_game.player.as_ref(), &house_game.game_id.to_le_bytes()],
+        bump = house_game.bump
+    )]
+    pub house_game: Account<'info, HouseGame>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimHouseForfeit<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_game", house_game.player.as_ref(), &house_game.game_id.to_le_bytes()],
+        bump = house_game.bump,
+        has_one = player @ ErrorCode::NotAPlayer
+    )]
+    pub house_game: Account<'info, HouseGame>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseHouseGame<'info> {
+    #[account(
+        mut,
+        seeds = [b"house_game", house_game.player.as_ref(), &house_game.game_id.to_le_bytes()],
+        bump = house_game.bump,
+        has_one = player @ ErrorCode::NotAPlayer,
+        close = player
+    )]
+    pub house_game: Account<'info, HouseGame>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(puzzle_id: u64)]
+pub struct PublishPuzzle<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = Puzzle::LEN,
+        seeds = [b"puzzle", creator.key().as_ref(), &puzzle_id.to_le_bytes()],
+        bump
+    )]
+    pub puzzle: Account<'info, Puzzle>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartPuzzleAttempt<'info> {
+    #[account(
+        mut,
+        seeds = [b"puzzle", puzzle.creator.as_ref(), &puzzle.puzzle_id.to_le_bytes()],
+        bump = puzzle.bump,
+        constraint = puzzle.creator != player.key() @ ErrorCode::CannotPlayAgainstYourself
+    )]
+    pub puzzle: Account<'info, Puzzle>,
+    
+    // One attempt per player per puzzle, so a solve can't be retried into a better score
+    #[account(
+        init,
+        payer = player,
+        space = PuzzleAttempt::LEN,
+        seeds = [b"puzzle_attempt", puzzle.key().as_ref(), player.key().as_ref()],
+        bump
+    )]
+    pub attempt: Account<'info, PuzzleAttempt>,
+    
+    // Solves are scored here, so the player needs a profile before starting
+    #[account(
+        seeds = [b"profile", player.key().as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FireAtPuzzle<'info> {
+    #[account(
+        mut,
+        seeds = [b"puzzle_attempt", attempt.puzzle.as_ref(), attempt.player.as_ref()],
+        bump = attempt.bump,
+        has_one = player @ ErrorCode::NotAPlayer
+    )]
+    pub attempt: Account<'info, PuzzleAttempt>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPuzzleForfeit<'info> {
+    #[account(
+        mut,
+        seeds = [b"puzzle", puzzle.creator.as_ref(), &puzzle.puzzle_id.to_le_bytes()],
+        bump = puzzle.bump
+    )]
+    pub puzzle: Account<'info, Puzzle>,
+    
+    #[account(
+        mut,
+        seeds = [b"puzzle_attempt", puzzle.key().as_ref(), player.key().as_ref()],
+        bump = attempt.bump,
+        has_one = player @ ErrorCode::NotAPlayer
+    )]
+    pub attempt: Account<'info, PuzzleAttempt>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AnswerPuzzleShot<'info> {
+    #[account(
+        mut,
+        seeds = [b"puzzle", puzzle.creator.as_ref(), &puzzle.puzzle_id.to_le_bytes()],
+        bump = puzzle.bump,
+        has_one = creator @ ErrorCode::NotPuzzleCreator
+    )]
+    pub puzzle: Account<'info, Puzzle>,
+    
+    #[account(
+        mut,
+        seeds = [b"puzzle_attempt", puzzle.key().as_ref(), attempt.player.as_ref()],
+        bump = attempt.bump
+    )]
+    pub attempt: Account<'info, PuzzleAttempt>,
+    
+    #[account(
+        mut,
+        seeds = [b"profile", attempt.player.as_ref()],
+        bump = player_profile.bump
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealPuzzleBoard<'info> {
+    #[account(
+        mut,
+        seeds = [b"puzzle", puzzle.creator.as_ref(), &puzzle.puzzle_id.to_le_bytes()],
+        bump = puzzle.bump,
+        has_one = creator @ ErrorCode::NotPuzzleCreator
+    )]
+    pub puzzle: Account<'info, Puzzle>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(day: u32)]
+pub struct OpenChallenge<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Challenge::LEN,
+        seeds = [b"challenge".as_ref(), &day.to_le_bytes()],
+        bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinChallenge<'info> {
+    #[account(
+        mut,
+        seeds = [b"challenge".as_ref(), &challenge.day.to_le_bytes()],
+        bump = challenge.bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(
+        init,
+        payer = player,
+        space = ChallengeEntry::LEN,
+        seeds = [b"challenge_entry", challenge.key().as_ref(), player.key().as_ref()],
         bump
     )]
-    pub game: Account<'info, Game>,
+    pub entry: Account<'info, ChallengeEntry>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FireAtChallenge<'info> {
+    #[account(
+        seeds = [b"challenge".as_ref(), &challenge.day.to_le_bytes()],
+        bump = challenge.bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(
+        mut,
+        seeds = [b"challenge_entry", challenge.key().as_ref(), player.key().as_ref()],
+        bump = entry.bump
+    )]
+    pub entry: Account<'info, ChallengeEntry>,
+    
+    pub player: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AnswerChallengeShot<'info> {
+    #[account(
+        mut,
+        seeds = [b"challenge".as_ref(), &challenge.day.to_le_bytes()],
+        bump = challenge.bump,
+        has_one = host @ ErrorCode::NotChallengeHost
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(
+        mut,
+        seeds = [b"challenge_entry", challenge.key().as_ref(), entry.player.as_ref()],
+        bump = entry.bump
+    )]
+    pub entry: Account<'info, ChallengeEntry>,
+    
+    pub host: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealChallengeBoard<'info> {
+    #[account(
+        mut,
+        seeds = [b"challenge".as_ref(), &challenge.day.to_le_bytes()],
+        bump = challenge.bump,
+        has_one = host @ ErrorCode::NotChallengeHost
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    pub host: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseChallengeEntry<'info> {
+    #[account(
+        seeds = [b"challenge".as_ref(), &challenge.day.to_le_bytes()],
+        bump = challenge.bump
+    )]
+    pub challenge: Account<'info, Challenge>,
+    
+    #[account(
+        mut,
+        seeds = [b"challenge_entry", challenge.key().as_ref(), player.key().as_ref()],
+        bump = entry.bump,
+        close = player
+    )]
+    pub entry: Account<'info, ChallengeEntry>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+// Zero-copy so instructions touch the account in place instead of Borsh-decoding all of it.
+// Fields are grouped by alignment (u64s, u32s, then byte-sized) so the layout has no padding;
+// flags are u8 (0 = false, 1 = true) since bool isn't Pod.
+#[account(zero_copy)]
+pub struct Game {
+    pub wager: u64,                    // 8 bytes - Lamports each player staked (0 = no wager)
+    pub last_action_slot: u64,         // 8 bytes - Slot of the last join/shot/reveal (or game end)
+    pub game_id: u64,                  // 8 bytes - Player1-chosen id, part of the PDA seeds
+    pub bond: u64,                     // 8 bytes - Honesty bond each player posted, in lamports
+    pub clock1: u64,                   // 8 bytes - Player1's remaining clock, in slots
+    pub clock2: u64,                   // 8 bytes - Player2's remaining clock, in slots
+    pub bond_docked1: u64,             // 8 bytes - Keeper bounties taken from player1's bond
+    pub bond_docked2: u64,             // 8 bytes - Keeper bounties taken from player2's bond
+    pub relay_nonce1: u64,             // 8 bytes - Next nonce player1's relayed moves must use
+    pub relay_nonce2: u64,             // 8 bytes - Next nonce player2's relayed moves must use
+    pub rematch_top_up: u64,           // 8 bytes - Lamports each player adds to the wager for the proposed rematch
+    pub last_move: MoveRecord,         // 16 bytes - Last resolved shot, kept whether or not the game has a move log
+    pub spectator_bets: u32,           // 4 bytes - Spectator bets not yet settled
+    pub season: u32,                   // 4 bytes - Season the game was created in
+    pub spectators: u32,               // 4 bytes - Registered spectator markers, kept across rematches
+    pub rules: GameRules,              // 16 bytes - Variant rules chosen at creation
+    pub move_count: u16,               // 2 bytes - Shots resolved so far (and entries in use in the move log)
+    pub last_emote1: u16,              // 2 bytes - move_count + 1 when player1 last emoted (0 = never)
+    pub last_emote2: u16,              // 2 bytes - move_count + 1 when player2 last emoted (0 = never)
+    pub abandon_refund_bps: u16,       // 2 bytes - Share of the pot a player who times out mid-game gets back, stamped at creation
+    pub player1: Pubkey,               // 32 bytes
+    pub player2: Pubkey,               // 32 bytes
+    pub pending_shot_by: Pubkey,       // 32 bytes - Who fired the pending shot
+    pub wager_mint: Pubkey,            // 32 bytes - SPL mint of the wager (default = lamports)
+    pub invited_opponent: Pubkey,      // 32 bytes - Only player allowed to join (default = anyone)
+    pub turn_commitment: [u8; 32],     // 32 bytes - sha256 of player1's coin-flip nonce (zeros = no flip)
+    pub turn_nonce: [u8; 32],          // 32 bytes - Player2's coin-flip nonce, given at join
+    pub randomness_account: Pubkey,    // 32 bytes - Outstanding Switchboard randomness request
+    pub randomness: [u8; 32],          // 32 bytes - Last VRF value consumed
+    pub rematch_commitment: [u8; 32],  // 32 bytes - Proposer's board commitment for the rematch
+    pub nft_mint1: Pubkey,             // 32 bytes - NFT player1 staked (default = not an NFT game)
+    pub nft_mint2: Pubkey,             // 32 bytes - NFT player2 staked
+    pub arbiter: Pubkey,               // 32 bytes - Agreed by both players to rule on a stuck game (default = none)
+    pub referrer1: Pubkey,             // 32 bytes - Who referred player1, stamped at creation (default = nobody)
+    pub referrer2: Pubkey,             // 32 bytes - Who referred player2, stamped at join (default = nobody)
+    pub ship_hits1: [u8; MAX_SHIPS],   // 8 bytes - Hits taken by each of player1's ships
+    pub ship_hits2: [u8; MAX_SHIPS],   // 8 bytes - Hits taken by each of player2's ships
+    pub fleet: [u8; MAX_SHIPS],        // 8 bytes - Ship lengths; ship n is fleet[n - 1]
+    pub fleet2: [u8; MAX_SHIPS],       // 8 bytes - Player2's ship lengths in a handicap game
+    pub pending_salvo: [[u8; 2]; MAX_SHIPS], // 16 bytes - Unresolved salvo shots as (x, y)
+    pub pending_shot: [u8; 2],         // 2 bytes - Current pending shot coordinates
+    pub radar_scan1: [u8; 3],          // 3 bytes - Player1's radar scan: center (x, y), then the ship cells player2 reported
+    pub radar_scan2: [u8; 3],          // 3 bytes - Player2's radar scan: center (x, y), then the ship cells player1 reported
+    pub fleet_len: u8,                 // 1 byte - Ships in use at the front of fleet
+    pub fleet2_len: u8,                // 1 byte - Ships in use at the front of fleet2 (0 = same fleet as player1)
+    pub pending_salvo_len: u8,         // 1 byte - Shots in use at the front of pending_salvo
+    pub has_pending_shot: u8,          // 1 byte - pending_shot holds an unresolved shot
+    pub turn: u8,                      // 1 byte - 1 for player1, 2 for player2
+    pub hits_count1: u8,               // 1 byte - Number of hits player1 has taken
+    pub hits_count2: u8,               // 1 byte - Number of hits player2 has taken
+    pub state: u8,                     // 1 byte - GameState discriminant
+    pub winner: u8,                    // 1 byte - 0=none, 1=player1, 2=player2
+    pub player1_revealed: u8,          // 1 byte - Player1 has revealed their board
+    pub player2_revealed: u8,          // 1 byte - Player2 has revealed their board
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub vault_bump: u8,                // 1 byte - Wager vault PDA bump
+    pub winnings_claimed: u8,          // 1 byte - Pot has been paid out
+    pub end_reason: u8,                // 1 byte - 0=in progress, 1=fleet sunk, 2=timeout, 3=resignation, 4=reveal forfeit, 5=draw, 6=out of ammo, 7=board exhausted, 8=arbiter ruling, 9=abandoned before the first shot
+    pub is_public: u8,                 // 1 byte - Listed in the lobby registry
+    pub cheater: u8,                   // 1 byte - 0=none, 1=player1, 2=player2 (board contradicted shot results)
+    pub bond1_settled: u8,             // 1 byte - Player1's bond refunded or forfeited
+    pub bond2_settled: u8,             // 1 byte - Player2's bond refunded or forfeited
+    pub turn_pending: u8,              // 1 byte - Waiting for the coin flip (nonce reveal or VRF)
+    pub first_turn: u8,                // 1 byte - Player who moved first (1 or 2)
+    pub rematch_proposer: u8,          // 1 byte - 0=none, 1=player1, 2=player2
+    pub rematch_roll_pot: u8,          // 1 byte - Rematch plays for the unclaimed pot
+    pub round_wins1: u8,               // 1 byte - Earlier series rounds won by player1
+    pub round_wins2: u8,               // 1 byte - Earlier series rounds won by player2
+    pub draw_offered_by: u8,           // 1 byte - 0=none, 1=player1, 2=player2
+    pub stats_recorded: u8,            // 1 byte - Result already counted in both profiles
+    pub trophy_minted: u8,             // 1 byte - Winner's trophy NFT already minted
+    pub radar_state1: u8,              // 1 byte - Player1's radar: 0=unused, 1=awaiting the answer, 2=answered
+    pub radar_state2: u8,              // 1 byte - Player2's radar: 0=unused, 1=awaiting the answer, 2=answered
+    pub bomb_used1: u8,                // 1 byte - Player1 has dropped their areabomb
+    pub bomb_used2: u8,                // 1 byte - Player2 has dropped their areabomb
+    pub depth_charge_used1: u8,        // 1 byte - Player1 has dropped their depth charge
+    pub depth_charge_used2: u8,        // 1 byte - Player2 has dropped their depth charge
+    pub mine_penalty: u8,              // 1 byte - 0=none, 1=a mine was struck this turn, 2=the pending shot is its penalty
+    pub shots_fired1: u8,              // 1 byte - Cells player1 has fired at (mine penalties excluded)
+    pub shots_fired2: u8,              // 1 byte - Cells player2 has fired at (mine penalties excluded)
+    pub ranked: u8,                    // 1 byte - Result counts toward ratings, stats and the leaderboard
+    pub carrier_finale: u8,            // 1 byte - The winning shot sank the fleet's largest ship
+    pub offense_recorded: u8,          // 1 byte - Cheating or reveal forfeit already entered in the cheater registry
+    pub abort_proposed_by: u8,         // 1 byte - 0=none, 1=player1, 2=player2
+    pub strikes1: u8,                  // 1 byte - Turn deadlines player1 let pass without being timed out
+    pub strikes2: u8,                  // 1 byte - Turn deadlines player2 let pass without being timed out
+    pub strike_limit: u8,              // 1 byte - Strikes that forfeit the game (0 = no limit)
+    pub fleet_proven1: u8,             // 1 byte - Player1's fleet proof verified (VARIANT_ZK_FLEET)
+    pub fleet_proven2: u8,             // 1 byte - Player2's fleet proof verified (VARIANT_ZK_FLEET)
+    pub move_history: u8,              // 1 byte - Account grown to hold the move log after the game state
+    pub _padding: [u8; 5],             // 5 bytes - Rounds the account up to 8-byte alignment
+}
+
+impl Game {
+    pub const LEN: usize = 8 + std::mem::size_of::<Game>(); // 728 bytes + discriminator
+    
+    pub fn state(&self) -> GameState {
+        GameState::from_u8(self.state)
+    }
+    
+    pub fn set_state(&mut self, state: GameState) {
+        self.state = state as u8;
+    }
+    
+    pub fn is_player(&self, key: &Pubkey) -> bool {
+        *key == self.player1 || *key == self.player2
+    }
+    
+    // Fleet sunk, timed out, resigned or drawn - whether or not the boards are revealed yet
+    pub fn is_over(&self) -> bool {
+        matches!(self.state(), GameState::AwaitingReveal | GameState::Finished | GameState::Aborted)
+    }
+    
+    pub fn fleet(&self) -> &[u8] {
+        &self.fleet[..self.fleet_len as usize]
+    }
+    
+    // Player2's fleet differs from player1's only in a handicap game
+    pub fn fleet_of(&self, player1: bool) -> &[u8] {
+        if player1 || self.fleet2_len == 0 {
+            self.fleet()
+        } else {
+            &self.fleet2[..self.fleet2_len as usize]
+        }
+    }
+    
+    pub fn set_fleet(&mut self, fleet: &[u8]) {
+        self.fleet = [0; MAX_SHIPS];
+        self.fleet[..fleet.len()].copy_from_slice(fleet);
+        self.fleet_len = fleet.len() as u8;
+    }
+    
+    pub fn pending_shot(&self) -> Option<(u8, u8)> {
+        (self.has_pending_shot != 0).then_some((self.pending_shot[0], self.pending_shot[1]))
+    }
+    
+    pub fn set_pending_shot(&mut self, shot: Option<(u8, u8)>) {
+        let (x, y) = shot.unwrap_or_default();
+        self.pending_shot = [x, y];
+        self.has_pending_shot = shot.is_some() as u8;
+    }
+    
+    pub fn pending_salvo(&self) -> &[[u8; 2]] {
+        &self.pending_salvo[..self.pending_salvo_len as usize]
+    }
+    
+    pub fn set_pending_salvo(&mut self, shots: &[(u8, u8)]) {
+        self.pending_salvo = [[0; 2]; MAX_SHIPS];
+        for (slot, &(x, y)) in self.pending_salvo.iter_mut().zip(shots) {
+            *slot = [x, y];
+        }
+        self.pending_salvo_len = shots.len() as u8;
+    }
+    
+    // Drops one resolved salvo shot, keeping the rest in firing order
+    pub fn remove_pending_salvo(&mut self, position: usize) {
+        let len = self.pending_salvo_len as usize;
+        self.pending_salvo.copy_within(position + 1..len, position);
+        self.pending_salvo[len - 1] = [0; 2];
+        self.pending_salvo_len -= 1;
+    }
+}
+
+// Lifecycle of a game. Only the transitions below are allowed:
+// WaitingForOpponent -> InProgress (join) or Cancelled (player1 cancels)
+// InProgress -> AwaitingReveal (fleet sunk, timeout, resignation or draw)
+// AwaitingReveal -> Finished (enough boards revealed to pay out) or Aborted (nobody revealed)
+// AwaitingReveal/Finished -> InProgress (rematch or next series round)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum GameState {
+    WaitingForOpponent,
+    InProgress,
+    AwaitingReveal,
+    Finished,
+    Cancelled,
+    Aborted,
+}
+
+impl GameState {
+    // Game.state is only ever written through set_state, so every stored byte is a variant
+    fn from_u8(state: u8) -> Self {
+        match state {
+            0 => GameState::WaitingForOpponent,
+            1 => GameState::InProgress,
+            2 => GameState::AwaitingReveal,
+            3 => GameState::Finished,
+            4 => GameState::Cancelled,
+            _ => GameState::Aborted,
+        }
+    }
+}
+
+// One resolved shot; replaying a game's moves in order rebuilds both hit boards
+#[zero_copy]
+#[derive(Default)]
+pub struct MoveRecord {
+    pub slot: u64,                     // 8 bytes - Slot the result was revealed in
+    pub x: u8,                         // 1 byte
+    pub y: u8,                         // 1 byte
+    pub shooter: u8,                   // 1 byte - 1=player1, 2=player2
+    pub result: u8,                    // 1 byte - 1=miss, 2=hit, 3=mine
+    pub _padding: [u8; 4],             // 4 bytes - Keeps records 8-byte aligned
+}
+
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, PartialEq, Eq)]
+pub struct GameRules {
+    pub clock_slots: u32,              // 4 bytes - Chess-clock budget per player (0 = untimed)
+    pub variants: u32,                 // 4 bytes - VARIANT_* bits for optional mechanics and the commitment hash
+    pub min_reputation: u32,           // 4 bytes - Reputation the joiner's profile must have (0 = anyone)
+    pub salvo: u8,                     // 1 byte - One shot per ship afloat each turn (0/1)
+    pub board_size: u8,                // 1 byte - Board width/height: 8, 10 or 12
+    pub vrf_first_turn: u8,            // 1 byte - Decide the first turn with VRF randomness (0/1)
+    pub best_of: u8,                   // 1 byte - Series length: 1, 3 or 5 rounds
+}
+
+impl GameRules {
+    // 1 for a flat board, up to MAX_LAYERS with submarine layers beneath the surface
+    pub fn layers(&self) -> usize {
+        1 + ((self.variants & VARIANT_LAYERS_MASK) >> VARIANT_LAYERS_SHIFT) as usize
+    }
+    
+    pub fn hex(&self) -> bool {
+        self.variants & VARIANT_HEX != 0
+    }
+    
+    pub fn correspondence_days(&self) -> u64 {
+        ((self.variants & VARIANT_CORRESPONDENCE_MASK) >> VARIANT_CORRESPONDENCE_SHIFT) as u64
+    }
+    
+    // Slots each move may take before it counts as late
+    pub fn turn_timeout_slots(&self) -> u64 {
+        match self.correspondence_days() {
+            0 => TURN_TIMEOUT_SLOTS,
+            days => days * SLOTS_PER_DAY,
+        }
+    }
     
-    #[account(mut)]
-    pub player: Signer<'info>,
+    // Rows of the whole board, every layer's stacked one after another
+    pub fn rows(&self) -> u8 {
+        self.board_size * self.layers() as u8
+    }
     
-    pub system_program: Program<'info, System>,
+    pub fn cells(&self) -> usize {
+        self.board_size as usize * self.rows() as usize
+    }
+    
+    pub fn commit_hash(&self) -> CommitHash {
+        if self.variants & VARIANT_KECCAK_COMMITMENTS != 0 {
+            CommitHash::Keccak256
+        } else if self.variants & VARIANT_BLAKE3_COMMITMENTS != 0 {
+            CommitHash::Blake3
+        } else {
+            CommitHash::Sha256
+        }
+    }
 }
 
-#[derive(Accounts)]
-pub struct JoinGame<'info> {
-    #[account(mut)]
-    pub game: Account<'info, Game>,
-    
-    pub player: Signer<'info>,
+// Hash primitive a game's board commitments (leaves, Merkle nodes and binding) are built with,
+// so clients can use whichever one they already ship
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CommitHash {
+    Sha256,
+    Keccak256,
+    Blake3,
 }
 
-#[derive(Accounts)]
-pub struct FireShot<'info> {
-    #[account(mut)]
-    pub game: Account<'info, Game>,
-    
-    pub player: Signer<'info>,
+impl CommitHash {
+    fn hashv(self, parts: &[&[u8]]) -> [u8; 32] {
+        match self {
+            CommitHash::Sha256 => hashv(parts).to_bytes(),
+            CommitHash::Keccak256 => keccak::hashv(parts).to_bytes(),
+            CommitHash::Blake3 => blake3::hashv(parts).to_bytes(),
+        }
+    }
 }
 
-#[derive(Accounts)]
-pub struct RevealShotResult<'info> {
-    #[account(mut)]
-    pub game: Account<'info, Game>,
-    
-    pub player: Signer<'info>,
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            clock_slots: 0,
+            variants: 0,
+            min_reputation: 0,
+            salvo: 0,
+            board_size: 10,
+            vrf_first_turn: 0,
+            best_of: 1,
+        }
+    }
 }
 
-#[derive(Accounts)]
-pub struct RevealBoard<'info> {
-    #[account(mut)]
-    pub game: Account<'info, Game>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ShotReveal {
+    pub x: u8,
+    pub y: u8,
+    pub ship_id: u8,                   // 0 = miss, n = ship n
+    pub cell_salt: [u8; 32],
+    pub proof: Vec<[u8; 32]>,          // Merkle sibling path for the cell
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CellOpening {
+    pub x: u8,
+    pub y: u8,
+    pub cell_salt: [u8; 32],
+    pub proof: Vec<[u8; 32]>,          // Merkle sibling path for the cell
+}
+
+// How a defender proves one cell of their board: a Merkle path up to the commitment, the
+// leaf already uploaded to their sealed BoardCommitments account, or a shot proof
+pub enum CellProof<'a> {
+    Merkle(&'a [[u8; 32]]),
+    Stored(&'a [u8; 32]),
+    Verified, // A zero-knowledge shot proof the caller already checked
+}
+
+// Returned by fire_shot and reveal_shot_result, so clients get the outcome from the transaction
+// (or a simulation) without fetching the game
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ShotOutcome {
+    pub x: u8,
+    pub y: u8,
+    pub result: u8,                    // 0 = pending, 1 = miss, 2 = hit, 3 = mine
+    pub ships_remaining: u8,           // Defender's ships still afloat
+    pub turn: u8,                      // Player to move next (1 or 2)
+    pub state: u8,                     // GameState after the shot
+}
+
+// Returned by get_game_summary
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GameSummary {
+    pub state: u8,                     // GameState discriminant
+    pub turn: u8,                      // 1 for player1, 2 for player2
+    pub player_to_move: u8,            // Who the game is waiting on, counting pending reveals
+    pub winner: u8,                    // 0=none, 1=player1, 2=player2
+    pub end_reason: u8,                // Same values as Game::end_reason
+    pub hits_count1: u8,               // Hits player1 has taken
+    pub hits_count2: u8,               // Hits player2 has taken
+    pub move_count: u16,               // Shots resolved so far
+    pub last_shot_x: u8,
+    pub last_shot_y: u8,
+    pub last_shot_by: u8,              // 0 = no shot resolved yet
+    pub last_shot_result: u8,          // 1=miss, 2=hit, 3=mine
+    pub last_action_slot: u64,
+    pub deadline_slot: u64,            // Turn timeout, or reveal deadline once the game is over (0 = none)
+    pub clock1: u64,                   // Player1's remaining clock, in slots
+    pub clock2: u64,                   // Player2's remaining clock, in slots
+}
+
+// Groth16 verifying key for one ZK_CIRCUIT_*, points encoded as the alt_bn128 syscalls
+// expect (big-endian, G2 coordinates imaginary part first)
+#[account]
+pub struct ZkVerifier {
+    pub alpha_g1: [u8; 64],            // 64 bytes
+    pub beta_g2: [u8; 128],            // 128 bytes
+    pub gamma_g2: [u8; 128],           // 128 bytes
+    pub delta_g2: [u8; 128],           // 128 bytes
+    pub ic: [[u8; 64]; MAX_ZK_INPUTS + 1], // 320 bytes - Constant term, then one point per public input
+    pub inputs: u8,                    // 1 byte - Public inputs the circuit takes
+    pub circuit: u8,                   // 1 byte - ZK_CIRCUIT_* this key verifies
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl ZkVerifier {
+    pub const LEN: usize = 8 + 64 + 128 + 128 + 128 + 64 * (MAX_ZK_INPUTS + 1) + 1 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ZkVerifyingKey {
+    pub alpha_g1: [u8; 64],
+    pub beta_g2: [u8; 128],
+    pub gamma_g2: [u8; 128],
+    pub delta_g2: [u8; 128],
+    pub ic: Vec<[u8; 64]>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ZkProof {
+    pub a: [u8; 64],                   // Negated by the client
+    pub b: [u8; 128],
+    pub c: [u8; 64],
+}
+
+// A board's per-cell Merkle leaves, uploaded up front so each shot is answered by opening
+// just the one cell instead of sending a Merkle path
+#[account(zero_copy)]
+pub struct BoardCommitments {
+    pub leaves: [[u8; 32]; MAX_CELLS], // 6400 bytes - Cell leaves in row-major order
+    pub game: Pubkey,                  // 32 bytes
+    pub player: Pubkey,                // 32 bytes - Board owner
+    pub commitment: [u8; 32],          // 32 bytes - Board commitment the leaves were sealed against (zeros = unsealed)
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub _padding: [u8; 7],             // 7 bytes - Rounds the account up to 8-byte alignment
+}
+
+impl BoardCommitments {
+    pub const LEN: usize = 8 + std::mem::size_of::<BoardCommitments>(); // 4712 bytes + discriminator
+}
+
+// One player's side of a two-player game, split out of Game so the hot account stays small.
+// Seeded by the game and the player's seat (1 or 2, as in Game::turn), so either board's
+// address is known before player2 joins
+#[account]
+pub struct PlayerBoard {
+    pub game: Pubkey,                  // 32 bytes
+    pub commitment: [u8; 32],          // 32 bytes - Merkle root of the player's per-cell commitments
+    pub hits: [u8; PACKED_CELLS],      // 50 bytes - Hits on the board, 2 bits per cell (0=empty, 1=miss, 2=hit, 3=mine)
+    pub ship_mask: CellMask,           // 32 bytes - Ship cells of the revealed board, one bit per cell (zeros until revealed)
+    pub seat: u8,                      // 1 byte - 1 for player1, 2 for player2
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl PlayerBoard {
+    pub const LEN: usize = 8 + 32 + 32 + PACKED_CELLS + 8 * MASK_WORDS + 1 + 1;
+}
+
+#[account]
+pub struct PlayerProfile {
+    pub wallet: Pubkey,                // 32 bytes - Profile owner
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub games_played: u32,             // 4 bytes - Finished games recorded
+    pub wins: u32,                     // 4 bytes
+    pub losses: u32,                   // 4 bytes
+    pub draws: u32,                    // 4 bytes
+    pub shots_fired: u32,              // 4 bytes - Accuracy = hits / shots_fired
+    pub hits: u32,                     // 4 bytes
+    pub ships_sunk: u32,               // 4 bytes - Opponent ships sunk
+    pub rating: u32,                   // 4 bytes - ELO rating
+    pub session_key: Pubkey,           // 32 bytes - Key allowed to fire/reveal for the wallet (default = none)
+    pub session_expires_slot: u64,     // 8 bytes - Last slot the session key is valid
+    pub season: u32,                   // 4 bytes - Season the season_* stats belong to
+    pub season_rating: u32,            // 4 bytes - ELO rating this season, reset each season
+    pub season_wins: u32,              // 4 bytes
+    pub season_losses: u32,            // 4 bytes
+    pub season_draws: u32,             // 4 bytes
+    pub win_streak: u32,               // 4 bytes - Consecutive ranked wins, up to the latest game
+    pub achievements: u32,             // 4 bytes - ACHIEVEMENT_* bits unlocked
+    pub loss_streak: u32,              // 4 bytes - Consecutive ranked losses, up to the latest game
+    pub best_win_streak: u32,          // 4 bytes - Longest win streak so far
+    pub referrer: Pubkey,              // 32 bytes - Who referred this player (default = nobody)
+    pub referral_rewards: u64,         // 8 bytes - Rake share earned from referred players, claimable from the treasury
+    pub timely_reveals: u32,           // 4 bytes - Ranked games whose board was revealed and proven honest
+    pub timeouts: u32,                 // 4 bytes - Ranked games lost to the clock or to a missing reveal
+    pub reputation: u32,               // 4 bytes - games_played + timely_reveals - REPUTATION_TIMEOUT_PENALTY per timeout
+    pub puzzles_solved: u32,           // 4 bytes - Puzzles solved within par
+    pub puzzle_score: u32,             // 4 bytes - A point per puzzle solved within par, plus one per shot to spare
+    pub name: String,                  // 4 + MAX_NAME_LEN bytes - Registered display name (empty = none)
+    pub avatar_mint: Pubkey,           // 32 bytes - NFT the player held when linking it as their avatar (default = none)
+    pub clan: Pubkey,                  // 32 bytes - Clan the player belongs to (default = none)
+}
+
+impl PlayerProfile {
+    pub const LEN: usize = 8 + 32 + 1 + 4 + 4 + 4 + 4 + 4 + 4 + 4 + 4 + 32 + 8 + 4 + 4 + 4 + 4 + 4 + 4 + 4 + 4 + 4 + 32 + 8 + 4 + 4 + 4 + 4 + 4 + (4 + MAX_NAME_LEN) + 32 + 32;
+}
+
+// A group of players whose ranked results against other clans add up to shared standings
+#[account]
+pub struct Clan {
+    pub name: String,                  // 4 + MAX_NAME_LEN bytes - Unique; the PDA is derived from it
+    pub founder: Pubkey,               // 32 bytes
+    pub members: u32,                  // 4 bytes - Profiles in the clan, up to MAX_CLAN_MEMBERS
+    pub wins: u32,                     // 4 bytes - Ranked games members won against other clans or clanless players
+    pub losses: u32,                   // 4 bytes
+    pub draws: u32,                    // 4 bytes
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl Clan {
+    pub const LEN: usize = 8 + (4 + MAX_NAME_LEN) + 32 + 4 + 4 + 4 + 4 + 1;
+}
+
+// A match-up between two clans: rostered members' games inside the slot window score points,
+// and the side with more takes both stakes
+#[account]
+pub struct ClanWar {
+    pub clan_a: Pubkey,                // 32 bytes - Declaring clan
+    pub clan_b: Pubkey,                // 32 bytes - Challenged clan
+    pub founder_a: Pubkey,             // 32 bytes - Staked for clan_a
+    pub founder_b: Pubkey,             // 32 bytes - Stakes for clan_b on accepting
+    pub war_id: u64,                   // 8 bytes - Lets a clan declare several wars
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub vault_bump: u8,                // 1 byte - Stake vault PDA bump
+    pub stake: u64,                    // 8 bytes - Lamports each side puts into the pot
+    pub start_slot: u64,               // 8 bytes - Games must finish at or after this slot to score
+    pub end_slot: u64,                 // 8 bytes - ...and at or before this one
+    pub state: u8,                     // 1 byte - 0=declared, 1=accepted, 2=finalized
+    pub roster_a: Vec<Pubkey>,         // 4 + 32 * MAX_WAR_ROSTER bytes - clan_a members enlisted
+    pub roster_b: Vec<Pubkey>,         // 4 + 32 * MAX_WAR_ROSTER bytes
+    pub points_a: u32,                 // 4 bytes - Wins by clan_a's roster over clan_b's
+    pub points_b: u32,                 // 4 bytes
+    pub winner: u8,                    // 1 byte - 0=draw, 1=clan_a, 2=clan_b; set on finalizing
+    pub claimed: u32,                  // 4 bytes - Bit per roster seat (roster_b offset by MAX_WAR_ROSTER) paid out
+}
+
+impl ClanWar {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 8 + 1 + 1 + 8 + 8 + 8 + 1
+        + (4 + 32 * MAX_WAR_ROSTER) + (4 + 32 * MAX_WAR_ROSTER) + 4 + 4 + 1 + 4;
+}
+
+// Marks a game as scored for a war; the PDA's address is derived from both
+#[account]
+pub struct WarGame {
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl WarGame {
+    pub const LEN: usize = 8 + 1;
+}
+
+// Claims a display name for one profile; the PDA's address is derived from the name itself
+#[account]
+pub struct NameRecord {
+    pub owner: Pubkey,                 // 32 bytes - Wallet whose profile holds the name
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl NameRecord {
+    pub const LEN: usize = 8 + 32 + 1;
+}
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,                 // 32 bytes - Program upgrade authority at setup; may be handed to a multisig
+    pub fee_bps: u16,                  // 2 bytes - Rake on wager payouts, in basis points
+    pub treasury: Pubkey,              // 32 bytes - Treasury PDA collecting the rake
+    pub treasury_bump: u8,             // 1 byte - Treasury PDA bump
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub is_paused: bool,               // 1 byte - Emergency stop for new games and joins
+    pub archive_tree: Pubkey,          // 32 bytes - Concurrent Merkle tree closed games are archived to (default = none)
+    pub max_rating_delta: u32,         // 4 bytes - Widest rating gap matchmaking will pair (0 = no limit)
+    pub season: u32,                   // 4 bytes - Current season; new games are stamped with it
+    pub season_started_slot: u64,      // 8 bytes - Slot the current season began
+    pub referral_rewards_owed: u64,    // 8 bytes - Treasury lamports set aside for unclaimed referral rewards
+    pub pending_admin: Pubkey,         // 32 bytes - Proposed admin awaiting acceptance (default = none)
+    pub timeout_strike_limit: u8,      // 1 byte - Missed turn deadlines that forfeit a new game (0 = no limit)
+    pub abandon_refund_bps: u16,       // 2 bytes - Share of a lamport pot returned to a player who times out mid-game
+    pub wager_limits: Vec<WagerLimit>, // 4 + MAX_WAGER_LIMITS * 48 bytes - Wager bounds per mint (unlisted = unbounded)
+}
+
+impl Config {
+    pub const LEN: usize = 8 + 32 + 2 + 32 + 1 + 1 + 1 + 32 + 4 + 4 + 8 + 8 + 32 + 1 + 2 + (4 + MAX_WAGER_LIMITS * WagerLimit::LEN);
+}
+
+// Marks an SPL mint as accepted for wagers; closing it stops new games in that mint
+#[account]
+pub struct AllowedMint {
+    pub mint: Pubkey,                  // 32 bytes
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl AllowedMint {
+    pub const LEN: usize = 8 + 32 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WagerLimit {
+    pub mint: Pubkey,                  // 32 bytes - Wager mint (default = lamports)
+    pub min_wager: u64,                // 8 bytes - Smallest wager a new game may stake
+    pub max_wager: u64,                // 8 bytes - Largest wager a new game may stake (0 = no cap)
+}
+
+impl WagerLimit {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+#[account]
+pub struct Tournament {
+    pub organizer: Pubkey,             // 32 bytes - Creator of the tournament
+    pub tournament_id: u64,            // 8 bytes - Lets an organizer run several tournaments
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub vault_bump: u8,                // 1 byte - Entry fee vault PDA bump
+    pub entry_fee: u64,                // 8 bytes - Lamports each entrant pays into the prize pool
+    pub max_players: u8,               // 1 byte - Bracket size: 4, 8 or 16
+    pub format: u8,                    // 1 byte - 0=single elimination, 1=Swiss
+    pub swiss_rounds: u8,              // 1 byte - Rounds a Swiss tournament plays
+    pub entrants: Vec<Pubkey>,         // 4 + 32 * MAX_TOURNAMENT_PLAYERS bytes - In registration order
+    pub scores: Vec<u8>,               // 4 + MAX_TOURNAMENT_PLAYERS bytes - Swiss points, indexed like entrants
+    pub state: u8,                     // 1 byte - 0=registration, 1=in progress, 2=finished
+    pub round: u8,                     // 1 byte - Current round, starting at 1
+    pub bracket: Vec<Pubkey>,          // 4 + 32 * MAX_TOURNAMENT_PLAYERS bytes - Current pairings; match i is 2i vs 2i+1
+    pub round_winners: Vec<Pubkey>,    // 4 + 32 * MAX_TOURNAMENT_PLAYERS / 2 bytes - Reported winners (default = pending)
+    pub round_start_slot: u64,         // 8 bytes - Games must finish after this to count
+    pub champion: Pubkey,              // 32 bytes - Set once the final is decided
+}
+
+impl Tournament {
+    pub const LEN: usize = 8 + 32 + 8 + 1 + 1 + 8 + 1 + 1 + 1 + 4 + 32 * MAX_TOURNAMENT_PLAYERS
+        + 4 + MAX_TOURNAMENT_PLAYERS + 1 + 1
+        + 4 + 32 * MAX_TOURNAMENT_PLAYERS + 4 + 32 * (MAX_TOURNAMENT_PLAYERS / 2) + 8 + 32;
+}
+
+#[account]
+pub struct Leaderboard {
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub entries: Vec<LeaderboardEntry>, // 4 + 40 * MAX_LEADERBOARD_ENTRIES bytes - Sorted by rating, best first
+}
+
+impl Leaderboard {
+    pub const LEN: usize = 8 + 1 + 4 + LeaderboardEntry::LEN * MAX_LEADERBOARD_ENTRIES;
+}
+
+// Players caught with an inconsistent board or who never revealed one, for frontends and
+// matchmaking to flag
+#[account]
+pub struct CheaterRegistry {
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub entries: Vec<CheaterEntry>,    // 4 + 44 * MAX_CHEATER_ENTRIES bytes - One per offender
+}
+
+impl CheaterRegistry {
+    pub const LEN: usize = 8 + 1 + 4 + CheaterEntry::LEN * MAX_CHEATER_ENTRIES;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct CheaterEntry {
+    pub player: Pubkey,                // 32 bytes - Offender's wallet
+    pub offenses: u32,                 // 4 bytes - Games caught cheating or forfeited by not revealing
+    pub last_offense_slot: u64,        // 8 bytes - Slot the latest offense was recorded
+}
+
+impl CheaterEntry {
+    pub const LEN: usize = 32 + 4 + 8;
+}
+
+// A finished season's final standings, written once by rollover_season
+#[account]
+pub struct Season {
+    pub season: u32,                   // 4 bytes - Season number, part of the PDA seeds
+    pub started_slot: u64,             // 8 bytes
+    pub ended_slot: u64,               // 8 bytes
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub standings: Vec<LeaderboardEntry>, // 4 + 40 * MAX_LEADERBOARD_ENTRIES bytes - Leaderboard as the season closed
+}
+
+impl Season {
+    pub const LEN: usize = 8 + 4 + 8 + 8 + 1 + 4 + LeaderboardEntry::LEN * MAX_LEADERBOARD_ENTRIES;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    pub player: Pubkey,                // 32 bytes - Player wallet
+    pub rating: u32,                   // 4 bytes - Rating when last placed
+    pub wins: u32,                     // 4 bytes - Wins when last placed (tie-breaker)
+}
+
+impl LeaderboardEntry {
+    pub const LEN: usize = 32 + 4 + 4;
+}
+
+#[account]
+pub struct Lobby {
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub entries: Vec<LobbyEntry>,      // 4 + 105 * MAX_LOBBY_ENTRIES bytes - Joinable public games
+}
+
+impl Lobby {
+    pub const LEN: usize = 8 + 1 + 4 + LobbyEntry::LEN * MAX_LOBBY_ENTRIES;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct LobbyEntry {
+    pub game: Pubkey,                  // 32 bytes - Game PDA
+    pub creator: Pubkey,               // 32 bytes - Player1
+    pub wager: u64,                    // 8 bytes - Stake each player puts up
+    pub wager_mint: Pubkey,            // 32 bytes - SPL mint of the wager (default = lamports)
+    pub board_size: u8,                // 1 byte - Board width/height
+}
+
+impl LobbyEntry {
+    pub const LEN: usize = 32 + 32 + 8 + 32 + 1;
+}
+
+#[account]
+pub struct BettingPool {
+    pub game: Pubkey,                  // 32 bytes - Game the spectators are betting on
+    pub opener: Pubkey,                // 32 bytes - Paid the pool's rent, gets it back at the end
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub total_on_player1: u64,         // 8 bytes - Lamports backing player1
+    pub total_on_player2: u64,         // 8 bytes - Lamports backing player2
+}
+
+impl BettingPool {
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 8 + 8;
+}
+
+// Observers (streamers, casters) a game's creator lets see both boards, and the hashes of the
+// encrypted boards each player handed them
+#[account]
+pub struct GameObservers {
+    pub game: Pubkey,                  // 32 bytes
+    pub observers: Vec<Pubkey>,        // 4 + 32 * MAX_OBSERVERS bytes
+    pub sealed1: Vec<[u8; 32]>,        // 4 + 32 * MAX_OBSERVERS bytes - Hash of player1's board encrypted to each observer (zeros = not sealed)
+    pub sealed2: Vec<[u8; 32]>,        // 4 + 32 * MAX_OBSERVERS bytes - Same for player2
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl GameObservers {
+    pub const LEN: usize = 8 + 32 + 3 * (4 + 32 * MAX_OBSERVERS) + 1;
+}
+
+// Marks one wallet as watching a game; Game.spectators counts these
+#[account]
+pub struct SpectatorMarker {
+    pub game: Pubkey,                  // 32 bytes
+    pub spectator: Pubkey,             // 32 bytes
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl SpectatorMarker {
+    pub const LEN: usize = 8 + 32 + 32 + 1;
+}
+
+#[account]
+pub struct SpectatorBet {
+    pub bettor: Pubkey,                // 32 bytes
+    pub game: Pubkey,                  // 32 bytes
+    pub side: u8,                      // 1 byte - 1=player1, 2=player2
+    pub amount: u64,                   // 8 bytes - Lamports staked
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl SpectatorBet {
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 8 + 1;
+}
+
+// A 3-4 player free-for-all, or a 2v2 team game, on 10x10 boards with the standard fleet.
+// Seats are indexed 0..player_count in join order; each seat has its own commitment and hit board.
+#[account(zero_copy)]
+pub struct FfaGame {
+    pub game_id: u64,                  // 8 bytes - Creator-chosen id, part of the PDA seeds
+    pub last_action_slot: u64,         // 8 bytes - Slot of the last join/shot/reveal
+    pub wager: u64,                    // 8 bytes - Lamports each player staked (0 = no wager)
+    pub players: [Pubkey; 4],          // 128 bytes - Seat 0 is the creator
+    pub referrers: [Pubkey; 4],        // 128 bytes - Who referred each seat, stamped as it's taken (default = nobody)
+    pub board_commits: [[u8; 32]; 4],  // 128 bytes - Merkle root of each player's per-cell commitments
+    pub board_hits: [[u8; PACKED_CELLS]; 4], // 200 bytes - Packed hit board per player
+    pub ship_hits: [[u8; MAX_SHIPS]; 4], // 32 bytes - Hits taken per ship, per player
+    pub hits_count: [u8; 4],           // 4 bytes - Hits taken per player
+    pub eliminated: [u8; 4],           // 4 bytes - Fleet sunk or timed out (0/1)
+    pub pending_shot: [u8; 2],         // 2 bytes - (x, y) awaiting the target's reveal
+    pub pending_target: u8,            // 1 byte - Seat the pending shot was fired at
+    pub has_pending_shot: u8,          // 1 byte - Whether pending_shot is set (0/1)
+    pub max_players: u8,               // 1 byte - 3 or 4
+    pub player_count: u8,              // 1 byte - Seats taken
+    pub turn: u8,                      // 1 byte - Seat to fire next (a sunk team seat's teammate fires for it)
+    pub state: u8,                     // 1 byte - GameState (Waiting, InProgress, Finished, or Cancelled if a winner cheated)
+    pub winner: u8,                    // 1 byte - 0=none, n=seat n-1 (in team games, with seat n+1 mod 4)
+    pub teams: u8,                     // 1 byte - 2v2: seats 0 and 2 against seats 1 and 3 (0/1)
+    pub winnings_claimed: u8,          // 1 byte - Pot already paid out (0/1)
+    pub bump: u8,                      // 1 byte - PDA bump
+    pub vault_bump: u8,                // 1 byte - Vault PDA bump
+    pub revealed: [u8; 4],             // 4 bytes - Board opened after the game, per seat (0=no, 1=honest, 2=cheated)
+    pub refunded: [u8; 4],             // 4 bytes - Stake reclaimed from a voided game, per seat (0/1)
+    pub _padding: [u8; 3],             // 3 bytes - Rounds the account up to 8-byte alignment
+}
+
+impl FfaGame {
+    pub const LEN: usize = 8 + std::mem::size_of::<FfaGame>(); // 616 bytes + discriminator
     
-    pub player: Signer<'info>,
+    pub fn state(&self) -> GameState {
+        GameState::from_u8(self.state)
+    }
+    
+    pub fn set_state(&mut self, state: GameState) {
+        self.state = state as u8;
+    }
 }
 
+// A player waiting for an opponent; the account's lamports escrow their stake, bond and
+// rent share until a keeper pairs them or they leave
 #[account]
-pub struct Game {
-    pub player1: Pubkey,               // 32 bytes
-    pub player2: Pubkey,               // 32 bytes
-    pub board_commit1: [u8; 32],       // 32 bytes - Player1's board commitment hash
-    pub board_commit2: [u8; 32],       // 32 bytes - Player2's board commitment hash
-    pub turn: u8,                      // 1 byte - 1 for player1, 2 for player2
-    pub board_hits1: [u8; 100],        // 100 bytes - Hits on player1's board (0=empty, 1=miss, 2=hit)
-    pub board_hits2: [u8; 100],        // 100 bytes - Hits on player2's board (0=empty, 1=miss, 2=hit)
-    pub hits_count1: u8,               // 1 byte - Number of hits player1 has taken
-    pub hits_count2: u8,               // 1 byte - Number of hits player2 has taken
-    pub is_initialized: bool,          // 1 byte - Both players joined
-    pub is_game_over: bool,            // 1 byte - Game finished
-    pub winner: u8,                    // 1 byte - 0=none, 1=player1, 2=player2
-    pub pending_shot: Option<(u8, u8)>, // 3 bytes - Current pending shot coordinates
-    pub pending_shot_by: Pubkey,       // 32 bytes - Who fired the pending shot
-    pub player1_revealed: bool,        // 1 byte - Player1 has revealed their board
-    pub player2_revealed: bool,        // 1 byte - Player2 has revealed their board
+pub struct QueueTicket {
+    pub player: Pubkey,                // 32 bytes
+    pub wager: u64,                    // 8 bytes - Lamports the player will stake (0 = no wager)
+    pub rating_band: u8,               // 1 byte - Only tickets in the same band are paired
+    pub entered_slot: u64,             // 8 bytes - Slot the player joined the queue
     pub bump: u8,                      // 1 byte - PDA bump
 }
 
-impl Game {
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 1 + 100 + 100 + 1 + 1 + 1 + 3 + 32 + 1 + 1 + 1; // ~380 bytes + discriminator
+impl QueueTicket {
+    pub const LEN: usize = 8 + 32 + 8 + 1 + 8 + 1;
+}
+
+// A solo game against the house on a 10x10 board with the standard fleet. The house's board is
+// dealt from Switchboard randomness and a secret the house committed before that randomness was
+// known, then answered shot by shot with Merkle proofs; once the fleet is sunk the house reveals
+// its secret to show the board was the one the randomness dealt, or forfeits if it stalls
+#[account]
+pub struct HouseGame {
+    pub player: Pubkey,                // 32 bytes
+    pub game_id: u64,                  // 8 bytes - Player-chosen id, part of the PDA seeds
+    pub house: Pubkey,                 // 32 bytes - Admin who deals the board and answers its shots
+    pub randomness_account: Pubkey,    // 32 bytes - Switchboard account the board is dealt from
+    pub secret_hash: [u8; 32],         // 32 bytes - Hash of the house's secret, committed before the deal
+    pub commitment: [u8; 32],          // 32 bytes - Merkle root of the dealt board's per-cell commitments
+    pub seed: [u8; 32],                // 32 bytes - VRF value the board was dealt from (zeros until dealt)
+    pub board_hits: [u8; PACKED_CELLS], // 50 bytes - Player's shots, 2 bits per cell (0=empty, 1=miss, 2=hit)
+    pub ship_hits: [u8; 5],            // 5 bytes - Hits taken by each house ship
+    pub hits: u8,                      // 1 byte - Hits landed in total
+    pub shots: u16,                    // 2 bytes - Shots fired; the player's score once the fleet is sunk
+    pub pending_shot: [u8; 2],         // 2 bytes - (x, y) awaiting the house's answer
+    pub has_pending_shot: u8,          // 1 byte - Whether pending_shot is set (0/1)
+    pub state: u8,                     // 1 byte - GameState (Waiting until dealt, InProgress, Finished)
+    pub verdict: u8,                   // 1 byte - 0=unsettled, 1=board matches the deal, 2=board short of a fleet, 3=house forfeited
+    pub last_action_slot: u64,         // 8 bytes - Slot of the deal, the last shot or the last answer
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl HouseGame {
+    pub const LEN: usize = 8 + 32 + 8 + 32 + 32 + 32 + 32 + 32 + PACKED_CELLS + 5 + 1 + 2 + 2 + 1 + 1 + 1 + 8 + 1;
+}
+
+// A curated 10x10 board with the standard fleet for solo attempts. The creator answers each
+// attempt's shots with Merkle proofs against the commitment, as a defender would in a game, and
+// retires the puzzle by revealing the board
+#[account]
+pub struct Puzzle {
+    pub creator: Pubkey,               // 32 bytes
+    pub puzzle_id: u64,                // 8 bytes - Creator-chosen id, part of the PDA seeds
+    pub commitment: [u8; 32],          // 32 bytes - Merkle root of the board's per-cell commitments
+    pub par: u16,                      // 2 bytes - Most shots a solve may take and still score
+    pub attempts: u32,                 // 4 bytes - Attempts started
+    pub solves: u32,                   // 4 bytes - Attempts that sank the fleet within par
+    pub forfeits: u32,                 // 4 bytes - Attempts the creator stalled past the turn timeout
+    pub revealed: u8,                  // 1 byte - Board revealed and verified, retiring the puzzle (0/1)
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl Puzzle {
+    pub const LEN: usize = 8 + 32 + 8 + 32 + 2 + 4 + 4 + 4 + 1 + 1;
+}
+
+// One player's run at a puzzle
+#[account]
+pub struct PuzzleAttempt {
+    pub puzzle: Pubkey,                // 32 bytes
+    pub player: Pubkey,                // 32 bytes
+    pub board_hits: [u8; PACKED_CELLS], // 50 bytes - Player's shots, 2 bits per cell (0=empty, 1=miss, 2=hit)
+    pub ship_hits: [u8; 5],            // 5 bytes - Hits taken by each ship of the puzzle's fleet
+    pub hits: u8,                      // 1 byte - Hits landed in total
+    pub shots: u16,                    // 2 bytes - Shots fired, pending one included
+    pub pending_shot: [u8; 2],         // 2 bytes - (x, y) awaiting the creator's answer
+    pub has_pending_shot: u8,          // 1 byte - Whether pending_shot is set (0/1)
+    pub state: u8,                     // 1 byte - GameState (InProgress, then Finished once the fleet is sunk or forfeited)
+    pub forfeited: u8,                 // 1 byte - Creator left a shot unanswered past the timeout (0/1)
+    pub last_action_slot: u64,         // 8 bytes - Slot of the last shot or answer, for the answer deadline
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl PuzzleAttempt {
+    pub const LEN: usize = 8 + 32 + 32 + PACKED_CELLS + 5 + 1 + 2 + 2 + 1 + 1 + 1 + 8 + 1;
+}
+
+// One day's shared 10x10 board with the standard fleet. Everyone attacks the same hidden board,
+// the host answers each shot with a Merkle proof, and the whole board is revealed once the day ends
+#[account]
+pub struct Challenge {
+    pub day: u32,                      // 4 bytes - Days since the Unix epoch (UTC), the PDA seed
+    pub host: Pubkey,                  // 32 bytes - Admin who committed the board and answers shots
+    pub commitment: [u8; 32],          // 32 bytes - Merkle root of the board's per-cell commitments
+    pub players: u32,                  // 4 bytes - Players who joined
+    pub leaderboard: Vec<ChallengeScore>, // 4 + MAX_CHALLENGE_LEADERS * 34 bytes - Fewest shots first
+    pub revealed: u8,                  // 1 byte - Board revealed and verified after the day (0/1)
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl Challenge {
+    pub const LEN: usize = 8 + 4 + 32 + 32 + 4 + (4 + MAX_CHALLENGE_LEADERS * ChallengeScore::LEN) + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ChallengeScore {
+    pub player: Pubkey,                // 32 bytes
+    pub shots: u16,                    // 2 bytes - Shots taken to sink the fleet
+}
+
+impl ChallengeScore {
+    pub const LEN: usize = 32 + 2;
+}
+
+// One player's run at a daily challenge
+#[account]
+pub struct ChallengeEntry {
+    pub challenge: Pubkey,             // 32 bytes
+    pub player: Pubkey,                // 32 bytes
+    pub board_hits: [u8; PACKED_CELLS], // 50 bytes - Player's shots, 2 bits per cell (0=empty, 1=miss, 2=hit)
+    pub ship_hits: [u8; 5],            // 5 bytes - Hits taken by each ship of the day's fleet
+    pub hits: u8,                      // 1 byte - Hits landed in total
+    pub shots: u16,                    // 2 bytes - Shots fired, pending one included
+    pub pending_shot: [u8; 2],         // 2 bytes - (x, y) awaiting the host's answer
+    pub has_pending_shot: u8,          // 1 byte - Whether pending_shot is set (0/1)
+    pub state: u8,                     // 1 byte - GameState (InProgress, then Finished once the fleet is sunk)
+    pub bump: u8,                      // 1 byte - PDA bump
+}
+
+impl ChallengeEntry {
+    pub const LEN: usize = 8 + 32 + 32 + PACKED_CELLS + 5 + 1 + 2 + 2 + 1 + 1 + 1;
+}
+
+#[event]
+pub struct ShipSunk {
+    pub game: Pubkey,
+    pub owner: Pubkey,                 // Player whose ship went down
+    pub ship_id: u8,                   // 1-based index into the game's fleet
+    pub ship_size: u8,
+    pub spectators: u32,               // Registered audience at the time (always 0 in free-for-alls)
+}
+
+#[event]
+pub struct MoveAwaited {
+    pub game: Pubkey,
+    pub player: Pubkey,                // Wallet the game is waiting on
+    pub player_num: u8,                // 1=player1, 2=player2
+    pub deadline_slot: u64,            // After this the opponent can claim the game
+}
+
+#[event]
+pub struct ShotResolved {
+    pub game: Pubkey,
+    pub shooter: Pubkey,
+    pub x: u8,
+    pub y: u8,
+    pub result: u8,                    // 1=miss, 2=hit, 3=mine
+}
+
+#[event]
+pub struct SpectatorJoined {
+    pub game: Pubkey,
+    pub spectator: Pubkey,
+    pub spectators: u32,               // Audience including the new spectator
+}
+
+#[event]
+pub struct SpectatorLeft {
+    pub game: Pubkey,
+    pub spectator: Pubkey,
+    pub spectators: u32,               // Audience left (0 once the game is closed)
+}
+
+#[event]
+pub struct RadarScanned {
+    pub game: Pubkey,
+    pub scanner: Pubkey,               // Player who used their radar
+    pub x: u8,                         // Center of the 3x3 area scanned
+    pub y: u8,
+    pub ship_cells: u8,                // Ship cells the defender reported in the area
+}
+
+#[event]
+pub struct CheatingDetected {
+    pub game: Pubkey,
+    pub cheater: Pubkey,               // Player whose revealed board contradicted their shot results
+}
+
+#[event]
+pub struct GameArchived {
+    pub game: Pubkey,
+    pub player1: Pubkey,
+    pub player2: Pubkey,
+    pub winner: u8,                    // 0=none, 1=player1, 2=player2
+    pub end_reason: u8,
+    pub boards_hash: [u8; 32],         // sha256 of both packed hit boards
+    pub shots: u16,                    // Resolved shots in the move log
+    pub ended_slot: u64,
+}
+
+#[event]
+pub struct PlayerEliminated {
+    pub game: Pubkey,                  // Free-for-all game
+    pub player: Pubkey,                // Player whose fleet sank or who timed out
+}
+
+#[event]
+pub struct AchievementUnlocked {
+    pub player: Pubkey,
+    pub game: Pubkey,                  // Game whose result unlocked it
+    pub achievement: u32,              // One ACHIEVEMENT_* bit
+}
+
+#[event]
+pub struct ArbiterRuling {
+    pub game: Pubkey,
+    pub arbiter: Pubkey,
+    pub winner: u8,                    // 0=draw, 1=player1, 2=player2
+    pub slot: u64,
+}
+
+#[event]
+pub struct EmoteSent {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub emote_id: u8,                  // Index into the frontend's emote set, below NUM_EMOTES
+}
+
+#[event]
+pub struct ChatMessage {
+    pub game: Pubkey,
+    pub sender: Pubkey,
+    pub message: String,               // Up to MAX_MESSAGE_LEN bytes of UTF-8
+}
+
+#[event]
+pub struct StreakMilestone {
+    pub player: Pubkey,
+    pub game: Pubkey,                  // Game that extended the streak
+    pub streak: u32,                   // Consecutive wins, a multiple of STREAK_MILESTONE
+}
+
+#[event]
+pub struct PlayersMatched {
+    pub game: Pubkey,                  // Fresh game both players now commit their boards to
+    pub player1: Pubkey,
+    pub player2: Pubkey,
+    pub wager: u64,
 }
 
 #[error_code]
@@ -326,7 +11504,7 @@ pub enum ErrorCode {
     GameOver,
     #[msg("Game is not over yet - cannot reveal")]
     GameNotOver,
-    #[msg("Invalid coordinate - must be 0-9")]
+    #[msg("Invalid coordinate - outside the board")]
     InvalidCoordinate,
     #[msg("Already shot at this coordinate")]
     AlreadyShotHere,
@@ -344,7 +11522,7 @@ pub enum ErrorCode {
     ShotPending,
     #[msg("Commitment hash does not match revealed data")]
     CommitmentMismatch,
-    #[msg("Invalid fleet configuration - must have exactly 17 ship squares")]
+    #[msg("Invalid fleet configuration - ship squares don't match the game's fleet")]
     InvalidFleetConfiguration,
     #[msg("Not player1")]
     NotPlayer1,
@@ -354,4 +11532,350 @@ pub enum ErrorCode {
     AlreadyRevealed,
     #[msg("Cheating detected - shot results don't match revealed board")]
     CheatingDetected,
+    #[msg("Wager is below the vault's rent-exempt minimum")]
+    WagerTooSmall,
+    #[msg("Both boards must be revealed first")]
+    BoardsNotRevealed,
+    #[msg("This game has no wager")]
+    NoWager,
+    #[msg("Winnings already claimed")]
+    WinningsAlreadyClaimed,
+    #[msg("Only the winner can claim")]
+    NotWinner,
+    #[msg("Wager type does not match this game")]
+    WrongWagerType,
+    #[msg("It is your move - you cannot claim a timeout")]
+    NotOpponentsMove,
+    #[msg("Opponent has not timed out yet")]
+    TimeoutNotReached,
+    #[msg("Winnings must be claimed before closing the game")]
+    WinningsNotClaimed,
+    #[msg("This game is invite-only")]
+    NotInvited,
+    #[msg("Lobby account required for public games")]
+    LobbyRequired,
+    #[msg("Game is already listed in the lobby")]
+    AlreadyListed,
+    #[msg("Lobby is full")]
+    LobbyFull,
+    #[msg("Merkle proof does not match the committed board")]
+    InvalidCellProof,
+    #[msg("Invalid ship placement - ships must be straight, non-overlapping and match the fleet")]
+    InvalidShipPlacement,
+    #[msg("Invalid ship id")]
+    InvalidShipId,
+    #[msg("Salvo games must use fire_salvo")]
+    SalvoGame,
+    #[msg("This game is not in salvo mode")]
+    NotSalvoGame,
+    #[msg("A salvo must have between one shot and one shot per ship afloat")]
+    InvalidSalvoSize,
+    #[msg("Board size must be 8, 10 or 12, with its layers fitting in 200 cells")]
+    InvalidBoardSize,
+    #[msg("Fleet must have at most 8 ships that fit on the board")]
+    InvalidFleet,
+    #[msg("Opponent has revealed their board - nothing to forfeit")]
+    OpponentRevealed,
+    #[msg("Reveal deadline has not passed yet")]
+    RevealDeadlineNotReached,
+    #[msg("Honesty bond already settled")]
+    BondAlreadySettled,
+    #[msg("Opponent's bond has not been forfeited")]
+    BondNotForfeited,
+    #[msg("An honest player can still collect a forfeited bond")]
+    BondsNotSettled,
+    #[msg("First turn has not been decided - player1 must reveal their nonce")]
+    TurnNotDecided,
+    #[msg("First turn has already been decided")]
+    TurnAlreadyDecided,
+    #[msg("Choose either a nonce coin flip or VRF for the first turn")]
+    ConflictingCoinFlip,
+    #[msg("A randomness request is already outstanding")]
+    RandomnessAlreadyRequested,
+    #[msg("Invalid randomness account")]
+    InvalidRandomnessAccount,
+    #[msg("Randomness must be committed in the previous slot")]
+    StaleRandomness,
+    #[msg("Randomness has not been revealed in this slot")]
+    RandomnessNotRevealed,
+    #[msg("A rematch has already been proposed")]
+    RematchAlreadyProposed,
+    #[msg("No rematch has been proposed")]
+    NoRematchProposed,
+    #[msg("You cannot accept your own rematch")]
+    CannotAcceptOwnRematch,
+    #[msg("A rematch offer is pending")]
+    RematchPending,
+    #[msg("Series length must be 1, 3 or 5")]
+    InvalidSeriesLength,
+    #[msg("The best-of-N series is still in progress")]
+    SeriesInProgress,
+    #[msg("No draw has been offered")]
+    NoDrawOffered,
+    #[msg("You cannot accept your own draw offer")]
+    CannotAcceptOwnDraw,
+    #[msg("Game did not end in an agreed draw")]
+    NotADraw,
+    #[msg("Game result already recorded")]
+    StatsAlreadyRecorded,
+    #[msg("Tournament size must be 4, 8 or 16 players")]
+    InvalidTournamentSize,
+    #[msg("Tournament registration is closed")]
+    RegistrationClosed,
+    #[msg("Already registered for this tournament")]
+    AlreadyRegistered,
+    #[msg("Tournament is not in progress")]
+    TournamentNotInProgress,
+    #[msg("Game does not match this bracket pairing")]
+    InvalidMatch,
+    #[msg("Match result already reported")]
+    ResultAlreadyReported,
+    #[msg("Game finished before this round started")]
+    StaleGame,
+    #[msg("Not every match in this round has a result")]
+    RoundIncomplete,
+    #[msg("Champion's account is required to pay the prize")]
+    ChampionAccountRequired,
+    #[msg("Format must be 0 (single elimination, no Swiss rounds) or 1 (Swiss, 1 to players - 1 rounds)")]
+    InvalidTournamentFormat,
+    #[msg("Fee cannot exceed 10%")]
+    FeeTooHigh,
+    #[msg("Only the admin can do this")]
+    NotAdmin,
+    #[msg("Treasury balance too low")]
+    InsufficientTreasury,
+    #[msg("The program is paused - no new games can start")]
+    Paused,
+    #[msg("The trophy for this game has already been minted")]
+    TrophyAlreadyMinted,
+    #[msg("Staked token must be an NFT (0 decimals, supply of 1)")]
+    NotAnNft,
+    #[msg("NFT-stake games are a single round")]
+    NftGameSingleRound,
+    #[msg("Bet on player 1 or player 2")]
+    InvalidBetSide,
+    #[msg("Players can't bet on their own game")]
+    PlayersCannotBet,
+    #[msg("Betting closed once the first shot was fired")]
+    BettingClosed,
+    #[msg("Remaining accounts must be (bet, bettor) pairs for this game")]
+    InvalidBetAccounts,
+    #[msg("Spectator bets on this game are still unsettled")]
+    BetsOutstanding,
+    #[msg("This game has no chess clock")]
+    NoTimeControl,
+    #[msg("Your clock has run out")]
+    ClockExpired,
+    #[msg("The player to move still has time on their clock")]
+    ClockNotExpired,
+    #[msg("The reveal forfeit has already been applied")]
+    AlreadyForfeited,
+    #[msg("Sessions last between 1 slot and a day")]
+    InvalidSessionDuration,
+    #[msg("Signer is not this profile's session key")]
+    InvalidSessionKey,
+    #[msg("Session key has expired")]
+    SessionExpired,
+    #[msg("Missing or invalid ed25519 signature for the relayed move")]
+    InvalidRelaySignature,
+    #[msg("Relayed move nonce already used or out of order")]
+    InvalidRelayNonce,
+    #[msg("The archive tree, compression and noop programs are required to close games")]
+    ArchiveAccountsRequired,
+    #[msg("Merkle tree is not the configured game archive")]
+    WrongArchiveTree,
+    #[msg("A free-for-all seats 3 or 4 players")]
+    InvalidPlayerCount,
+    #[msg("Target must be another player still afloat")]
+    InvalidTarget,
+    #[msg("Team winnings need the signer's teammate account")]
+    WrongTeammate,
+    #[msg("Radar scans are not enabled for this game")]
+    RadarDisabled,
+    #[msg("Radar has already been used this game")]
+    RadarAlreadyUsed,
+    #[msg("A radar scan is waiting for the defender's answer")]
+    ScanPending,
+    #[msg("No radar scan to answer")]
+    NoPendingScan,
+    #[msg("A 3x3 scan holds at most 9 ship cells")]
+    InvalidScanResult,
+    #[msg("Areabombs are not enabled for this game")]
+    AreabombDisabled,
+    #[msg("Areabomb has already been used this game")]
+    AreabombAlreadyUsed,
+    #[msg("Boards may only hide mines, at most 2, in the sea mines variant")]
+    InvalidMines,
+    #[msg("Ships can't move in this game")]
+    MovingShipsDisabled,
+    #[msg("Only ships that haven't been hit can move")]
+    ShipDamaged,
+    #[msg("A ship moves one cell along its own axis")]
+    InvalidShipMove,
+    #[msg("These variants can't be combined")]
+    ConflictingVariants,
+    #[msg("Not enough ammunition left for this shot")]
+    OutOfAmmo,
+    #[msg("Queued players want different wagers or rating bands")]
+    QueueMismatch,
+    #[msg("Only games made by the matchmaking queue")]
+    NotAMatchedGame,
+    #[msg("Board is already committed")]
+    BoardAlreadyCommitted,
+    #[msg("Players' ratings are too far apart to be matched")]
+    RatingGapTooWide,
+    #[msg("Ranked games need both players' profiles")]
+    ProfileRequired,
+    #[msg("Casual games don't count toward ratings or stats")]
+    CasualGame,
+    #[msg("Profile hasn't played in the current season")]
+    StaleSeason,
+    #[msg("No referral rewards to claim")]
+    NoReferralRewards,
+    #[msg("Neither player cheated or skipped their reveal")]
+    NoOffense,
+    #[msg("This game's offense is already recorded")]
+    OffenseAlreadyRecorded,
+    #[msg("Your reputation is below this game's minimum")]
+    ReputationTooLow,
+    #[msg("The arbiter can't be one of the players")]
+    PlayerCannotArbitrate,
+    #[msg("Only the game's arbiter can rule on it")]
+    NotArbiter,
+    #[msg("Only a game in play or awaiting reveals can be ruled on")]
+    GameNotStuck,
+    #[msg("The game hasn't gone quiet for the dispute window yet")]
+    DisputeWindowOpen,
+    #[msg("Ruling must be 0 (draw), 1 or 2, and a series can't be ruled a draw")]
+    InvalidRuling,
+    #[msg("Only the proposed admin can accept the admin role")]
+    NotPendingAdmin,
+    #[msg("No abort has been proposed")]
+    NoAbortProposed,
+    #[msg("You cannot confirm your own abort proposal")]
+    CannotConfirmOwnAbort,
+    #[msg("Abandon refund can be at most 50% of the pot")]
+    AbandonRefundTooHigh,
+    #[msg("The opponent's account is needed to refund their share")]
+    LoserAccountRequired,
+    #[msg("Minimum wager can't be above the maximum")]
+    InvalidWagerLimits,
+    #[msg("Wager limits are already set for the most mints allowed")]
+    TooManyWagerLimits,
+    #[msg("Wager is above the maximum allowed")]
+    WagerTooLarge,
+    #[msg("The uploaded cell hashes don't add up to the board commitment")]
+    CellHashesMismatch,
+    #[msg("Cell hashes aren't sealed against your current board commitment")]
+    CellHashesNotSealed,
+    #[msg("This game doesn't use fleet proofs")]
+    NotAZkGame,
+    #[msg("Your fleet is already proven")]
+    FleetAlreadyProven,
+    #[msg("Fleet proof does not verify against the board commitment")]
+    InvalidFleetProof,
+    #[msg("Verifying key doesn't fit the circuit")]
+    InvalidVerifyingKey,
+    #[msg("Shot proof does not verify against the board commitment")]
+    InvalidShotProof,
+    #[msg("This game only accepts zero-knowledge shot proofs")]
+    ShotProofRequired,
+    #[msg("The house board is already dealt")]
+    HouseBoardDealt,
+    #[msg("Par must be between 17 and 100 shots")]
+    InvalidPar,
+    #[msg("Only the puzzle's creator can answer its shots")]
+    NotPuzzleCreator,
+    #[msg("That day's challenge is already over")]
+    ChallengeDayOver,
+    #[msg("This challenge is not today's")]
+    ChallengeNotToday,
+    #[msg("The challenge's day hasn't ended yet")]
+    ChallengeNotOver,
+    #[msg("Only the challenge's host can do this")]
+    NotChallengeHost,
+    #[msg("Names are 3-20 lowercase letters, digits or underscores")]
+    InvalidName,
+    #[msg("Pass the profile's current name record to rename")]
+    NameRecordRequired,
+    #[msg("You don't hold this NFT")]
+    AvatarNotOwned,
+    #[msg("You're already in a clan")]
+    AlreadyInClan,
+    #[msg("Not a member of this clan")]
+    NotInClan,
+    #[msg("This clan is full")]
+    ClanFull,
+    #[msg("Pass the clan account of each player in a clan")]
+    ClanAccountRequired,
+    #[msg("Only the clan's founder can do this")]
+    NotClanFounder,
+    #[msg("A clan can't go to war with itself")]
+    SameClan,
+    #[msg("A war must start in the future and end after it starts")]
+    InvalidWarWindow,
+    #[msg("This war has already been accepted")]
+    WarAlreadyAccepted,
+    #[msg("This war has already started")]
+    WarStarted,
+    #[msg("This war was never accepted")]
+    WarNotAccepted,
+    #[msg("This war's window hasn't closed yet")]
+    WarNotOver,
+    #[msg("This clan's war roster is full")]
+    RosterFull,
+    #[msg("Only the winning roster can claim war spoils")]
+    NotEligibleForPrize,
+    #[msg("Unknown emote")]
+    InvalidEmote,
+    #[msg("One emote per move")]
+    EmoteRateLimited,
+    #[msg("Messages must be 1-140 bytes")]
+    InvalidMessage,
+    #[msg("Name 1-4 observers other than yourself, and seal one hash per observer")]
+    InvalidObservers,
+    #[msg("Boards can only be sealed for observers before the first shot")]
+    ObserverSealClosed,
+    #[msg("Board already sealed for the observers")]
+    BoardAlreadySealed,
+    #[msg("Ships may not touch, even diagonally, in this game")]
+    ShipsTouching,
+    #[msg("Depth charges need a layered board")]
+    SingleLayerBoard,
+    #[msg("Depth charge has already been used this game")]
+    DepthChargeAlreadyUsed,
+    #[msg("Only the fleet's last ship, its submarine, may lie below the surface")]
+    InvalidShipDepth,
+    #[msg("Move history is already enabled for this game")]
+    MoveHistoryEnabled,
+    #[msg("Move history can only be enabled before the first shot")]
+    MoveHistoryClosed,
+    #[msg("A single pending shot takes exactly one result")]
+    InvalidBatchSize,
+    #[msg("Vault holds less than the bonds it owes")]
+    VaultShortfall,
+    #[msg("Move history doesn't fit a whole number of moves")]
+    InvalidMoveHistory,
+    #[msg("ZK fleet and shot variants need a lamport game")]
+    ZkGameNeedsLamports,
+    #[msg("Commit your board (and prove your fleet) before cancelling")]
+    SetupNotDone,
+    #[msg("The reveal window has closed")]
+    RevealWindowClosed,
+    #[msg("Reveal your board first")]
+    BoardNotRevealed,
+    #[msg("Stakes are only refunded from a voided game")]
+    GameNotVoided,
+    #[msg("The house's board isn't the one its randomness dealt")]
+    HouseBoardNotDealt,
+    #[msg("This puzzle's board has been revealed")]
+    PuzzleRetired,
+    #[msg("This game doesn't count toward the war")]
+    NotAWarGame,
+    #[msg("The house's board must be revealed or forfeited first")]
+    HouseGameUnsettled,
+    #[msg("The founder can only leave once everyone else has")]
+    FounderLeavesLast,
+    #[msg("Each referrer stamped on the game needs their account to be paid")]
+    ReferrerAccountRequired,
 } 
\ No newline at end of file
@@ -2,9 +2,137 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Battleship } from "../target/types/battleship";
 import { expect } from "chai";
-import { PublicKey, Keypair } from "@solana/web3.js";
+import { PublicKey, Keypair, Ed25519Program } from "@solana/web3.js";
 import * as crypto from "crypto";
 
+// Variant rules for a standard game
+const CLASSIC_RULES = { clockSlots: 0, variants: 0, minReputation: 0, salvo: 0, boardSize: 10, vrfFirstTurn: 0, bestOf: 1 };
+// GameRules.variants bits
+const VARIANT_RADAR = 1 << 0;
+const VARIANT_AREABOMB = 1 << 1;
+const VARIANT_MINES = 1 << 2;
+const VARIANT_MOVING_SHIPS = 1 << 3;
+const VARIANT_LIMITED_AMMO = 1 << 4;
+const VARIANT_ZK_FLEET = 1 << 7;
+const VARIANT_ZK_SHOTS = 1 << 8;
+const VARIANT_SPECTATOR_CHAT = 1 << 9;
+const VARIANT_NO_TOUCH = 1 << 10;
+const VARIANT_LAYERS_SHIFT = 11;
+const VARIANT_HEX = 1 << 13;
+const VARIANT_CORRESPONDENCE_SHIFT = 14;
+// Slots in a day of correspondence deadlines
+const SLOTS_PER_DAY = 216000;
+// Committed cell value of a sea mine
+const MINE_CELL = 255;
+// An empty fleet selects the standard 5-ship fleet
+const STANDARD_FLEET = Buffer.from([]);
+// Lamports each player posts at creation/join, refunded on an honest reveal
+const HONESTY_BOND = 10000000;
+// All-zero turn commitment (and join nonce) keeps player1 moving first
+const NO_COIN_FLIP = Array(32).fill(0);
+
+// Game.state discriminants, matching the program's GameState enum
+const GameState = { waitingForOpponent: 0, inProgress: 1, awaitingReveal: 2, finished: 3, cancelled: 4, aborted: 5 };
+
+// Hit boards pack four 2-bit cells per byte (0 = empty, 1 = miss, 2 = hit)
+function hitCell(board: number[], index: number): number {
+  return (board[index >> 2] >> ((index & 3) * 2)) & 3;
+}
+
+// Each player's hit board and commitment live in a PlayerBoard PDA seeded by the game and their seat
+function playerBoardPda(programId: PublicKey, game: PublicKey, seat: number): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("player_board"), game.toBuffer(), Buffer.from([seat])], programId)[0];
+}
+
+// Board commitments are Merkle roots over per-cell leaves, zero-padded to a power of two
+function merkleLeafCount(cells: number): number {
+  return 1 << Math.ceil(Math.log2(cells));
+}
+
+function sha256(...parts: Buffer[]): Buffer {
+  return crypto.createHash("sha256").update(Buffer.concat(parts)).digest();
+}
+
+function indexBytes(index: number): Buffer {
+  const buffer = Buffer.alloc(2);
+  buffer.writeUInt16LE(index);
+  return buffer;
+}
+
+// Each cell's salt is derived from the board salt so one cell can be opened at a time
+function cellSalt(salt: Buffer, index: number): Buffer {
+  return sha256(salt, indexBytes(index));
+}
+
+function merkleLevels(board: number[], salt: Buffer): Buffer[][] {
+  let level: Buffer[] = [];
+  for (let i = 0; i < merkleLeafCount(board.length); i++) {
+    level.push(
+      i < board.length
+        ? sha256(indexBytes(i), Buffer.from([board[i]]), cellSalt(salt, i))
+        : Buffer.alloc(32)
+    );
+  }
+
+  const levels = [level];
+  while (level.length > 1) {
+    const next: Buffer[] = [];
+    for (let i = 0; i < level.length; i += 2) {
+      next.push(sha256(level[i], level[i + 1]));
+    }
+    levels.push(next);
+    level = next;
+  }
+  return levels;
+}
+
+// Helper function to compute commitment hash: the Merkle root bound to the game and its owner
+function computeCommitment(board: number[], salt: Buffer, game: PublicKey, player: PublicKey): Buffer {
+  const levels = merkleLevels(board, salt);
+  return sha256(levels[levels.length - 1][0], game.toBuffer(), player.toBuffer());
+}
+
+// Sibling path proving a single cell against the commitment
+function merkleProof(board: number[], salt: Buffer, index: number): number[][] {
+  const proof: number[][] = [];
+  let position = index;
+  for (const level of merkleLevels(board, salt).slice(0, -1)) {
+    proof.push(Array.from(level[position ^ 1]));
+    position >>= 1;
+  }
+  return proof;
+}
+
+async function fundedKeypair(): Promise<Keypair> {
+  const connection = anchor.getProvider().connection;
+  const keypair = Keypair.generate();
+  await connection.confirmTransaction(
+    await connection.requestAirdrop(keypair.publicKey, 2000000000)
+  );
+  return keypair;
+}
+
+// Every game creation checks the pause switch, so the config must exist first
+before(async () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+
+  if (!(await program.provider.connection.getAccountInfo(configPda))) {
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    await program.methods
+      .initializeConfig(250)
+      .accounts({ config: configPda, programData })
+      .rpc();
+  }
+});
+
 describe("battleship", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -41,22 +169,17 @@ describe("battleship", () => {
   ];
 
   // Set up boards
-  player1Ships.flat().forEach(pos => player1Board[pos] = 1);
-  player2Ships.flat().forEach(pos => player2Board[pos] = 1);
+  // Cells hold the ship id (1-based index into the fleet), 0 = water
+  player1Ships.forEach((ship, i) => ship.forEach(pos => player1Board[pos] = i + 1));
+  player2Ships.forEach((ship, i) => ship.forEach(pos => player2Board[pos] = i + 1));
 
   // Generate salts for commit-reveal
   const player1Salt = crypto.randomBytes(32);
   const player2Salt = crypto.randomBytes(32);
 
-  // Helper function to compute commitment hash
-  function computeCommitment(board: number[], salt: Buffer): Buffer {
-    const boardBuffer = Buffer.from(board);
-    const combined = Buffer.concat([boardBuffer, salt]);
-    return crypto.createHash('sha256').update(combined).digest();
-  }
-
-  const player1Commitment = computeCommitment(player1Board, player1Salt);
-  const player2Commitment = computeCommitment(player2Board, player2Salt);
+  // Commitments are bound to the game and player, so they're computed per test
+  let player1Commitment: Buffer;
+  let player2Commitment: Buffer;
 
   beforeEach(async () => {
     player1 = Keypair.generate();
@@ -72,14 +195,17 @@ describe("battleship", () => {
 
     // Derive PDA for game account
     [gamePda, gameBump] = PublicKey.findProgramAddressSync(
-      [Buffer.from("game"), player1.publicKey.toBuffer()],
+      [Buffer.from("game"), player1.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
       program.programId
     );
+
+    player1Commitment = computeCommitment(player1Board, player1Salt, gamePda, player1.publicKey);
+    player2Commitment = computeCommitment(player2Board, player2Salt, gamePda, player2.publicKey);
   });
 
   it("Initializes a new game", async () => {
     await program.methods
-      .initializeGame(Array.from(player1Commitment))
+      .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, true, PublicKey.default)
       .accounts({
         game: gamePda,
         player: player1.publicKey,
@@ -92,15 +218,16 @@ describe("battleship", () => {
     
     expect(gameAccount.player1.toString()).to.equal(player1.publicKey.toString());
     expect(gameAccount.player2.toString()).to.equal(PublicKey.default.toString());
-    expect(gameAccount.isInitialized).to.be.false;
-    expect(gameAccount.isGameOver).to.be.false;
+    expect(gameAccount.state).to.equal(GameState.waitingForOpponent);
     expect(gameAccount.turn).to.equal(1);
-    expect(Array.from(gameAccount.boardCommit1)).to.deep.equal(Array.from(player1Commitment));
+    expect(gameAccount.ranked).to.equal(1);
+    const board1 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 1));
+    expect(Array.from(board1.commitment)).to.deep.equal(Array.from(player1Commitment));
   });
 
   it("Second player joins the game", async () => {
     await program.methods
-      .joinGame(Array.from(player2Commitment))
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
       .accounts({
         game: gamePda,
         player: player2.publicKey,
@@ -111,8 +238,9 @@ describe("battleship", () => {
     const gameAccount = await program.account.game.fetch(gamePda);
     
     expect(gameAccount.player2.toString()).to.equal(player2.publicKey.toString());
-    expect(gameAccount.isInitialized).to.be.true;
-    expect(Array.from(gameAccount.boardCommit2)).to.deep.equal(Array.from(player2Commitment));
+    expect(gameAccount.state).to.equal(GameState.inProgress);
+    const board2 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 2));
+    expect(Array.from(board2.commitment)).to.deep.equal(Array.from(player2Commitment));
   });
 
   it("Prevents joining when game is full", async () => {
@@ -123,7 +251,7 @@ describe("battleship", () => {
 
     try {
       await program.methods
-        .joinGame(Array.from(player2Commitment))
+        .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
         .accounts({
           game: gamePda,
           player: player3.publicKey,
@@ -137,6 +265,30 @@ describe("battleship", () => {
     }
   });
 
+  it("Grows the game account to keep a move log", async () => {
+    await program.methods
+      .enableMoveHistory()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    const gameInfo = await anchor.getProvider().connection.getAccountInfo(gamePda);
+    expect(gameInfo.data.length).to.equal(program.account.game.size + 400 * 16); // MAX_MOVES records
+    expect((await program.account.game.fetch(gamePda)).moveHistory).to.equal(1);
+
+    try {
+      await program.methods
+        .enableMoveHistory()
+        .accounts({ game: gamePda, player: player2.publicKey })
+        .signers([player2])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Move history is already enabled");
+    }
+  });
+
   it("Player 1 fires first shot", async () => {
     const targetX = 5;
     const targetY = 0;
@@ -158,24 +310,57 @@ describe("battleship", () => {
 
   it("Player 2 reveals shot result (hit)", async () => {
     // Player 1 shot at (5,0) which hits Player 2's ship
-    const wasHit = player2Board[5] === 1; // Should be true
-
-    await program.methods
-      .revealShotResult(wasHit)
+    const shipId = player2Board[5]; // Carrier
+    const reveal = program.methods
+      .revealShotResult(shipId, Array.from(cellSalt(player2Salt, 5)), merkleProof(player2Board, player2Salt, 5))
       .accounts({
         game: gamePda,
         player: player2.publicKey,
       })
-      .signers([player2])
-      .rpc();
+      .signers([player2]);
+
+    // The outcome comes back as return data, so a simulation shows it without fetching the game
+    const simulation = await reveal.simulate();
+    const returnLog = simulation.raw.find((log) => log.startsWith(`Program return: ${program.programId}`));
+    const outcome = program.coder.types.decode("ShotOutcome", Buffer.from(returnLog.split(" ")[3], "base64"));
+    expect(outcome.x).to.equal(5);
+    expect(outcome.result).to.equal(2); // 2 = hit
+    expect(outcome.turn).to.equal(2);
+
+    await reveal.rpc();
 
     const gameAccount = await program.account.game.fetch(gamePda);
     
-    expect(gameAccount.pendingShot).to.be.null;
+    expect(gameAccount.hasPendingShot).to.equal(0);
     expect(gameAccount.pendingShotBy.toString()).to.equal(PublicKey.default.toString());
-    expect(gameAccount.boardHits2[5]).to.equal(2); // 2 = hit
+    const board2 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 2));
+    expect(hitCell(board2.hits, 5)).to.equal(2); // 2 = hit
     expect(gameAccount.hitsCount2).to.equal(1);
     expect(gameAccount.turn).to.equal(2); // Turn switches to player 2
+    
+    // The move log after the game state records the resolved shot for replays
+    const gameInfo = await anchor.getProvider().connection.getAccountInfo(gamePda);
+    const firstMove = program.coder.types.decode("MoveRecord", gameInfo.data.subarray(program.account.game.size));
+    expect(gameAccount.moveCount).to.equal(1);
+    expect(firstMove.x).to.equal(5);
+    expect(firstMove.y).to.equal(0);
+    expect(firstMove.shooter).to.equal(1);
+    expect(firstMove.result).to.equal(2);
+  });
+
+  it("Summarizes the game through a read-only view", async () => {
+    const summary = await program.methods
+      .getGameSummary()
+      .accounts({ game: gamePda })
+      .view();
+
+    expect(summary.turn).to.equal(2);
+    expect(summary.playerToMove).to.equal(2);
+    expect(summary.hitsCount2).to.equal(1);
+    expect(summary.moveCount).to.equal(1);
+    expect(summary.lastShotX).to.equal(5);
+    expect(summary.lastShotBy).to.equal(1);
+    expect(summary.lastShotResult).to.equal(2);
   });
 
   it("Player 2 fires shot", async () => {
@@ -199,10 +384,10 @@ describe("battleship", () => {
 
   it("Player 1 reveals shot result (hit)", async () => {
     // Player 2 shot at (0,0) which hits Player 1's ship
-    const wasHit = player1Board[0] === 1; // Should be true
+    const shipId = player1Board[0]; // Carrier
 
     await program.methods
-      .revealShotResult(wasHit)
+      .revealShotResult(shipId, Array.from(cellSalt(player1Salt, 0)), merkleProof(player1Board, player1Salt, 0))
       .accounts({
         game: gamePda,
         player: player1.publicKey,
@@ -212,7 +397,8 @@ describe("battleship", () => {
 
     const gameAccount = await program.account.game.fetch(gamePda);
     
-    expect(gameAccount.boardHits1[0]).to.equal(2); // 2 = hit
+    const board1 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 1));
+    expect(hitCell(board1.hits, 0)).to.equal(2); // 2 = hit
     expect(gameAccount.hitsCount1).to.equal(1);
     expect(gameAccount.turn).to.equal(1); // Turn switches back to player 1
   });
@@ -234,6 +420,25 @@ describe("battleship", () => {
     }
   });
 
+  it("Rejects shots from a wallet that isn't in the game", async () => {
+    const stranger = Keypair.generate();
+
+    try {
+      await program.methods
+        .fireShot(1, 1)
+        .accounts({
+          game: gamePda,
+          player: stranger.publicKey,
+        })
+        .signers([stranger])
+        .rpc();
+      
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("You are not a player in this game");
+    }
+  });
+
   it("Prevents firing at same coordinate twice", async () => {
     try {
       await program.methods
@@ -279,9 +484,9 @@ describe("battleship", () => {
         .rpc();
 
       // Player 2 reveals result
-      const wasHit = player2Board[x + 10 * y] === 1;
+      const index = x + 10 * y;
       await program.methods
-        .revealShotResult(wasHit)
+        .revealShotResult(player2Board[index], Array.from(cellSalt(player2Salt, index)), merkleProof(player2Board, player2Salt, index))
         .accounts({
           game: gamePda,
           player: player2.publicKey,
@@ -292,7 +497,7 @@ describe("battleship", () => {
       gameAccount = await program.account.game.fetch(gamePda);
       
       // Check if game is over
-      if (gameAccount.isGameOver) {
+      if (gameAccount.state === GameState.awaitingReveal) {
         expect(gameAccount.winner).to.equal(1); // Player 1 wins
         expect(gameAccount.hitsCount2).to.equal(17); // All ships sunk
         break;
@@ -302,7 +507,7 @@ describe("battleship", () => {
 
   it("Player 1 reveals board after game completion", async () => {
     await program.methods
-      .revealBoardPlayer1(player1Board, Array.from(player1Salt))
+      .revealBoardPlayer1(Buffer.from(player1Board), Array.from(player1Salt))
       .accounts({
         game: gamePda,
         player: player1.publicKey,
@@ -311,12 +516,13 @@ describe("battleship", () => {
       .rpc();
 
     const gameAccount = await program.account.game.fetch(gamePda);
-    expect(gameAccount.player1Revealed).to.be.true;
+    expect(gameAccount.player1Revealed).to.equal(1);
+    expect(gameAccount.bond1Settled).to.equal(1);
   });
 
   it("Player 2 reveals board after game completion", async () => {
     await program.methods
-      .revealBoardPlayer2(player2Board, Array.from(player2Salt))
+      .revealBoardPlayer2(Buffer.from(player2Board), Array.from(player2Salt))
       .accounts({
         game: gamePda,
         player: player2.publicKey,
@@ -325,7 +531,169 @@ describe("battleship", () => {
       .rpc();
 
     const gameAccount = await program.account.game.fetch(gamePda);
-    expect(gameAccount.player2Revealed).to.be.true;
+    expect(gameAccount.player2Revealed).to.equal(1);
+    expect(gameAccount.bond2Settled).to.equal(1);
+    expect(gameAccount.state).to.equal(GameState.finished);
+  });
+
+  it("Records the finished game in both player profiles", async () => {
+    const profiles = [player1, player2].map((player) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("profile"), player.publicKey.toBuffer()],
+        program.programId
+      )[0]
+    );
+
+    for (const player of [player1, player2]) {
+      await program.methods
+        .initializeProfile()
+        .accounts({ player: player.publicKey })
+        .signers([player])
+        .rpc();
+    }
+
+    const [leaderboardPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("leaderboard")],
+      program.programId
+    );
+    await program.methods
+      .initializeLeaderboard()
+      .accounts({ leaderboard: leaderboardPda })
+      .rpc();
+
+    await program.methods
+      .recordGameResult()
+      .accounts({
+        game: gamePda,
+        player1Profile: profiles[0],
+        player2Profile: profiles[1],
+        leaderboard: leaderboardPda,
+      })
+      .rpc();
+
+    const [profile1, profile2] = await Promise.all(
+      profiles.map((profile) => program.account.playerProfile.fetch(profile))
+    );
+    expect(profile1.gamesPlayed).to.equal(1);
+    expect(profile2.gamesPlayed).to.equal(1);
+    expect(profile1.wins + profile2.wins).to.equal(1);
+    expect(profile1.shotsFired).to.be.greaterThan(0);
+    // Equal 1200 ratings move 16 points each way under K = 32
+    expect(profile1.rating + profile2.rating).to.equal(2400);
+    expect(Math.max(profile1.rating, profile2.rating)).to.equal(1216);
+
+    expect(Math.max(profile1.seasonRating, profile2.seasonRating)).to.equal(1216);
+    expect(profile1.seasonWins + profile2.seasonWins).to.equal(1);
+
+    // The winner unlocks their first-win achievement; the loser unlocks nothing
+    const [winnerProfile, loserProfile] = profile1.wins === 1 ? [profile1, profile2] : [profile2, profile1];
+    expect(winnerProfile.achievements & 1).to.equal(1);
+    expect(winnerProfile.winStreak).to.equal(1);
+    expect(winnerProfile.bestWinStreak).to.equal(1);
+    expect(loserProfile.lossStreak).to.equal(1);
+    expect(loserProfile.achievements).to.equal(0);
+
+    const leaderboard = await program.account.leaderboard.fetch(leaderboardPda);
+    expect(leaderboard.entries[0].rating).to.equal(1216);
+  });
+
+  it("Archives the standings when the season rolls over", async () => {
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const [leaderboardPda] = PublicKey.findProgramAddressSync([Buffer.from("leaderboard")], program.programId);
+    const { season } = await program.account.config.fetch(configPda);
+    const [seasonPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("season"), new anchor.BN(season).toArrayLike(Buffer, "le", 4)],
+      program.programId
+    );
+
+    await program.methods
+      .rolloverSeason()
+      .accounts({ config: configPda, season: seasonPda, leaderboard: leaderboardPda })
+      .rpc();
+
+    const archive = await program.account.season.fetch(seasonPda);
+    expect(archive.season).to.equal(season);
+    expect(archive.standings[0].rating).to.equal(1216);
+    expect((await program.account.leaderboard.fetch(leaderboardPda)).entries).to.be.empty;
+    expect((await program.account.config.fetch(configPda)).season).to.equal(season + 1);
+  });
+
+  it("Only records an offense for a cheater or a missed reveal", async () => {
+    const [registryPda] = PublicKey.findProgramAddressSync([Buffer.from("cheater_registry")], program.programId);
+    await program.methods
+      .initializeCheaterRegistry()
+      .accounts({ registry: registryPda })
+      .rpc();
+
+    try {
+      // Both boards were revealed and proven honest
+      await program.methods
+        .recordOffense()
+        .accounts({ game: gamePda, registry: registryPda })
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Neither player cheated or skipped their reveal");
+    }
+
+    expect((await program.account.cheaterRegistry.fetch(registryPda)).entries).to.be.empty;
+  });
+
+  it("Only the winner can mint the trophy NFT", async () => {
+    const gameAccount = await program.account.game.fetch(gamePda);
+    const loser = gameAccount.winner === 1 ? player2 : player1;
+    const trophyMint = Keypair.generate();
+
+    try {
+      await program.methods
+        .mintTrophy()
+        .accounts({
+          game: gamePda,
+          trophyMint: trophyMint.publicKey,
+          winner: loser.publicKey,
+        })
+        .signers([trophyMint, loser])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the winner can claim");
+    }
+  });
+
+  it("Only tops up a rematch of a wagered game", async () => {
+    try {
+      await program.methods
+        .proposeRematch(Array.from(crypto.randomBytes(32)), false, new anchor.BN(1000000))
+        .accounts({ game: gamePda, player: player1.publicKey })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("This game has no wager");
+    }
+  });
+
+  it("Starts a rematch in place with player 2 moving first", async () => {
+    await program.methods
+      .proposeRematch(Array.from(crypto.randomBytes(32)), false, new anchor.BN(0))
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .acceptRematch(Array.from(crypto.randomBytes(32)))
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.state).to.equal(GameState.inProgress);
+    expect(gameAccount.turn).to.equal(2);
+    expect(gameAccount.hitsCount1).to.equal(0);
+    expect(gameAccount.hitsCount2).to.equal(0);
   });
 
   it("Prevents revealing with wrong commitment", async () => {
@@ -336,13 +704,13 @@ describe("battleship", () => {
     );
 
     const [wrongGamePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("game"), wrongPlayer.publicKey.toBuffer()],
+      [Buffer.from("game"), wrongPlayer.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
       program.programId
     );
 
     // Initialize with correct commitment
     await program.methods
-      .initializeGame(Array.from(player1Commitment))
+      .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
       .accounts({
         game: wrongGamePda,
         player: wrongPlayer.publicKey,
@@ -358,7 +726,7 @@ describe("battleship", () => {
     );
 
     await program.methods
-      .joinGame(Array.from(player2Commitment))
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
       .accounts({
         game: wrongGamePda,
         player: player2Wrong.publicKey,
@@ -372,7 +740,7 @@ describe("battleship", () => {
     try {
       const wrongSalt = crypto.randomBytes(32);
       await program.methods
-        .revealBoardPlayer1(player1Board, Array.from(wrongSalt))
+        .revealBoardPlayer1(Buffer.from(player1Board), Array.from(wrongSalt))
         .accounts({
           game: wrongGamePda,
           player: wrongPlayer.publicKey,
@@ -392,14 +760,13 @@ describe("battleship", () => {
     // Create board with wrong number of ships (18 instead of 17)
     const invalidBoard = new Array(100).fill(0);
     invalidBoard[0] = 1; invalidBoard[1] = 1; invalidBoard[2] = 1; invalidBoard[3] = 1; invalidBoard[4] = 1; // 5-ship
-    invalidBoard[10] = 1; invalidBoard[11] = 1; invalidBoard[12] = 1; invalidBoard[13] = 1; // 4-ship
-    invalidBoard[20] = 1; invalidBoard[21] = 1; invalidBoard[22] = 1; // 3-ship
-    invalidBoard[30] = 1; invalidBoard[31] = 1; invalidBoard[32] = 1; // 3-ship
-    invalidBoard[40] = 1; invalidBoard[41] = 1; // 2-ship
-    invalidBoard[50] = 1; // Extra ship square (18 total)
+    invalidBoard[10] = 2; invalidBoard[11] = 2; invalidBoard[12] = 2; invalidBoard[13] = 2; // 4-ship
+    invalidBoard[20] = 3; invalidBoard[21] = 3; invalidBoard[22] = 3; // 3-ship
+    invalidBoard[30] = 4; invalidBoard[31] = 4; invalidBoard[32] = 4; // 3-ship
+    invalidBoard[40] = 5; invalidBoard[41] = 5; // 2-ship
+    invalidBoard[50] = 5; // Extra ship square (18 total)
     
     const invalidSalt = crypto.randomBytes(32);
-    const invalidCommitment = computeCommitment(invalidBoard, invalidSalt);
 
     const testPlayer = Keypair.generate();
     await anchor.getProvider().connection.confirmTransaction(
@@ -407,13 +774,14 @@ describe("battleship", () => {
     );
 
     const [testGamePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("game"), testPlayer.publicKey.toBuffer()],
+      [Buffer.from("game"), testPlayer.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
       program.programId
     );
+    const invalidCommitment = computeCommitment(invalidBoard, invalidSalt, testGamePda, testPlayer.publicKey);
 
     // This should work - commitment doesn't verify fleet size
     await program.methods
-      .initializeGame(Array.from(invalidCommitment))
+      .initializeGame(new anchor.BN(0), Array.from(invalidCommitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
       .accounts({
         game: testGamePda,
         player: testPlayer.publicKey,
@@ -438,14 +806,14 @@ describe("battleship edge cases", () => {
     );
 
     const [gamePda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("game"), player.publicKey.toBuffer()],
+      [Buffer.from("game"), player.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
       program.programId
     );
 
     const commitment = crypto.randomBytes(32);
     
     await program.methods
-      .initializeGame(Array.from(commitment))
+      .initializeGame(new anchor.BN(0), Array.from(commitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
       .accounts({
         game: gamePda,
         player: player.publicKey,
@@ -460,7 +828,7 @@ describe("battleship edge cases", () => {
     );
 
     await program.methods
-      .joinGame(Array.from(commitment))
+      .joinGame(Array.from(commitment), NO_COIN_FLIP)
       .accounts({
         game: gamePda,
         player: player2.publicKey,
@@ -483,4 +851,3551 @@ describe("battleship edge cases", () => {
       expect(error.message).to.include("Invalid coordinate");
     }
   });
-}); 
\ No newline at end of file
+
+  it("Allows one player to host several games at once", async () => {
+    const player = Keypair.generate();
+    await anchor.getProvider().connection.confirmTransaction(
+      await anchor.getProvider().connection.requestAirdrop(player.publicKey, 2000000000)
+    );
+
+    for (const id of [1, 2]) {
+      const gameId = new anchor.BN(id);
+      const [gamePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("game"), player.publicKey.toBuffer(), gameId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .initializeGame(gameId, Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({
+          game: gamePda,
+          player: player.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([player])
+        .rpc();
+
+      const gameAccount = await program.account.game.fetch(gamePda);
+      expect(gameAccount.gameId.toNumber()).to.equal(id);
+    }
+  });
+
+  it("Rejects strangers from invite-only games", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, friend, stranger] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, stranger]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), friend.publicKey, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    try {
+      await program.methods
+        .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+        .accounts({ game: gamePda, player: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("This game is invite-only");
+    }
+  });
+
+  it("Lets an unfunded authority host through the CPI entry point while a payer covers rent", async () => {
+    const authority = Keypair.generate();
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), authority.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGameCpi(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, authority: authority.publicKey, payer: anchor.getProvider().publicKey })
+      .signers([authority])
+      .rpc();
+
+    const game = await program.account.game.fetch(gamePda);
+    expect(game.player1.toString()).to.equal(authority.publicKey.toString());
+
+    try {
+      await program.methods
+        .joinGameCpi(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+        .accounts({ game: gamePda, authority: authority.publicKey, payer: anchor.getProvider().publicKey })
+        .signers([authority])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Cannot play against yourself");
+    }
+  });
+
+  it("Turns away joiners below the game's minimum reputation", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, newcomer] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, newcomer]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [newcomerProfile] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), newcomer.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, minReputation: 5 }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .initializeProfile()
+      .accounts({ player: newcomer.publicKey })
+      .signers([newcomer])
+      .rpc();
+
+    try {
+      // A fresh profile has no finished games or reveals to its name
+      await program.methods
+        .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+        .accounts({ game: gamePda, player: newcomer.publicKey, playerProfile: newcomerProfile })
+        .signers([newcomer])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Your reputation is below this game's minimum");
+    }
+  });
+
+  it("Rejects a shot result that contradicts the committed board", async () => {
+    const connection = anchor.getProvider().connection;
+    const [attacker, defender] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [attacker, defender]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const defenderBoard = new Array(100).fill(0);
+    [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]].forEach((ship, id) =>
+      ship.forEach((i) => (defenderBoard[i] = id + 1))
+    );
+    const defenderSalt = crypto.randomBytes(32);
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), attacker.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(computeCommitment(defenderBoard, defenderSalt, gamePda, defender.publicKey)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    await program.methods
+      .fireShot(0, 0)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    try {
+      // (0,0) holds a ship, so claiming a miss cannot be proven
+      await program.methods
+        .revealShotResult(0, Array.from(cellSalt(defenderSalt, 0)), merkleProof(defenderBoard, defenderSalt, 0))
+        .accounts({ game: gamePda, player: defender.publicKey })
+        .signers([defender])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Merkle proof does not match the committed board");
+    }
+  });
+
+  it("Answers a shot by opening one cell against uploaded cell hashes", async () => {
+    const connection = anchor.getProvider().connection;
+    const [attacker, defender] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [attacker, defender]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const defenderBoard = new Array(100).fill(0);
+    [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]].forEach((ship, id) =>
+      ship.forEach((i) => (defenderBoard[i] = id + 1))
+    );
+    const defenderSalt = crypto.randomBytes(32);
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), attacker.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [boardCommitmentsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("board_commitments"), gamePda.toBuffer(), defender.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(computeCommitment(defenderBoard, defenderSalt, gamePda, defender.publicKey)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    await program.methods
+      .initBoardCommitments()
+      .accounts({ game: gamePda, boardCommitments: boardCommitmentsPda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    // 100 leaves don't fit in one transaction
+    const leaves = merkleLevels(defenderBoard, defenderSalt)[0].slice(0, 100);
+    for (let offset = 0; offset < leaves.length; offset += 25) {
+      await program.methods
+        .uploadCellHashes(offset, leaves.slice(offset, offset + 25).map((leaf) => Array.from(leaf)))
+        .accounts({ game: gamePda, boardCommitments: boardCommitmentsPda, player: defender.publicKey })
+        .signers([defender])
+        .rpc();
+    }
+
+    await program.methods
+      .sealBoardCommitments()
+      .accounts({ game: gamePda, boardCommitments: boardCommitmentsPda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    await program.methods
+      .fireShot(0, 0)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    await program.methods
+      .revealShotResultStored(1, Array.from(cellSalt(defenderSalt, 0)))
+      .accounts({ game: gamePda, boardCommitments: boardCommitmentsPda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.hitsCount2).to.equal(1);
+  });
+
+  it("Rejects a commitment copied from another game", async () => {
+    const connection = anchor.getProvider().connection;
+    const [attacker, defender] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [attacker, defender]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const defenderBoard = new Array(100).fill(0);
+    defenderBoard[0] = 1;
+    const defenderSalt = crypto.randomBytes(32);
+
+    const [gamePda, otherGamePda] = [0, 1].map((id) => PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), attacker.publicKey.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0]);
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    // Bound to a different game, so none of its cells can be proven here
+    await program.methods
+      .joinGame(Array.from(computeCommitment(defenderBoard, defenderSalt, otherGamePda, defender.publicKey)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    await program.methods
+      .fireShot(0, 0)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    try {
+      await program.methods
+        .revealShotResult(1, Array.from(cellSalt(defenderSalt, 0)), merkleProof(defenderBoard, defenderSalt, 0))
+        .accounts({ game: gamePda, player: defender.publicKey })
+        .signers([defender])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Merkle proof does not match the committed board");
+    }
+  });
+
+  it("Salvo games take one shot per ship afloat", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, salvo: 1 }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    try {
+      await program.methods
+        .fireSalvo([[0, 0], [1, 1], [2, 2], [3, 3], [4, 4], [5, 5]])
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("A salvo must have between one shot and one shot per ship afloat");
+    }
+
+    await program.methods
+      .fireSalvo([[0, 0], [1, 1], [2, 2], [3, 3], [4, 4]])
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.pendingSalvoLen).to.equal(5);
+  });
+
+  it("Radar scans once per game and passes the turn", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_RADAR }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    await program.methods
+      .radarScan(4, 4)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    // Nothing can be fired until the defender answers
+    try {
+      await program.methods
+        .fireShot(0, 0)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("ScanPending");
+    }
+
+    await program.methods
+      .revealRadarScan(3)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.radarScan1).to.deep.equal([4, 4, 3]);
+    expect(gameAccount.radarState1).to.equal(2);
+    expect(gameAccount.hitsCount2).to.equal(0); // Scanned cells aren't marked
+    expect(gameAccount.turn).to.equal(2);
+  });
+
+  it("Areabombs queue a 2x2 area for a single reveal", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_AREABOMB }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    // The whole 2x2 area has to fit on the board
+    try {
+      await program.methods
+        .fireBomb(9, 9)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Invalid coordinate");
+    }
+
+    await program.methods
+      .fireBomb(3, 3)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.pendingSalvoLen).to.equal(4);
+    expect(gameAccount.bombUsed1).to.equal(1);
+  });
+
+  it("Drops a depth charge down every layer of a layered board", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    // Three 10x10 layers don't fit in the cell budget
+    try {
+      await program.methods
+        .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: 2 << VARIANT_LAYERS_SHIFT }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Board size must be 8, 10 or 12");
+    }
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: 1 << VARIANT_LAYERS_SHIFT }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    // Surface (3, 4) sits over row 14 of the submarine layer
+    await program.methods
+      .fireDepthCharge(3, 4)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.pendingSalvoLen).to.equal(2);
+    expect(gameAccount.pendingSalvo.slice(0, 2)).to.deep.equal([[3, 4], [3, 14]]);
+    expect(gameAccount.depthChargeUsed1).to.equal(1);
+  });
+
+  it("Striking a mine costs the attacker a cell of their own board", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    // The guest hides a mine in the corner
+    const guestBoard = new Array(100).fill(0);
+    guestBoard[99] = MINE_CELL;
+    const guestSalt = crypto.randomBytes(32);
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_MINES }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(computeCommitment(guestBoard, guestSalt, gamePda, guest.publicKey)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    await program.methods
+      .fireShot(9, 9)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .revealShotResult(MINE_CELL, Array.from(cellSalt(guestSalt, 99)), merkleProof(guestBoard, guestSalt, 99))
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    // The host now owes a reveal on one of their own cells before the guest moves
+    const gameAccount = await program.account.game.fetch(gamePda);
+    const board2 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 2));
+    expect(hitCell(board2.hits, 99)).to.equal(3); // 3 = mine
+    expect(gameAccount.minePenalty).to.equal(2);
+    expect(gameAccount.hasPendingShot).to.equal(1);
+    expect(gameAccount.pendingShotBy.equals(guest.publicKey)).to.be.true;
+    expect(gameAccount.turn).to.equal(1);
+  });
+
+  it("Moves an undamaged ship one cell instead of firing", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const hostBoard = new Array(100).fill(0);
+    [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]]
+      .forEach((ship, i) => ship.forEach(pos => hostBoard[pos] = i + 1));
+    const hostSalt = crypto.randomBytes(32);
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(computeCommitment(hostBoard, hostSalt, gamePda, host.publicKey)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_MOVING_SHIPS }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    // Slide the destroyer right: cell 40 empties, then cell 42 fills, each proven in turn
+    const vacated = { x: 0, y: 4, cellSalt: Array.from(cellSalt(hostSalt, 40)), proof: merkleProof(hostBoard, hostSalt, 40) };
+    const midBoard = [...hostBoard];
+    midBoard[40] = 0;
+    const occupied = { x: 2, y: 4, cellSalt: Array.from(cellSalt(hostSalt, 42)), proof: merkleProof(midBoard, hostSalt, 42) };
+    const movedBoard = [...midBoard];
+    movedBoard[42] = 5;
+    const newCommitment = computeCommitment(movedBoard, hostSalt, gamePda, host.publicKey);
+
+    await program.methods
+      .moveShip(5, vacated, occupied, Array.from(newCommitment))
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    const board1 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 1));
+    expect(Buffer.from(board1.commitment).equals(newCommitment)).to.be.true;
+    expect(gameAccount.turn).to.equal(2);
+  });
+
+  it("Holds a fleet-proof game back until both fleets are proven", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_ZK_FLEET }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.state).to.equal(GameState.waitingForOpponent);
+    expect(gameAccount.player2.toBase58()).to.equal(guest.publicKey.toBase58());
+
+    try {
+      await program.methods
+        .fireShot(0, 0)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Game is not ready");
+    }
+  });
+
+  it("Requires proven fleets for a game of proven shots", async () => {
+    const host = Keypair.generate();
+    await anchor.getProvider().connection.confirmTransaction(
+      await anchor.getProvider().connection.requestAirdrop(host.publicKey, 2000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_ZK_SHOTS }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("These variants can't be combined");
+    }
+  });
+
+  it("Counts shots against the ammo limit", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_LIMITED_AMMO }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    await program.methods
+      .fireShot(0, 0)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.shotsFired1).to.equal(1);
+    expect(gameAccount.shotsFired2).to.equal(0);
+  });
+
+
+  it("Stores a custom fleet and rejects one that doesn't fit", async () => {
+    const connection = anchor.getProvider().connection;
+    const host = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(host.publicKey, 2000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, boardSize: 8 }, Buffer.from([9]), NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Fleet must have at most 8 ships that fit on the board");
+    }
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, Buffer.from([4, 3, 2, 2]), NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(Array.from(gameAccount.fleet).slice(0, gameAccount.fleetLen)).to.deep.equal([4, 3, 2, 2]);
+  });
+
+
+  it("Decides the first turn by a commit-reveal coin flip", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const hostNonce = crypto.randomBytes(32);
+    const guestNonce = crypto.randomBytes(32);
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, Array.from(sha256(hostNonce)), false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), Array.from(guestNonce))
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    try {
+      await program.methods
+        .fireShot(0, 0)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("First turn has not been decided");
+    }
+
+    await program.methods
+      .revealTurnNonce(Array.from(hostNonce))
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.turnPending).to.equal(0);
+    expect(gameAccount.turn).to.equal((sha256(hostNonce, guestNonce)[0] & 1) + 1);
+  });
+
+
+  it("Rejects combining a nonce coin flip with VRF first-turn selection", async () => {
+    const connection = anchor.getProvider().connection;
+    const host = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(host.publicKey, 2000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, vrfFirstTurn: 1 }, STANDARD_FLEET, Array.from(sha256(crypto.randomBytes(32))), false, PublicKey.default)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Choose either a nonce coin flip or VRF for the first turn");
+    }
+  });
+
+
+  it("Only allows best-of-1, 3 or 5 series", async () => {
+    const connection = anchor.getProvider().connection;
+    const host = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(host.publicKey, 2000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, bestOf: 2 }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Series length must be 1, 3 or 5");
+    }
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, bestOf: 3 }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.rules.bestOf).to.equal(3);
+  });
+
+  it("Defender reveals and fires back in one transaction", async () => {
+    const connection = anchor.getProvider().connection;
+    const [attacker, defender] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [attacker, defender]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const defenderBoard = new Array(100).fill(0);
+    [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]].forEach((ship, id) =>
+      ship.forEach((i) => (defenderBoard[i] = id + 1))
+    );
+    const defenderSalt = crypto.randomBytes(32);
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), attacker.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(computeCommitment(defenderBoard, defenderSalt, gamePda, defender.publicKey)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    await program.methods
+      .fireShot(0, 0)
+      .accounts({ game: gamePda, player: attacker.publicKey })
+      .signers([attacker])
+      .rpc();
+
+    await program.methods
+      .revealAndFire(defenderBoard[0], Array.from(cellSalt(defenderSalt, 0)), merkleProof(defenderBoard, defenderSalt, 0), 9, 9)
+      .accounts({ game: gamePda, player: defender.publicKey })
+      .signers([defender])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    const board2 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 2));
+    expect(hitCell(board2.hits, 0)).to.equal(2);
+    expect(gameAccount.pendingShot).to.deep.equal([9, 9]);
+    expect(gameAccount.pendingShotBy.toString()).to.equal(defender.publicKey.toString());
+  });
+
+  it("Lets a registered session key fire for its wallet", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest, session] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest, session]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [profilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), host.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    await program.methods
+      .initializeProfile()
+      .accounts({ player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .registerSessionKey(session.publicKey, new anchor.BN(1000))
+      .accounts({ profile: profilePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .fireShot(3, 3)
+      .accounts({ game: gamePda, profile: profilePda, player: session.publicKey })
+      .signers([session])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.pendingShot).to.deep.equal([3, 3]);
+    expect(gameAccount.pendingShotBy.toString()).to.equal(host.publicKey.toString());
+  });
+
+  it("Registers unique display names and frees the old one on rename", async () => {
+    const connection = anchor.getProvider().connection;
+    const [alice, bob] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [alice, bob]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+      await program.methods
+        .initializeProfile()
+        .accounts({ player: keypair.publicKey })
+        .signers([keypair])
+        .rpc();
+    }
+    const nameRecord = (name: string) =>
+      PublicKey.findProgramAddressSync([Buffer.from("name"), Buffer.from(name)], program.programId)[0];
+    const first = "admiral_" + crypto.randomBytes(4).toString("hex");
+    const second = "captain_" + crypto.randomBytes(4).toString("hex");
+
+    try {
+      await program.methods
+        .registerName("Admiral!")
+        .accounts({ player: alice.publicKey })
+        .signers([alice])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Names are 3-20 lowercase letters, digits or underscores");
+    }
+
+    await program.methods
+      .registerName(first)
+      .accounts({ player: alice.publicKey, oldNameRecord: null })
+      .signers([alice])
+      .rpc();
+
+    try {
+      await program.methods
+        .registerName(first)
+        .accounts({ player: bob.publicKey, oldNameRecord: null })
+        .signers([bob])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("already in use");
+    }
+
+    await program.methods
+      .registerName(second)
+      .accounts({ player: alice.publicKey, oldNameRecord: nameRecord(first) })
+      .signers([alice])
+      .rpc();
+    expect(await connection.getAccountInfo(nameRecord(first))).to.be.null;
+
+    await program.methods
+      .registerName(first)
+      .accounts({ player: bob.publicKey, oldNameRecord: null })
+      .signers([bob])
+      .rpc();
+    const [bobProfile] = PublicKey.findProgramAddressSync([Buffer.from("profile"), bob.publicKey.toBuffer()], program.programId);
+    expect((await program.account.playerProfile.fetch(bobProfile)).name).to.equal(first);
+  });
+
+  it("Refuses to link a fungible token as an avatar", async () => {
+    const provider = anchor.getProvider();
+    const player = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(player.publicKey, 2000000000)
+    );
+    await program.methods
+      .initializeProfile()
+      .accounts({ player: player.publicKey })
+      .signers([player])
+      .rpc();
+
+    // A wrapped SOL token account, built by hand: create it, then InitializeAccount3 (tag 18)
+    const tokenProgram = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+    const wrappedSol = new PublicKey("So11111111111111111111111111111111111111112");
+    const tokenAccount = Keypair.generate();
+    const tx = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.createAccount({
+        fromPubkey: player.publicKey,
+        newAccountPubkey: tokenAccount.publicKey,
+        lamports: await provider.connection.getMinimumBalanceForRentExemption(165),
+        space: 165,
+        programId: tokenProgram,
+      }),
+      new anchor.web3.TransactionInstruction({
+        programId: tokenProgram,
+        keys: [
+          { pubkey: tokenAccount.publicKey, isSigner: false, isWritable: true },
+          { pubkey: wrappedSol, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.concat([Buffer.from([18]), player.publicKey.toBuffer()]),
+      })
+    );
+    await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [player, tokenAccount]);
+
+    try {
+      await program.methods
+        .linkAvatar()
+        .accounts({ nftMint: wrappedSol, playerNftAccount: tokenAccount.publicKey, player: player.publicKey })
+        .signers([player])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Staked token must be an NFT");
+    }
+  });
+
+  it("Founds a clan, takes in members and closes once everyone leaves", async () => {
+    const connection = anchor.getProvider().connection;
+    const [founder, recruit] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [founder, recruit]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+      await program.methods
+        .initializeProfile()
+        .accounts({ player: keypair.publicKey })
+        .signers([keypair])
+        .rpc();
+    }
+    const name = "fleet_" + crypto.randomBytes(4).toString("hex");
+    const [clanPda] = PublicKey.findProgramAddressSync([Buffer.from("clan"), Buffer.from(name)], program.programId);
+
+    await program.methods
+      .createClan(name)
+      .accounts({ clan: clanPda, player: founder.publicKey })
+      .signers([founder])
+      .rpc();
+    await program.methods
+      .joinClan()
+      .accounts({ clan: clanPda, player: recruit.publicKey })
+      .signers([recruit])
+      .rpc();
+
+    const clan = await program.account.clan.fetch(clanPda);
+    expect(clan.members).to.equal(2);
+    expect(clan.founder.toString()).to.equal(founder.publicKey.toString());
+
+    try {
+      const otherName = "other_" + crypto.randomBytes(4).toString("hex");
+      await program.methods
+        .createClan(otherName)
+        .accounts({ player: recruit.publicKey })
+        .signers([recruit])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("You're already in a clan");
+    }
+
+    try {
+      await program.methods
+        .leaveClan()
+        .accounts({ clan: clanPda, player: founder.publicKey })
+        .signers([founder])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("The founder can only leave once everyone else has");
+    }
+
+    for (const member of [recruit, founder]) {
+      await program.methods
+        .leaveClan()
+        .accounts({ clan: clanPda, player: member.publicKey })
+        .signers([member])
+        .rpc();
+    }
+    expect(await connection.getAccountInfo(clanPda)).to.be.null;
+  });
+
+  it("Declares clan wars only over a window in the future", async () => {
+    const connection = anchor.getProvider().connection;
+    const founders = [Keypair.generate(), Keypair.generate()];
+    const clans = [];
+    for (const founder of founders) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(founder.publicKey, 2000000000)
+      );
+      await program.methods
+        .initializeProfile()
+        .accounts({ player: founder.publicKey })
+        .signers([founder])
+        .rpc();
+      const name = "war_" + crypto.randomBytes(4).toString("hex");
+      const [clanPda] = PublicKey.findProgramAddressSync([Buffer.from("clan"), Buffer.from(name)], program.programId);
+      await program.methods
+        .createClan(name)
+        .accounts({ clan: clanPda, player: founder.publicKey })
+        .signers([founder])
+        .rpc();
+      clans.push(clanPda);
+    }
+
+    const slot = await connection.getSlot();
+    try {
+      await program.methods
+        .declareClanWar(new anchor.BN(0), new anchor.BN(0), new anchor.BN(slot), new anchor.BN(slot + 1000))
+        .accounts({ clanA: clans[0], clanB: clans[1], founder: founders[0].publicKey })
+        .signers([founders[0]])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("A war must start in the future and end after it starts");
+    }
+
+    const [warPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("clan_war"), clans[0].toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .declareClanWar(new anchor.BN(0), new anchor.BN(0), new anchor.BN(slot + 1000), new anchor.BN(slot + 2000))
+      .accounts({ clanA: clans[0], clanB: clans[1], founder: founders[0].publicKey })
+      .signers([founders[0]])
+      .rpc();
+    let war = await program.account.clanWar.fetch(warPda);
+    expect(war.founderB.toString()).to.equal(founders[1].publicKey.toString());
+    expect(war.state).to.equal(0);
+
+    // Only a clan's founder fills its roster, and only with its own members
+    try {
+      await program.methods
+        .enlistForWar()
+        .accounts({ clanWar: warPda, clan: clans[1], player: founders[0].publicKey, founder: founders[0].publicKey })
+        .signers([founders[0]])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the clan's founder can do this");
+    }
+    try {
+      await program.methods
+        .enlistForWar()
+        .accounts({ clanWar: warPda, clan: clans[1], player: founders[0].publicKey, founder: founders[1].publicKey })
+        .signers([founders[1]])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Not a member of this clan");
+    }
+    await program.methods
+      .enlistForWar()
+      .accounts({ clanWar: warPda, clan: clans[0], player: founders[0].publicKey, founder: founders[0].publicKey })
+      .signers([founders[0]])
+      .rpc();
+    war = await program.account.clanWar.fetch(warPda);
+    expect(war.rosterA.map((member) => member.toString())).to.deep.equal([founders[0].publicKey.toString()]);
+    expect(war.rosterB).to.be.empty;
+  });
+
+  it("Allows one emote per player per move", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    for (const player of [host, guest]) {
+      await program.methods
+        .sendEmote(3)
+        .accounts({ game: gamePda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+    }
+
+    try {
+      await program.methods
+        .sendEmote(4)
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("One emote per move");
+    }
+  });
+
+  it("Keeps chat to the players unless the game opens it to spectators", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, stranger] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, stranger]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const gamePdas = [0, 1].map((id) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0]
+    );
+    for (const [id, variants] of [[0, 0], [1, VARIANT_SPECTATOR_CHAT]]) {
+      await program.methods
+        .initializeGame(new anchor.BN(id), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({ game: gamePdas[id], player: host.publicKey })
+        .signers([host])
+        .rpc();
+    }
+
+    await program.methods
+      .sendMessage("good luck, have fun")
+      .accounts({ game: gamePdas[0], sender: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    try {
+      await program.methods
+        .sendMessage("hello from the stands")
+        .accounts({ game: gamePdas[0], sender: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("You are not a player in this game");
+    }
+
+    await program.methods
+      .sendMessage("hello from the stands")
+      .accounts({ game: gamePdas[1], sender: stranger.publicKey })
+      .signers([stranger])
+      .rpc();
+  });
+
+  it("Counts each registered spectator once", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, fan] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, fan]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [markerPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("spectator"), gamePda.toBuffer(), fan.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .registerSpectator()
+      .accounts({ game: gamePda, spectator: fan.publicKey })
+      .signers([fan])
+      .rpc();
+    expect((await program.account.game.fetch(gamePda)).spectators).to.equal(1);
+
+    try {
+      await program.methods
+        .registerSpectator()
+        .accounts({ game: gamePda, spectator: fan.publicKey })
+        .signers([fan])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("already in use");
+    }
+
+    await program.methods
+      .unregisterSpectator()
+      .accounts({ marker: markerPda, game: gamePda, spectator: fan.publicKey })
+      .signers([fan])
+      .rpc();
+    expect((await program.account.game.fetch(gamePda)).spectators).to.equal(0);
+    expect(await connection.getAccountInfo(markerPda)).to.be.null;
+  });
+
+  it("Records each player's board sealed for the designated observers once", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, caster] = [Keypair.generate(), Keypair.generate()];
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(host.publicKey, 2000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [observersPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("observers"), gamePda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .designateObservers([caster.publicKey])
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const sealed = crypto.randomBytes(32);
+    await program.methods
+      .sealBoardForObservers([Array.from(sealed)])
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+    const record = await program.account.gameObservers.fetch(observersPda);
+    expect(Buffer.from(record.sealed1[0]).equals(sealed)).to.be.true;
+
+    try {
+      await program.methods
+        .sealBoardForObservers([Array.from(crypto.randomBytes(32))])
+        .accounts({ game: gamePda, player: host.publicKey })
+        .signers([host])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Board already sealed for the observers");
+    }
+  });
+
+  it("Lets the creator hand the joiner a different fleet", async () => {
+    const connection = anchor.getProvider().connection;
+    const host = Keypair.generate();
+    const stranger = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(host.publicKey, 2000000000)
+    );
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(stranger.publicKey, 1000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    try {
+      await program.methods
+        .setHandicapFleet(Buffer.from([5, 4]))
+        .accounts({ game: gamePda, player: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Not player1");
+    }
+
+    await program.methods
+      .setHandicapFleet(Buffer.from([5, 4, 3, 3, 2, 2]))
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.fleet2Len).to.equal(6);
+    expect(Array.from(gameAccount.fleet2).slice(0, gameAccount.fleet2Len)).to.deep.equal([5, 4, 3, 3, 2, 2]);
+  });
+
+  it("Rejects a revealed board whose ships touch under the no-touch rule", async () => {
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_NO_TOUCH }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    // Player1's ships are stacked on consecutive rows, so each one touches the next
+    try {
+      await program.methods
+        .revealBoardPlayer1(Buffer.from(player1Board), Array.from(player1Salt))
+        .accounts({ game: gamePda, player: player1.publicKey })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Ships may not touch");
+    }
+  });
+
+  it("Accepts ships along the third axis of a hex grid", async () => {
+    try {
+      await program.methods
+        .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_HEX | VARIANT_RADAR }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({ game: gamePda, player: player1.publicKey })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("These variants can't be combined");
+    }
+
+    // The carrier runs from (4, 0) down-left to (0, 4) in axial coordinates
+    const hexBoard = new Array(100).fill(0);
+    [[4, 13, 22, 31, 40], [60, 61, 62, 63], [70, 71, 72], [80, 81, 82], [90, 91]]
+      .forEach((ship, i) => ship.forEach(pos => hexBoard[pos] = i + 1));
+    const hexSalt = crypto.randomBytes(32);
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(computeCommitment(hexBoard, hexSalt, gamePda, player1.publicKey)), new anchor.BN(0), PublicKey.default, { ...CLASSIC_RULES, variants: VARIANT_HEX }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .revealBoardPlayer1(Buffer.from(hexBoard), Array.from(hexSalt))
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.player1Revealed).to.equal(1);
+  });
+
+  it("Reveals the signer's own board without naming their seat", async () => {
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    const stranger = Keypair.generate();
+    try {
+      await program.methods
+        .revealBoard(Buffer.from(player2Board), Array.from(player2Salt))
+        .accounts({ game: gamePda, player: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("You are not a player in this game");
+    }
+
+    await program.methods
+      .revealBoard(Buffer.from(player2Board), Array.from(player2Salt))
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.player2Revealed).to.equal(1);
+    expect(gameAccount.bond2Settled).to.equal(1);
+  });
+
+  it("Stores the revealed fleet as a ship mask", async () => {
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    await program.methods
+      .revealBoard(Buffer.from(player1Board), Array.from(player1Salt))
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    // One bit per cell, cell i at bit i % 64 of word i / 64
+    const board1 = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, 1));
+    for (let i = 0; i < 100; i++) {
+      expect(board1.shipMask[i >> 6].testn(i & 63)).to.equal(player1Board[i] !== 0);
+    }
+  });
+
+  it("Resolves queued shots in a batch with one event per shot", async () => {
+    const connection = anchor.getProvider().connection;
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    await program.methods
+      .fireShot(5, 0)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    const shotReveal = (index: number) => ({
+      x: index % 10,
+      y: Math.floor(index / 10),
+      shipId: player2Board[index],
+      cellSalt: Array.from(cellSalt(player2Salt, index)),
+      proof: merkleProof(player2Board, player2Salt, index),
+    });
+
+    // A lone pending shot takes exactly its own result
+    try {
+      await program.methods
+        .resolveShotsBatch([shotReveal(5), shotReveal(6)])
+        .accounts({ game: gamePda, player: player2.publicKey })
+        .signers([player2])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("A single pending shot takes exactly one result");
+    }
+
+    const signature = await program.methods
+      .resolveShotsBatch([shotReveal(5)])
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc({ commitment: "confirmed" });
+
+    const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const events = tx.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .filter((ix) => tx.transaction.message.staticAccountKeys[ix.programIdIndex].equals(program.programId))
+      .map((ix) => {
+        // Skip the 8-byte event-CPI tag; the rest is the usual discriminator + Borsh payload
+        const payload = anchor.utils.bytes.bs58.decode(ix.data).subarray(8);
+        return program.coder.events.decode(anchor.utils.bytes.base64.encode(Buffer.from(payload)));
+      })
+      .filter((event) => event?.name === "ShotResolved");
+
+    expect(events.length).to.equal(1);
+    expect(events[0].data.shooter.toString()).to.equal(player1.publicKey.toString());
+    expect(events[0].data.x).to.equal(5);
+    expect(events[0].data.result).to.equal(2); // 2 = hit
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.hasPendingShot).to.equal(0);
+    expect(gameAccount.turn).to.equal(2);
+
+    // This game keeps no move log, yet the summary still reports the last shot
+    const summary = await program.methods.getGameSummary().accounts({ game: gamePda }).view();
+    expect(summary.lastShotX).to.equal(5);
+    expect(summary.lastShotBy).to.equal(1);
+    expect(summary.lastShotResult).to.equal(2);
+  });
+
+  it("Plays a correspondence game with days per move and a MoveAwaited event", async () => {
+    const connection = anchor.getProvider().connection;
+    const correspondence = { ...CLASSIC_RULES, variants: 3 << VARIANT_CORRESPONDENCE_SHIFT }; // 3 days per move
+
+    // Long deadlines replace the chess clock rather than run alongside it
+    try {
+      await program.methods
+        .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, { ...correspondence, clockSlots: 3000 }, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+        .accounts({ game: gamePda, player: player1.publicKey })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("These variants can't be combined");
+    }
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(player1Commitment), new anchor.BN(0), PublicKey.default, correspondence, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(player2Commitment), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    const signature = await program.methods
+      .fireShot(5, 0)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc({ commitment: "confirmed" });
+
+    const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const eventIx = tx.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .find((ix) => tx.transaction.message.staticAccountKeys[ix.programIdIndex].equals(program.programId));
+    // Skip the 8-byte event-CPI tag; the rest is the usual discriminator + Borsh payload
+    const payload = anchor.utils.bytes.bs58.decode(eventIx.data).subarray(8);
+    const event = program.coder.events.decode(anchor.utils.bytes.base64.encode(Buffer.from(payload)));
+
+    // The defender owes the answer, and has three days to give it
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(event.name).to.equal("MoveAwaited");
+    expect(event.data.player.toString()).to.equal(player2.publicKey.toString());
+    expect(event.data.playerNum).to.equal(2);
+    expect(event.data.deadlineSlot.toNumber()).to.equal(gameAccount.lastActionSlot.toNumber() + 3 * SLOTS_PER_DAY);
+
+    try {
+      await program.methods
+        .claimTimeoutVictory()
+        .accounts({ game: gamePda, player: player1.publicKey })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Opponent has not timed out yet");
+    }
+  });
+
+  it("Relays a move signed off-chain by the player", async () => {
+    const connection = anchor.getProvider().connection;
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const hostCommitment = crypto.randomBytes(32);
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(hostCommitment), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    // game || signer's commitment || action (1 = fire) || x || y || nonce
+    const message = Buffer.concat([
+      gamePda.toBuffer(),
+      hostCommitment,
+      Buffer.from([1, 4, 7]),
+      new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+    ]);
+    const signatureIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: host.secretKey,
+      message,
+    });
+
+    // The provider wallet relays and pays; the host never signs the transaction
+    await program.methods
+      .relayedFireShot(4, 7, new anchor.BN(0))
+      .accounts({ game: gamePda })
+      .preInstructions([signatureIx])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.pendingShot).to.deep.equal([4, 7]);
+    expect(gameAccount.pendingShotBy.toString()).to.equal(host.publicKey.toString());
+    expect(gameAccount.relayNonce1.toNumber()).to.equal(1);
+
+    try {
+      // Replaying the same signed move is rejected
+      await program.methods
+        .relayedFireShot(4, 7, new anchor.BN(0))
+        .accounts({ game: gamePda })
+        .preInstructions([signatureIx])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Relayed move nonce already used or out of order");
+    }
+  });
+}); 
+describe("battleship wagers", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+  const connection = anchor.getProvider().connection;
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+
+  it("Escrows both players' stakes in the vault", async () => {
+    const player1 = Keypair.generate();
+    const player2 = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(player1.publicKey, 2000000000)
+    );
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(player2.publicKey, 2000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), player1.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), gamePda.toBuffer()],
+      program.programId
+    );
+
+    const wager = new anchor.BN(100000000); // 0.1 SOL
+    const commitment = crypto.randomBytes(32);
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(commitment), wager, PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({
+        game: gamePda,
+        vault: vaultPda,
+        player: player1.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(commitment), NO_COIN_FLIP)
+      .accounts({
+        game: gamePda,
+        vault: vaultPda,
+        player: player2.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([player2])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.wager.toString()).to.equal(wager.toString());
+    expect(await connection.getBalance(vaultPda)).to.equal((wager.toNumber() + HONESTY_BOND) * 2);
+  });
+
+  it("Records a referrer on a new player's first game", async () => {
+    const [referrer, newcomer] = [Keypair.generate(), Keypair.generate()];
+    for (const player of [referrer, newcomer]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(player.publicKey, 2000000000)
+      );
+      await program.methods
+        .initializeProfile()
+        .accounts({ player: player.publicKey })
+        .signers([player])
+        .rpc();
+    }
+    const profilePda = (player: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("profile"), player.toBuffer()],
+      program.programId
+    )[0];
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), newcomer.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({
+        game: gamePda,
+        player: newcomer.publicKey,
+        playerProfile: profilePda(newcomer.publicKey),
+        referrerProfile: profilePda(referrer.publicKey),
+      })
+      .signers([newcomer])
+      .rpc();
+
+    const profile = await program.account.playerProfile.fetch(profilePda(newcomer.publicKey));
+    expect(profile.referrer.toString()).to.equal(referrer.publicKey.toString());
+    // The game keeps the referrer, so the winner can't leave them out of the payout
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.referrer1.toString()).to.equal(referrer.publicKey.toString());
+
+    // Nothing is owed until a referred player's game pays rake
+    try {
+      await program.methods
+        .claimReferralRewards()
+        .accounts({ player: referrer.publicKey })
+        .signers([referrer])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("No referral rewards to claim");
+    }
+  });
+
+  it("Rejects claiming before the game is over", async () => {
+    const player1 = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(player1.publicKey, 2000000000)
+    );
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), player1.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(100000000), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({
+        game: gamePda,
+        player: player1.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([player1])
+      .rpc();
+
+    try {
+      await program.methods
+        .claimWinnings()
+        .accounts({
+          game: gamePda,
+          player: player1.publicKey,
+        })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Game is not over yet");
+    }
+  });
+
+  it("Only the admin can allow a wager mint", async () => {
+    const stranger = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(stranger.publicKey, 2000000000)
+    );
+
+    // Wrapped SOL's mint is always present on a local validator
+    const mint = new PublicKey("So11111111111111111111111111111111111111112");
+    const [allowedMintPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("allowed_mint"), mint.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .allowWagerMint()
+        .accounts({ allowedMint: allowedMintPda, mint, config: configPda, admin: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the admin can do this");
+    }
+  });
+
+  it("Only the admin can withdraw from the treasury", async () => {
+    const stranger = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(stranger.publicKey, 2000000000)
+    );
+
+    try {
+      await program.methods
+        .withdrawTreasury(new anchor.BN(1))
+        .accounts({ config: configPda, admin: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the admin can do this");
+    }
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.feeBps).to.equal(250);
+  });
+
+  it("Only the admin can pause the program", async () => {
+    const stranger = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(stranger.publicKey, 2000000000)
+    );
+
+    try {
+      await program.methods
+        .setPaused(true)
+        .accounts({ config: configPda, admin: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the admin can do this");
+    }
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.isPaused).to.equal(false);
+  });
+
+  it("Only hands over the admin role to the proposed admin", async () => {
+    const [multisig, stranger] = [Keypair.generate(), Keypair.generate()];
+
+    await program.methods
+      .proposeAdminChange(multisig.publicKey)
+      .accounts({ config: configPda })
+      .rpc();
+
+    try {
+      await program.methods
+        .acceptAdminChange()
+        .accounts({ config: configPda, newAdmin: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the proposed admin can accept the admin role");
+    }
+
+    // Withdraw the offer so the rest of the suite keeps the provider wallet as admin
+    await program.methods
+      .proposeAdminChange(PublicKey.default)
+      .accounts({ config: configPda })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    expect(config.admin.toBase58()).to.equal(anchor.getProvider().publicKey.toBase58());
+    expect(config.pendingAdmin.toBase58()).to.equal(PublicKey.default.toBase58());
+  });
+
+  it("Only the admin can create the game archive tree", async () => {
+    const stranger = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(stranger.publicKey, 2000000000)
+    );
+
+    try {
+      await program.methods
+        .createArchiveTree(14, 64)
+        .accounts({
+          config: configPda,
+          merkleTree: Keypair.generate().publicKey,
+          admin: stranger.publicKey,
+          compressionProgram: new PublicKey("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK"),
+          noopProgram: new PublicKey("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV"),
+        })
+        .signers([stranger])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the admin can do this");
+    }
+
+    // Until a tree exists, games close without archiving
+    const config = await program.account.config.fetch(configPda);
+    expect(config.archiveTree.equals(PublicKey.default)).to.be.true;
+  });
+
+  it("Takes spectator bets from outsiders only", async () => {
+    const [host, guest, spectator] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest, spectator]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet_pool"), gamePda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    await program.methods
+      .openBettingPool()
+      .accounts({ game: gamePda, opener: spectator.publicKey })
+      .signers([spectator])
+      .rpc();
+
+    await program.methods
+      .placeSpectatorBet(2, new anchor.BN(50000000))
+      .accounts({ game: gamePda, bettor: spectator.publicKey })
+      .signers([spectator])
+      .rpc();
+
+    try {
+      await program.methods
+        .placeSpectatorBet(1, new anchor.BN(50000000))
+        .accounts({ game: gamePda, bettor: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Players can't bet on their own game");
+    }
+
+    const pool = await program.account.bettingPool.fetch(poolPda);
+    expect(pool.totalOnPlayer2.toNumber()).to.equal(50000000);
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.spectatorBets).to.equal(1);
+  });
+
+  it("Holds spectator bets until the game has a final result or is aborted", async () => {
+    const [host, guest, spectator] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest, spectator]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [betPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bet"), gamePda.toBuffer(), spectator.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    await program.methods
+      .openBettingPool()
+      .accounts({ game: gamePda, opener: spectator.publicKey })
+      .signers([spectator])
+      .rpc();
+
+    await program.methods
+      .placeSpectatorBet(1, new anchor.BN(50000000))
+      .accounts({ game: gamePda, bettor: spectator.publicKey })
+      .signers([spectator])
+      .rpc();
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    // Until the boards are revealed the result could still flip on a cheat
+    try {
+      await program.methods
+        .settleSpectatorBets()
+        .accounts({ game: gamePda, opener: spectator.publicKey })
+        .remainingAccounts([
+          { pubkey: betPda, isWritable: true, isSigner: false },
+          { pubkey: spectator.publicKey, isWritable: true, isSigner: false },
+        ])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Both boards must be revealed first");
+    }
+
+    // If neither player reveals by the deadline the crank aborts the game, and the same
+    // settle_spectator_bets call then refunds every stake instead of locking it in the pool
+    try {
+      await program.methods
+        .crankRevealForfeit()
+        .accounts({ game: gamePda, keeper: spectator.publicKey })
+        .signers([spectator])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Reveal deadline has not passed yet");
+    }
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.state).to.equal(GameState.awaitingReveal);
+    expect(gameAccount.spectatorBets).to.equal(1);
+  });
+
+  it("Rejects wager limits whose minimum is above the maximum", async () => {
+    try {
+      await program.methods
+        .setWagerLimits(PublicKey.default, new anchor.BN(2000000000), new anchor.BN(1000000000))
+        .accounts({ config: configPda })
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Minimum wager can't be above the maximum");
+    }
+  });
+});
+
+describe("battleship game endings", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+  const connection = anchor.getProvider().connection;
+
+  async function startGame(player1: Keypair, player2: Keypair): Promise<PublicKey> {
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), player1.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    return gamePda;
+  }
+
+  it("Resigning hands the win to the opponent", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.state).to.equal(GameState.awaitingReveal);
+    expect(gameAccount.winner).to.equal(2);
+    expect(gameAccount.endReason).to.equal(3);
+  });
+
+  it("Prevents claiming a timeout before the deadline", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    try {
+      // Player1 is on move, so only player2 could claim - and not yet
+      await program.methods
+        .claimTimeoutVictory()
+        .accounts({ game: gamePda, player: player2.publicKey })
+        .signers([player2])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Opponent has not timed out yet");
+    }
+  });
+
+  it("Player1 can cancel an unjoined game and reclaim rent", async () => {
+    const player1 = await fundedKeypair();
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), player1.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .cancelGame()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    expect(await connection.getAccountInfo(gamePda)).to.be.null;
+    expect(await connection.getAccountInfo(playerBoardPda(program.programId, gamePda, 1))).to.be.null;
+  });
+
+  it("Keeps each player's board in its own account", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    for (const seat of [1, 2]) {
+      const board = await program.account.playerBoard.fetch(playerBoardPda(program.programId, gamePda, seat));
+      expect(board.game.toString()).to.equal(gamePda.toString());
+      expect(board.seat).to.equal(seat);
+      expect(board.hits.every((cells) => cells === 0)).to.be.true;
+    }
+  });
+
+
+  it("Only a player who revealed can claim a reveal forfeit", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    try {
+      await program.methods
+        .claimRevealForfeit()
+        .accounts({ game: gamePda, player: player2.publicKey })
+        .signers([player2])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Both boards must be revealed first");
+    }
+  });
+
+
+  it("Holds a bond from each player until the boards are revealed", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    const [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), gamePda.toBuffer()],
+      program.programId
+    );
+    expect(await connection.getBalance(vaultPda)).to.equal(HONESTY_BOND * 2);
+
+    await program.methods
+      .resign()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    try {
+      await program.methods
+        .claimForfeitedBond()
+        .accounts({ game: gamePda, player: player2.publicKey })
+        .signers([player2])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Both boards must be revealed first");
+    }
+  });
+
+
+  it("Ends the game as a draw once both players agree", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    await program.methods
+      .offerDraw()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    try {
+      await program.methods
+        .acceptDraw()
+        .accounts({ game: gamePda, player: player1.publicKey })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("You cannot accept your own draw offer");
+    }
+
+    await program.methods
+      .acceptDraw()
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.state).to.equal(GameState.awaitingReveal);
+    expect(gameAccount.winner).to.equal(0);
+    expect(gameAccount.endReason).to.equal(5);
+  });
+
+  it("Voids the game once the other player confirms the abort", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    await program.methods
+      .proposeAbort()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    try {
+      await program.methods
+        .confirmAbort()
+        .accounts({ game: gamePda, player1: player1.publicKey, player2: player2.publicKey, player: player1.publicKey })
+        .signers([player1])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("You cannot confirm your own abort proposal");
+    }
+
+    // A move lets the proposal lapse, so it can't be confirmed later once the game has turned
+    await program.methods
+      .fireShot(0, 0)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    try {
+      await program.methods
+        .confirmAbort()
+        .accounts({ game: gamePda, player1: player1.publicKey, player2: player2.publicKey, player: player2.publicKey })
+        .signers([player2])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("No abort has been proposed");
+    }
+
+    await program.methods
+      .proposeAbort()
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .confirmAbort()
+      .accounts({ game: gamePda, player1: player1.publicKey, player2: player2.publicKey, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    expect(await program.account.game.fetchNullable(gamePda)).to.be.null;
+  });
+
+  it("Only the agreed arbiter can rule, and only once the game has gone quiet", async () => {
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const [arbiter, stranger] = [Keypair.generate(), Keypair.generate()];
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), player1.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, arbiter.publicKey)
+      .accounts({ game: gamePda, player: player1.publicKey })
+      .signers([player1])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: player2.publicKey })
+      .signers([player2])
+      .rpc();
+
+    for (const [signer, message] of [
+      [stranger, "Only the game's arbiter can rule on it"],
+      [arbiter, "The game hasn't gone quiet for the dispute window yet"],
+    ] as [Keypair, string][]) {
+      try {
+        await program.methods
+          .arbitrateGame(1)
+          .accounts({ game: gamePda, player1: player1.publicKey, player2: player2.publicKey, arbiter: signer.publicKey })
+          .signers([signer])
+          .rpc();
+
+        expect.fail("Should have thrown error");
+      } catch (error) {
+        expect(error.message).to.include(message);
+      }
+    }
+  });
+
+  it("Only flags a player once their chess clock runs out", async () => {
+    const [host, guest] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const blitz = { ...CLASSIC_RULES, clockSlots: 3000 };
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, blitz, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    try {
+      await program.methods
+        .flagTimeout()
+        .accounts({ game: gamePda, keeper: anchor.getProvider().publicKey })
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("The player to move still has time on their clock");
+    }
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.clock1.toNumber()).to.equal(3000);
+    expect(gameAccount.clock2.toNumber()).to.equal(3000);
+  });
+
+  it("Stamps new games with the configured timeout strike limit", async () => {
+    await program.methods
+      .setTimeoutStrikeLimit(3)
+      .accounts({ config: configPda })
+      .rpc();
+
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.strikeLimit).to.equal(3);
+    expect(gameAccount.strikes1).to.equal(0);
+    expect(gameAccount.strikes2).to.equal(0);
+
+    await program.methods
+      .setTimeoutStrikeLimit(0)
+      .accounts({ config: configPda })
+      .rpc();
+  });
+
+  it("Caps the share of the pot refunded to a player who abandons mid-game", async () => {
+    try {
+      await program.methods
+        .setAbandonRefund(6000)
+        .accounts({ config: configPda })
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Abandon refund can be at most 50% of the pot");
+    }
+  });
+
+  it("Stamps new games with the configured abandon refund", async () => {
+    await program.methods
+      .setAbandonRefund(2500)
+      .accounts({ config: configPda })
+      .rpc();
+
+    const player1 = await fundedKeypair();
+    const player2 = await fundedKeypair();
+    const gamePda = await startGame(player1, player2);
+
+    // A later config change doesn't reach games already under way
+    await program.methods
+      .setAbandonRefund(0)
+      .accounts({ config: configPda })
+      .rpc();
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.abandonRefundBps).to.equal(2500);
+  });
+
+  it("Keepers can't crank a game before it times out", async () => {
+    const [host, guest, keeper] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const keypair of [host, guest, keeper]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, player: guest.publicKey })
+      .signers([guest])
+      .rpc();
+
+    try {
+      await program.methods
+        .crankTimeout()
+        .accounts({ game: gamePda, keeper: keeper.publicKey })
+        .signers([keeper])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Opponent has not timed out yet");
+    }
+
+    const gameAccount = await program.account.game.fetch(gamePda);
+    expect(gameAccount.state).to.equal(GameState.inProgress);
+    expect(gameAccount.bondDocked1.toNumber()).to.equal(0);
+  });
+});
+
+describe("battleship lobby", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+  const connection = anchor.getProvider().connection;
+
+  const [lobbyPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("lobby")],
+    program.programId
+  );
+
+  before(async () => {
+    if (!(await connection.getAccountInfo(lobbyPda))) {
+      await program.methods
+        .initializeLobby()
+        .accounts({ lobby: lobbyPda })
+        .rpc();
+    }
+  });
+
+  it("Lists a public game and removes it once joined", async () => {
+    const host = Keypair.generate();
+    const joiner = Keypair.generate();
+    for (const keypair of [host, joiner]) {
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(keypair.publicKey, 2000000000)
+      );
+    }
+
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), host.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeGame(new anchor.BN(0), Array.from(crypto.randomBytes(32)), new anchor.BN(0), PublicKey.default, CLASSIC_RULES, STANDARD_FLEET, NO_COIN_FLIP, false, PublicKey.default)
+      .accounts({ game: gamePda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    await program.methods
+      .listGame()
+      .accounts({ game: gamePda, lobby: lobbyPda, player: host.publicKey })
+      .signers([host])
+      .rpc();
+
+    let lobby = await program.account.lobby.fetch(lobbyPda);
+    const entry = lobby.entries.find((e) => e.game.equals(gamePda));
+    expect(entry.creator.toString()).to.equal(host.publicKey.toString());
+    expect(entry.boardSize).to.equal(10);
+
+    await program.methods
+      .joinGame(Array.from(crypto.randomBytes(32)), NO_COIN_FLIP)
+      .accounts({ game: gamePda, lobby: lobbyPda, player: joiner.publicKey })
+      .signers([joiner])
+      .rpc();
+
+    lobby = await program.account.lobby.fetch(lobbyPda);
+    expect(lobby.entries.some((e) => e.game.equals(gamePda))).to.be.false;
+  });
+});
+
+describe("battleship tournaments", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+  const connection = anchor.getProvider().connection;
+
+  it("Starts the bracket once every seat is filled", async () => {
+    const organizer = await fundedKeypair();
+    const entryFee = new anchor.BN(10000000);
+    const [tournamentPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tournament"), organizer.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tournament_vault"), tournamentPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createTournament(new anchor.BN(0), entryFee, 4, 0, 0)
+      .accounts({ tournament: tournamentPda, organizer: organizer.publicKey })
+      .signers([organizer])
+      .rpc();
+
+    for (let i = 0; i < 4; i++) {
+      const player = await fundedKeypair();
+      await program.methods
+        .register()
+        .accounts({ tournament: tournamentPda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+    }
+
+    const tournament = await program.account.tournament.fetch(tournamentPda);
+    expect(tournament.state).to.equal(1);
+    expect(tournament.round).to.equal(1);
+    expect(tournament.bracket.length).to.equal(4);
+    expect(tournament.roundWinners.length).to.equal(2);
+    expect(await connection.getBalance(vaultPda)).to.equal(entryFee.toNumber() * 4);
+  });
+
+
+  it("Seats a Swiss tournament by standings", async () => {
+    const organizer = await fundedKeypair();
+    const [tournamentPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tournament"), organizer.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .createTournament(new anchor.BN(0), new anchor.BN(0), 4, 1, 4)
+        .accounts({ tournament: tournamentPda, organizer: organizer.publicKey })
+        .signers([organizer])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Format must be 0");
+    }
+
+    await program.methods
+      .createTournament(new anchor.BN(0), new anchor.BN(0), 4, 1, 3)
+      .accounts({ tournament: tournamentPda, organizer: organizer.publicKey })
+      .signers([organizer])
+      .rpc();
+
+    const players: Keypair[] = [];
+    for (let i = 0; i < 4; i++) {
+      const player = await fundedKeypair();
+      players.push(player);
+      await program.methods
+        .register()
+        .accounts({ tournament: tournamentPda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+    }
+
+    const tournament = await program.account.tournament.fetch(tournamentPda);
+    expect(tournament.format).to.equal(1);
+    expect(tournament.scores).to.deep.equal([0, 0, 0, 0]);
+    // Level on points, so round 1 pairs in registration order
+    expect(tournament.bracket[0].equals(players[0].publicKey)).to.be.true;
+    expect(tournament.bracket[1].equals(players[1].publicKey)).to.be.true;
+  });
+});
+
+describe("battleship free-for-all", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+  const connection = anchor.getProvider().connection;
+
+  // Every seat uses the same layout with its own salt
+  const board = new Array(100).fill(0);
+  [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]]
+    .forEach((ship, i) => ship.forEach(pos => board[pos] = i + 1));
+
+  it("Seats three players and lets the shooter pick a target", async () => {
+    const players = [await fundedKeypair(), await fundedKeypair(), await fundedKeypair()];
+    const salts = players.map(() => crypto.randomBytes(32));
+    const [ffaPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ffa_game"), players[0].publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeFfaGame(new anchor.BN(0), 3, false, new anchor.BN(0), Array.from(computeCommitment(board, salts[0], ffaPda, players[0].publicKey)))
+      .accounts({ game: ffaPda, player: players[0].publicKey })
+      .signers([players[0]])
+      .rpc();
+    for (let i = 1; i < 3; i++) {
+      await program.methods
+        .joinFfaGame(Array.from(computeCommitment(board, salts[i], ffaPda, players[i].publicKey)))
+        .accounts({ game: ffaPda, player: players[i].publicKey })
+        .signers([players[i]])
+        .rpc();
+    }
+
+    let game = await program.account.ffaGame.fetch(ffaPda);
+    expect(game.state).to.equal(GameState.inProgress);
+    expect(game.playerCount).to.equal(3);
+    expect(game.turn).to.equal(0);
+
+    // The shooter can't target their own fleet
+    try {
+      await program.methods
+        .fireFfaShot(0, 0, 0)
+        .accounts({ game: ffaPda, player: players[0].publicKey })
+        .signers([players[0]])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidTarget");
+    }
+
+    await program.methods
+      .fireFfaShot(2, 0, 0)
+      .accounts({ game: ffaPda, player: players[0].publicKey })
+      .signers([players[0]])
+      .rpc();
+    await program.methods
+      .revealFfaShotResult(board[0], Array.from(cellSalt(salts[2], 0)), merkleProof(board, salts[2], 0))
+      .accounts({ game: ffaPda, player: players[2].publicKey })
+      .signers([players[2]])
+      .rpc();
+
+    game = await program.account.ffaGame.fetch(ffaPda);
+    expect(hitCell(game.boardHits[2], 0)).to.equal(2); // 2 = hit
+    expect(game.hitsCount).to.deep.equal([0, 0, 1, 0]);
+    expect(game.turn).to.equal(1); // Round-robin to the next seat
+  });
+
+  it("Pools team stakes and only lets a pair fire at the other pair", async () => {
+    const players = [];
+    for (let i = 0; i < 4; i++) {
+      players.push(await fundedKeypair());
+    }
+    const wager = new anchor.BN(100000000);
+    const [teamPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ffa_game"), players[0].publicKey.toBuffer(), new anchor.BN(1).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), teamPda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeFfaGame(new anchor.BN(1), 4, true, wager, Array.from(computeCommitment(board, crypto.randomBytes(32), teamPda, players[0].publicKey)))
+      .accounts({ game: teamPda, player: players[0].publicKey })
+      .signers([players[0]])
+      .rpc();
+    for (let i = 1; i < 4; i++) {
+      await program.methods
+        .joinFfaGame(Array.from(computeCommitment(board, crypto.randomBytes(32), teamPda, players[i].publicKey)))
+        .accounts({ game: teamPda, player: players[i].publicKey })
+        .signers([players[i]])
+        .rpc();
+    }
+
+    const game = await program.account.ffaGame.fetch(teamPda);
+    expect(game.teams).to.equal(1);
+    expect(game.state).to.equal(GameState.inProgress);
+    expect(await connection.getBalance(vaultPda)).to.equal(wager.toNumber() * 4);
+
+    // Seat 2 is seat 0's teammate
+    try {
+      await program.methods
+        .fireFfaShot(2, 0, 0)
+        .accounts({ game: teamPda, player: players[0].publicKey })
+        .signers([players[0]])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidTarget");
+    }
+
+    await program.methods
+      .fireFfaShot(3, 0, 0)
+      .accounts({ game: teamPda, player: players[0].publicKey })
+      .signers([players[0]])
+      .rpc();
+  });
+
+  it("Pays a free-for-all only once the winner's board checks out, and flags a crooked one", async () => {
+    const players = [await fundedKeypair(), await fundedKeypair(), await fundedKeypair()];
+    const salts = players.map(() => crypto.randomBytes(32));
+    const wager = new anchor.BN(100000000);
+    // Seat 2 hides its carrier on every other cell, which no legal fleet allows
+    const crookedBoard = board.slice();
+    [1, 3].forEach(pos => crookedBoard[pos] = 0);
+    [6, 8].forEach(pos => crookedBoard[pos] = 1);
+    const boards = [board, board, crookedBoard];
+    const [ffaPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("ffa_game"), players[0].publicKey.toBuffer(), new anchor.BN(2).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .initializeFfaGame(new anchor.BN(2), 3, false, wager, Array.from(computeCommitment(boards[0], salts[0], ffaPda, players[0].publicKey)))
+      .accounts({ game: ffaPda, player: players[0].publicKey })
+      .signers([players[0]])
+      .rpc();
+    for (let i = 1; i < 3; i++) {
+      await program.methods
+        .joinFfaGame(Array.from(computeCommitment(boards[i], salts[i], ffaPda, players[i].publicKey)))
+        .accounts({ game: ffaPda, player: players[i].publicKey })
+        .signers([players[i]])
+        .rpc();
+    }
+
+    const fire = async (shooter: number, target: number, cell: number) => {
+      await program.methods
+        .fireFfaShot(target, cell % 10, Math.floor(cell / 10))
+        .accounts({ game: ffaPda, player: players[shooter].publicKey })
+        .signers([players[shooter]])
+        .rpc();
+      await program.methods
+        .revealFfaShotResult(boards[target][cell], Array.from(cellSalt(salts[target], cell)), merkleProof(boards[target], salts[target], cell))
+        .accounts({ game: ffaPda, player: players[target].publicKey })
+        .signers([players[target]])
+        .rpc();
+    };
+
+    // Seat 0 sinks seat 1 and then seat 2, while the others only find water on seat 0's board
+    const shipCells = (target: number) => boards[target].flatMap((cell, i) => cell !== 0 ? [i] : []);
+    const water = board.flatMap((cell, i) => cell === 0 ? [i] : []);
+    const seat1Cells = shipCells(1);
+    for (const [i, cell] of seat1Cells.entries()) {
+      await fire(0, 1, cell);
+      if (i < seat1Cells.length - 1) {
+        await fire(1, 0, water.shift());
+      }
+      await fire(2, 0, water.shift());
+    }
+    const seat2Cells = shipCells(2);
+    for (const [i, cell] of seat2Cells.entries()) {
+      await fire(0, 2, cell);
+      if (i < seat2Cells.length - 1) {
+        await fire(2, 0, water.shift());
+      }
+    }
+
+    let game = await program.account.ffaGame.fetch(ffaPda);
+    expect(game.state).to.equal(GameState.finished);
+    expect(game.winner).to.equal(1);
+
+    // The pot waits for the winner's board
+    try {
+      await program.methods
+        .claimFfaWinnings()
+        .accounts({ game: ffaPda, player: players[0].publicKey, teammate: null })
+        .signers([players[0]])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("BoardNotRevealed");
+    }
+
+    // Seat 2's reveal matches its commitment but not a legal fleet
+    await program.methods
+      .revealFfaBoard(Buffer.from(crookedBoard), Array.from(salts[2]))
+      .accounts({ game: ffaPda, player: players[2].publicKey })
+      .signers([players[2]])
+      .rpc();
+    await program.methods
+      .revealFfaBoard(Buffer.from(board), Array.from(salts[0]))
+      .accounts({ game: ffaPda, player: players[0].publicKey })
+      .signers([players[0]])
+      .rpc();
+
+    game = await program.account.ffaGame.fetch(ffaPda);
+    expect(game.revealed).to.deep.equal([1, 0, 2, 0]);
+    expect(game.state).to.equal(GameState.finished);
+
+    const before = await connection.getBalance(players[0].publicKey);
+    await program.methods
+      .claimFfaWinnings()
+      .accounts({ game: ffaPda, player: players[0].publicKey, teammate: null })
+      .signers([players[0]])
+      .rpc();
+    expect(await connection.getBalance(players[0].publicKey)).to.be.greaterThan(before + 2 * wager.toNumber());
+  });
+});
+
+describe("battleship matchmaking", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+  const connection = anchor.getProvider().connection;
+
+  const board = new Array(100).fill(0);
+  [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]]
+    .forEach((ship, i) => ship.forEach(pos => board[pos] = i + 1));
+
+  function ticketPda(player: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("queue_ticket"), player.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // A fresh player with a profile (rating 1200), waiting in the queue
+  async function queuedPlayer(wager: anchor.BN, ratingBand: number): Promise<Keypair> {
+    const player = await fundedKeypair();
+    await program.methods
+      .initializeProfile()
+      .accounts({ player: player.publicKey })
+      .signers([player])
+      .rpc();
+    await program.methods
+      .enterQueue(wager, ratingBand)
+      .accounts({ ticket: ticketPda(player.publicKey), player: player.publicKey })
+      .signers([player])
+      .rpc();
+    return player;
+  }
+
+  it("Only the admin can set the matchmaking rating delta", async () => {
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const stranger = await fundedKeypair();
+
+    try {
+      await program.methods
+        .setMaxRatingDelta(1)
+        .accounts({ config: configPda, admin: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Only the admin can do this");
+    }
+
+    await program.methods
+      .setMaxRatingDelta(200)
+      .accounts({ config: configPda })
+      .rpc();
+    const config = await program.account.config.fetch(configPda);
+    expect(config.maxRatingDelta).to.equal(200);
+  });
+
+  it("Pairs two queued players into a game with both stakes escrowed", async () => {
+    const wager = new anchor.BN(100000000);
+    const [alice, bob] = [await queuedPlayer(wager, 1), await queuedPlayer(wager, 1)];
+
+    const gameId = new anchor.BN(7);
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), alice.publicKey.toBuffer(), gameId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), gamePda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .matchPlayers(gameId)
+      .accounts({
+        ticket1: ticketPda(alice.publicKey),
+        ticket2: ticketPda(bob.publicKey),
+        player1: alice.publicKey,
+        player2: bob.publicKey,
+        game: gamePda,
+        vault: vaultPda,
+      })
+      .rpc();
+
+    expect(await connection.getAccountInfo(ticketPda(alice.publicKey))).to.be.null;
+    const vaultBalance = await connection.getBalance(vaultPda);
+    expect(vaultBalance).to.equal(2 * (100000000 + 10000000));
+
+    let game = await program.account.game.fetch(gamePda);
+    expect(game.state).to.equal(GameState.waitingForOpponent);
+    expect(game.player2.toString()).to.equal(bob.publicKey.toString());
+
+    // Nobody else can take the matched seat
+    const stranger = await fundedKeypair();
+    try {
+      await program.methods
+        .joinGame(Array.from(computeCommitment(board, crypto.randomBytes(32), gamePda, stranger.publicKey)), NO_COIN_FLIP)
+        .accounts({ game: gamePda, player: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Game is already full");
+    }
+
+    // Only a player whose board is in can call the match off
+    try {
+      await program.methods
+        .cancelMatch()
+        .accounts({ game: gamePda, player1: alice.publicKey, player2: bob.publicKey, player: alice.publicKey })
+        .signers([alice])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Commit your board (and prove your fleet) before cancelling");
+    }
+
+    for (const player of [alice, bob]) {
+      await program.methods
+        .commitMatchedBoard(Array.from(computeCommitment(board, crypto.randomBytes(32), gamePda, player.publicKey)))
+        .accounts({ game: gamePda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+    }
+
+    game = await program.account.game.fetch(gamePda);
+    expect(game.state).to.equal(GameState.inProgress);
+    // Who moves first is left to VRF, not to which ticket the keeper listed first
+    expect(game.turnPending).to.equal(1);
+    expect(game.rules.vrfFirstTurn).to.equal(1);
+  });
+
+  it("Emits the pairing through a self-CPI that indexers can decode from instruction data", async () => {
+    const wager = new anchor.BN(100000000);
+    const [alice, bob] = [await queuedPlayer(wager, 2), await queuedPlayer(wager, 2)];
+
+    const gameId = new anchor.BN(8);
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), alice.publicKey.toBuffer(), gameId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const signature = await program.methods
+      .matchPlayers(gameId)
+      .accounts({
+        ticket1: ticketPda(alice.publicKey),
+        ticket2: ticketPda(bob.publicKey),
+        player1: alice.publicKey,
+        player2: bob.publicKey,
+        game: gamePda,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const tx = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const eventIx = tx.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .find((ix) => tx.transaction.message.staticAccountKeys[ix.programIdIndex].equals(program.programId));
+    // Skip the 8-byte event-CPI tag; the rest is the usual discriminator + Borsh payload
+    const payload = anchor.utils.bytes.bs58.decode(eventIx.data).subarray(8);
+    const event = program.coder.events.decode(anchor.utils.bytes.base64.encode(Buffer.from(payload)));
+
+    expect(event.name).to.equal("PlayersMatched");
+    expect(event.data.game.toString()).to.equal(gamePda.toString());
+    expect(event.data.player2.toString()).to.equal(bob.publicKey.toString());
+  });
+
+  it("Only pairs tickets with the same wager and band", async () => {
+    const [alice, bob] = [await queuedPlayer(new anchor.BN(0), 1), await queuedPlayer(new anchor.BN(0), 2)];
+
+    const gameId = new anchor.BN(8);
+    const [gamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), alice.publicKey.toBuffer(), gameId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    try {
+      await program.methods
+        .matchPlayers(gameId)
+        .accounts({
+          ticket1: ticketPda(alice.publicKey),
+          ticket2: ticketPda(bob.publicKey),
+          player1: alice.publicKey,
+          player2: bob.publicKey,
+          game: gamePda,
+        })
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Queued players want different wagers or rating bands");
+    }
+
+    // Leaving the queue hands the escrow back
+    await program.methods
+      .leaveQueue()
+      .accounts({ ticket: ticketPda(bob.publicKey), player: bob.publicKey })
+      .signers([bob])
+      .rpc();
+    expect(await connection.getAccountInfo(ticketPda(bob.publicKey))).to.be.null;
+  });
+});
+
+describe("battleship house games", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+
+  it("Only deals the house board from Switchboard randomness", async () => {
+    const player = Keypair.generate();
+    await anchor.getProvider().connection.confirmTransaction(
+      await anchor.getProvider().connection.requestAirdrop(player.publicKey, 1000000000)
+    );
+
+    const gameId = new anchor.BN(1);
+    const [houseGamePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("house_game"), player.publicKey.toBuffer(), gameId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    try {
+      // A system-owned account can't stand in for a randomness account
+      await program.methods
+        .startHouseGame(gameId, Array.from(crypto.randomBytes(32)))
+        .accounts({ houseGame: houseGamePda, randomnessAccount: player.publicKey, player: player.publicKey })
+        .signers([player])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("An owner constraint was violated");
+    }
+  });
+});
+
+describe("battleship puzzles", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+
+  it("Rejects a par no fleet could be sunk in", async () => {
+    const creator = Keypair.generate();
+    await anchor.getProvider().connection.confirmTransaction(
+      await anchor.getProvider().connection.requestAirdrop(creator.publicKey, 1000000000)
+    );
+
+    const puzzleId = new anchor.BN(1);
+    const [puzzlePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("puzzle"), creator.publicKey.toBuffer(), puzzleId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .publishPuzzle(puzzleId, Array.from(crypto.randomBytes(32)), 16)
+        .accounts({ puzzle: puzzlePda, creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Par must be between 17 and 100 shots");
+    }
+  });
+
+  it("Scores a solve answered with Merkle proofs, then retires the puzzle on reveal", async () => {
+    const connection = anchor.getProvider().connection;
+    const [creator, player] = [Keypair.generate(), Keypair.generate()];
+    for (const keypair of [creator, player]) {
+      await connection.confirmTransaction(await connection.requestAirdrop(keypair.publicKey, 1000000000));
+    }
+    await program.methods
+      .initializeProfile()
+      .accounts({ player: player.publicKey })
+      .signers([player])
+      .rpc();
+
+    const board = new Array(100).fill(0);
+    [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]]
+      .forEach((ship, i) => ship.forEach(pos => board[pos] = i + 1));
+    const salt = crypto.randomBytes(32);
+    const puzzleId = new anchor.BN(2);
+    const [puzzlePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("puzzle"), creator.publicKey.toBuffer(), puzzleId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [attemptPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("puzzle_attempt"), puzzlePda.toBuffer(), player.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .publishPuzzle(puzzleId, Array.from(computeCommitment(board, salt, puzzlePda, creator.publicKey)), 20)
+      .accounts({ puzzle: puzzlePda, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+
+    // The creator knows the answers, so they can't score on their own puzzle
+    const [creatorAttemptPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("puzzle_attempt"), puzzlePda.toBuffer(), creator.publicKey.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .startPuzzleAttempt()
+        .accounts({ puzzle: puzzlePda, attempt: creatorAttemptPda, player: creator.publicKey })
+        .signers([creator])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Cannot play against yourself");
+    }
+    await program.methods
+      .startPuzzleAttempt()
+      .accounts({ puzzle: puzzlePda, attempt: attemptPda, player: player.publicKey })
+      .signers([player])
+      .rpc();
+
+    const fire = async (cell: number) => {
+      await program.methods
+        .fireAtPuzzle(cell % 10, Math.floor(cell / 10))
+        .accounts({ attempt: attemptPda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+    };
+    const [profilePda] = PublicKey.findProgramAddressSync([Buffer.from("profile"), player.publicKey.toBuffer()], program.programId);
+    const answer = async (cell: number, shipId: number) => {
+      await program.methods
+        .answerPuzzleShot(shipId, Array.from(cellSalt(salt, cell)), merkleProof(board, salt, cell))
+        .accounts({ puzzle: puzzlePda, attempt: attemptPda, playerProfile: profilePda, creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+    };
+
+    // A shot only forfeits once the creator has sat on it past the turn timeout
+    await fire(99);
+    try {
+      await program.methods
+        .claimPuzzleForfeit()
+        .accounts({ puzzle: puzzlePda, attempt: attemptPda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Opponent has not timed out yet");
+    }
+
+    // The creator can't pass a hit off as a miss
+    await answer(99, 0);
+    await fire(0);
+    try {
+      await answer(0, 0);
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Merkle proof does not match the committed board");
+    }
+    await answer(0, board[0]);
+    for (const cell of board.flatMap((ship, i) => ship !== 0 && i !== 0 ? [i] : [])) {
+      await fire(cell);
+      await answer(cell, board[cell]);
+    }
+
+    const attempt = await program.account.puzzleAttempt.fetch(attemptPda);
+    expect(attempt.state).to.equal(GameState.finished);
+    expect(attempt.shots).to.equal(18);
+    const profile = await program.account.playerProfile.fetch(profilePda);
+    expect(profile.puzzlesSolved).to.equal(1);
+    expect(profile.puzzleScore).to.equal(3); // One for the solve, two for the shots under par
+
+    // A board that doesn't match the commitment can't retire the puzzle; the real one can
+    try {
+      await program.methods
+        .revealPuzzleBoard(Buffer.from(board.map((cell, i) => i === 99 ? 5 : cell)), Array.from(salt))
+        .accounts({ puzzle: puzzlePda, creator: creator.publicKey })
+        .signers([creator])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Commitment hash does not match revealed data");
+    }
+    await program.methods
+      .revealPuzzleBoard(Buffer.from(board), Array.from(salt))
+      .accounts({ puzzle: puzzlePda, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    const puzzle = await program.account.puzzle.fetch(puzzlePda);
+    expect(puzzle.revealed).to.equal(1);
+    expect(puzzle.solves).to.equal(1);
+  });
+});
+
+describe("battleship daily challenges", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.Battleship as Program<Battleship>;
+
+  it("Won't open a challenge for a day that has passed", async () => {
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const yesterday = Math.floor(Date.now() / 1000 / 86400) - 1;
+    const dayBytes = Buffer.alloc(4);
+    dayBytes.writeUInt32LE(yesterday);
+    const [challengePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("challenge"), dayBytes],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .openChallenge(yesterday, Array.from(crypto.randomBytes(32)))
+        .accounts({ challenge: challengePda, config: configPda })
+        .rpc();
+
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("That day's challenge is already over");
+    }
+  });
+
+  it("Ranks a player who sinks the day's fleet, with every answer proven", async () => {
+    const connection = anchor.getProvider().connection;
+    const host = anchor.getProvider().publicKey;
+    const player = Keypair.generate();
+    await connection.confirmTransaction(await connection.requestAirdrop(player.publicKey, 1000000000));
+
+    const board = new Array(100).fill(0);
+    [[0, 1, 2, 3, 4], [10, 11, 12, 13], [20, 21, 22], [30, 31, 32], [40, 41]]
+      .forEach((ship, i) => ship.forEach(pos => board[pos] = i + 1));
+    const salt = crypto.randomBytes(32);
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    // The day comes from the cluster's clock, which is what the program checks against
+    const today = Math.floor((await connection.getBlockTime(await connection.getSlot())) / 86400);
+    const dayBytes = Buffer.alloc(4);
+    dayBytes.writeUInt32LE(today);
+    const [challengePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("challenge"), dayBytes],
+      program.programId
+    );
+    const [entryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("challenge_entry"), challengePda.toBuffer(), player.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .openChallenge(today, Array.from(computeCommitment(board, salt, challengePda, host)))
+      .accounts({ challenge: challengePda, config: configPda })
+      .rpc();
+    await program.methods
+      .joinChallenge()
+      .accounts({ challenge: challengePda, entry: entryPda, player: player.publicKey })
+      .signers([player])
+      .rpc();
+
+    const shoot = async (cell: number, shipId: number) => {
+      await program.methods
+        .fireAtChallenge(cell % 10, Math.floor(cell / 10))
+        .accounts({ challenge: challengePda, entry: entryPda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+      await program.methods
+        .answerChallengeShot(shipId, Array.from(cellSalt(salt, cell)), merkleProof(board, salt, cell))
+        .accounts({ challenge: challengePda, entry: entryPda, host })
+        .rpc();
+    };
+
+    await shoot(99, 0);
+    // The host can't claim a hit on open water
+    try {
+      await shoot(98, 5);
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("Merkle proof does not match the committed board");
+    }
+    await program.methods
+      .answerChallengeShot(0, Array.from(cellSalt(salt, 98)), merkleProof(board, salt, 98))
+      .accounts({ challenge: challengePda, entry: entryPda, host })
+      .rpc();
+    for (const cell of board.flatMap((ship, i) => ship !== 0 ? [i] : [])) {
+      await shoot(cell, board[cell]);
+    }
+
+    const entry = await program.account.challengeEntry.fetch(entryPda);
+    expect(entry.state).to.equal(GameState.finished);
+    expect(entry.shots).to.equal(19);
+    const challenge = await program.account.challenge.fetch(challengePda);
+    expect(challenge.players).to.equal(1);
+    expect(challenge.leaderboard.length).to.equal(1);
+    expect(challenge.leaderboard[0].player.toString()).to.equal(player.publicKey.toString());
+    expect(challenge.leaderboard[0].shots).to.equal(19);
+
+    // The board stays hidden until the day is over
+    try {
+      await program.methods
+        .revealChallengeBoard(Buffer.from(board), Array.from(salt))
+        .accounts({ challenge: challengePda, host })
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("ChallengeNotOver");
+    }
+    // ...and so does the entry
+    try {
+      await program.methods
+        .closeChallengeEntry()
+        .accounts({ challenge: challengePda, entry: entryPda, player: player.publicKey })
+        .signers([player])
+        .rpc();
+      expect.fail("Should have thrown error");
+    } catch (error) {
+      expect(error.message).to.include("ChallengeNotOver");
+    }
+  });
+});